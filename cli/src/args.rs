@@ -1,4 +1,4 @@
-use clap::{ArgAction, Parser, Subcommand};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
@@ -12,10 +12,23 @@ pub struct CliArguments {
     /// -v = warning & error, -vv = info, -vvv = debug, -vvvv = trace
     #[clap(short, long, action = ArgAction::Count)]
     pub verbosity: u8,
-    /// Path to read SVG file from.
+    /// Path to read SVG file from. Use `-` to read from stdin.
     pub input: Option<PathBuf>,
-    /// Path to write PDF file to.
+    /// Path to write PDF file to. Use `-` to write to stdout, which is also
+    /// the default when reading from stdin. With `--multipage`, this is
+    /// another SVG file.
     pub output: Option<PathBuf>,
+    /// More SVG files to read when using `--multipage`.
+    #[clap(requires = "multipage")]
+    pub more_inputs: Vec<PathBuf>,
+    /// Whether to combine all given SVG files into a single PDF with one page
+    /// per file, in the order they were given.
+    #[clap(long, requires = "input")]
+    pub multipage: bool,
+    /// Path to write the combined PDF file to when using `--multipage`. Use
+    /// `-` to write to stdout, which is also the default.
+    #[clap(long = "output", short = 'o', requires = "multipage")]
+    pub multipage_output: Option<PathBuf>,
     /// The number of SVG pixels per PDF points.
     #[clap(long, default_value = "72.0")]
     pub dpi: f32,
@@ -26,6 +39,105 @@ pub struct CliArguments {
     /// How much raster images of rasterized effects should be scaled up.
     #[clap(long, default_value = "1.5")]
     pub raster_scale: f32,
+    /// The width that should be assumed for SVGs that have a relative
+    /// width (e.g. `width="100%"`) and no viewBox.
+    #[clap(long, default_value = "100.0")]
+    pub default_width: f32,
+    /// The height that should be assumed for SVGs that have a relative
+    /// height (e.g. `height="100%"`) and no viewBox.
+    #[clap(long, default_value = "100.0")]
+    pub default_height: f32,
+    /// The size of the page, either a paper format (A3, A4, A5, Letter or
+    /// Legal) or a width and height like `100mmx50mm`. By default, the page
+    /// has the size of the SVG.
+    #[clap(long, value_parser = parse_page_size)]
+    pub page_size: Option<usvg::Size>,
+    /// How the SVG is fitted into the page if a page size is given.
+    #[clap(long, value_enum, default_value_t = Fit::Contain)]
+    pub fit: Fit,
+    /// The margin around the SVG, e.g. `10mm`. Lengths without a unit are
+    /// in points.
+    #[clap(long, value_parser = parse_length, default_value = "0")]
+    pub margin: f32,
+    #[command(flatten)]
+    pub fonts: FontArgs,
+}
+
+/// Arguments for the fonts that are available during the conversion.
+#[derive(Debug, Clone, Args)]
+pub struct FontArgs {
+    /// Adds a directory with additional fonts. Can be repeated.
+    #[arg(long = "font-dir")]
+    pub font_dirs: Vec<PathBuf>,
+    /// The font family that is used for text without a font family and
+    /// for text whose font families are not available. Note that this
+    /// also replaces the generic `serif` family.
+    #[arg(long)]
+    pub fallback_font: Option<String>,
+}
+
+/// How the SVG is fitted into the page.
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum Fit {
+    /// Scale the SVG so that it fits completely into the page.
+    Contain,
+    /// Scale the SVG so that it covers the whole page.
+    Cover,
+    /// Stretch the SVG so that it exactly fills the page.
+    Fill,
+    /// Don't scale the SVG.
+    None,
+}
+
+impl From<Fit> for svg2pdf::Fit {
+    fn from(fit: Fit) -> Self {
+        match fit {
+            Fit::Contain => Self::Contain,
+            Fit::Cover => Self::Cover,
+            Fit::Fill => Self::Fill,
+            Fit::None => Self::None,
+        }
+    }
+}
+
+/// Parse a paper format or a size like `100mmx50mm` into a size in points.
+fn parse_page_size(value: &str) -> Result<usvg::Size, String> {
+    let (width, height) = match value.to_ascii_lowercase().as_str() {
+        "a3" => (841.89, 1190.55),
+        "a4" => (595.28, 841.89),
+        "a5" => (419.53, 595.28),
+        "letter" => (612.0, 792.0),
+        "legal" => (612.0, 1008.0),
+        size => {
+            let (width, height) = size
+                .split_once('x')
+                .ok_or("expected a paper format or a size like `100mmx50mm`")?;
+            (parse_length(width)?, parse_length(height)?)
+        }
+    };
+
+    usvg::Size::from_wh(width, height).ok_or("the page size must be positive".into())
+}
+
+/// Parse a length with an optional unit (pt, mm, cm or in) into points.
+fn parse_length(value: &str) -> Result<f32, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f32 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid length `{value}`"))?;
+
+    let factor = match unit {
+        "" | "pt" => 1.0,
+        "mm" => 72.0 / 25.4,
+        "cm" => 72.0 / 2.54,
+        "in" => 72.0,
+        _ => return Err(format!("unknown unit `{unit}`, expected pt, mm, cm or in")),
+    };
+
+    Ok(number * factor)
 }
 
 // What to do.
@@ -34,6 +146,8 @@ pub struct CliArguments {
 pub enum Command {
     /// Lists all discovered fonts in system
     Fonts(FontsCommand),
+    /// Converts many SVG files at once
+    Batch(BatchCommand),
 }
 
 /// Lists all discovered fonts in system.
@@ -43,3 +157,15 @@ pub struct FontsCommand {
     #[arg(long)]
     pub all: bool,
 }
+
+/// Converts many SVG files at once.
+#[derive(Debug, Clone, Parser)]
+pub struct BatchCommand {
+    /// Paths to read the SVG files from.
+    #[arg(required = true)]
+    pub inputs: Vec<PathBuf>,
+    /// The directory to write the PDF files to. Each PDF is named after its
+    /// SVG file. Defaults to the current directory.
+    #[arg(long, short = 'd')]
+    pub output_dir: Option<PathBuf>,
+}
@@ -1,52 +1,241 @@
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 #[cfg(feature = "text")]
 use std::sync::Arc;
-use svg2pdf::{ConversionOptions, PageOptions};
+use svg2pdf::{ConversionOptions, PageOptions, PreprocessOptions, ResourceResolver};
 
+use crate::args::{BatchCommand, FontArgs};
+
+#[cfg_attr(not(feature = "text"), allow(unused_variables))]
 pub fn convert_(
     input: &PathBuf,
     output: Option<PathBuf>,
     conversion_options: ConversionOptions,
     page_options: PageOptions,
+    default_size: usvg::Size,
+    fonts: &FontArgs,
 ) -> Result<(), String> {
     if let Ok(()) = log::set_logger(&LOGGER) {
         log::set_max_level(log::LevelFilter::Warn);
     }
 
-    let mut fontdb = fontdb::Database::new();
-    fontdb.load_system_fonts();
+    let svg = if is_stdio(input) {
+        let mut svg = String::new();
+        io::stdin()
+            .read_to_string(&mut svg)
+            .map_err(|_| "Failed to read SVG from stdin")?;
+        svg
+    } else {
+        std::fs::read_to_string(input).map_err(|_| "Failed to load SVG file")?
+    };
 
-    fontdb.set_serif_family("Times New Roman");
-    fontdb.set_sans_serif_family("Arial");
-    fontdb.set_cursive_family("Comic Sans MS");
-    fontdb.set_fantasy_family("Impact");
-    fontdb.set_monospace_family("Courier New");
+    #[cfg(feature = "text")]
+    let mut fontdb = system_fontdb(fonts);
+
+    #[cfg(feature = "text")]
+    svg2pdf::load_font_faces(&svg, &mut fontdb);
 
     #[cfg(feature = "text")]
-    let options = usvg::Options {
+    let mut options = usvg::Options {
         fontdb: Arc::new(fontdb),
+        default_size,
         ..usvg::Options::default()
     };
 
-    #[cfg(not(feature = "text"))]
-    let options = usvg::Options::default();
+    #[cfg(feature = "text")]
+    set_fallback_font(&mut options, fonts);
 
-    // Convert the file.
-    let name = Path::new(input.file_name().ok_or("Input path does not point to a file")?);
-    let output = output.unwrap_or_else(|| name.with_extension("pdf"));
+    #[cfg(not(feature = "text"))]
+    let options = usvg::Options { default_size, ..usvg::Options::default() };
 
-    let svg = std::fs::read_to_string(input).map_err(|_| "Failed to load SVG file")?;
+    // Convert the file. When reading from stdin, the PDF is written to stdout
+    // by default.
+    let output = match output {
+        Some(output) => output,
+        None if is_stdio(input) => PathBuf::from("-"),
+        None => {
+            let name = input.file_name().ok_or("Input path does not point to a file")?;
+            Path::new(name).with_extension("pdf")
+        }
+    };
 
-    let tree = usvg::Tree::from_str(&svg, &options).map_err(|err| err.to_string())?;
+    let preprocessed = svg2pdf::preprocess(&svg, &PreprocessOptions::default())
+        .map_err(|e| format!("Failed to convert PDF file: {e}"))?;
+    let tree = usvg::Tree::from_str(&preprocessed.svg, &options)
+        .map_err(|err| err.to_string())?;
 
     let pdf = svg2pdf::to_pdf(&tree, conversion_options, page_options)
         .map_err(|e| format!("Failed to convert PDF file: {e}"))?;
 
-    std::fs::write(output, pdf).map_err(|_| "Failed to write PDF file")?;
+    write_output(&output, &pdf)
+}
+
+/// Write a PDF to a file or to stdout.
+fn write_output(output: &Path, pdf: &[u8]) -> Result<(), String> {
+    if is_stdio(output) {
+        // Logging goes to stderr, so it can't corrupt the PDF.
+        let mut stdout = io::stdout().lock();
+        stdout
+            .write_all(pdf)
+            .and_then(|_| stdout.flush())
+            .map_err(|_| "Failed to write PDF to stdout")?;
+    } else {
+        std::fs::write(output, pdf).map_err(|_| "Failed to write PDF file")?;
+    }
 
     Ok(())
 }
 
+/// Whether a path refers to stdin or stdout.
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+#[cfg_attr(not(feature = "text"), allow(unused_variables))]
+pub fn convert_many(
+    command: &BatchCommand,
+    conversion_options: ConversionOptions,
+    page_options: PageOptions,
+    default_size: usvg::Size,
+    fonts: &FontArgs,
+) -> Result<(), String> {
+    if let Ok(()) = log::set_logger(&LOGGER) {
+        log::set_max_level(log::LevelFilter::Warn);
+    }
+
+    // The system fonts are only loaded once for all files.
+    #[cfg(feature = "text")]
+    let mut options = usvg::Options {
+        fontdb: Arc::new(system_fontdb(fonts)),
+        default_size,
+        ..usvg::Options::default()
+    };
+
+    #[cfg(feature = "text")]
+    set_fallback_font(&mut options, fonts);
+
+    #[cfg(not(feature = "text"))]
+    let options = usvg::Options { default_size, ..usvg::Options::default() };
+
+    // Files that are referenced by `use` elements are read relative to the
+    // working directory, like the inputs.
+    let preprocess_options = PreprocessOptions {
+        resource_resolver: Some(ResourceResolver::from_dir(".")),
+        ..Default::default()
+    };
+
+    let output_dir = command.output_dir.clone().unwrap_or_default();
+    let results = svg2pdf::convert_many(
+        &command.inputs,
+        &options,
+        &preprocess_options,
+        &conversion_options,
+        page_options,
+    );
+
+    let mut failed = 0;
+    for (input, result) in command.inputs.iter().zip(results) {
+        let written = result.map_err(|e| e.to_string()).and_then(|pdf| {
+            let name = input.file_name().ok_or("Input path does not point to a file")?;
+            let output = output_dir.join(Path::new(name).with_extension("pdf"));
+            std::fs::write(output, pdf).map_err(|_| "Failed to write PDF file".into())
+        });
+
+        if let Err(e) = written {
+            eprintln!("Failed to convert {}: {e}", input.display());
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!(
+            "failed to convert {failed} of {} files",
+            command.inputs.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Convert many SVG files into a single PDF with one page per file. The PDF is
+/// written to stdout if no output path is given.
+#[cfg_attr(not(feature = "text"), allow(unused_variables))]
+pub fn convert_multipage(
+    inputs: &[PathBuf],
+    output: Option<PathBuf>,
+    conversion_options: ConversionOptions,
+    page_options: PageOptions,
+    default_size: usvg::Size,
+    fonts: &FontArgs,
+) -> Result<(), String> {
+    if let Ok(()) = log::set_logger(&LOGGER) {
+        log::set_max_level(log::LevelFilter::Warn);
+    }
+
+    // The system fonts are only loaded once for all files.
+    #[cfg(feature = "text")]
+    let mut options = usvg::Options {
+        fontdb: Arc::new(system_fontdb(fonts)),
+        default_size,
+        ..usvg::Options::default()
+    };
+
+    #[cfg(feature = "text")]
+    set_fallback_font(&mut options, fonts);
+
+    #[cfg(not(feature = "text"))]
+    let options = usvg::Options { default_size, ..usvg::Options::default() };
+
+    let trees = inputs
+        .iter()
+        .map(|input| {
+            let svg = std::fs::read(input)
+                .map_err(|_| format!("Failed to load SVG file {}", input.display()))?;
+            usvg::Tree::from_data(&svg, &options)
+                .map_err(|err| format!("{}: {err}", input.display()))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let pdf = svg2pdf::to_multipage_pdf(&trees, conversion_options, page_options)
+        .map_err(|e| format!("Failed to convert PDF file: {e}"))?;
+
+    write_output(&output.unwrap_or_else(|| PathBuf::from("-")), &pdf)
+}
+
+/// Create a font database with the system fonts, the fonts from the font
+/// directories and common generic families.
+#[cfg(feature = "text")]
+fn system_fontdb(fonts: &FontArgs) -> fontdb::Database {
+    let mut fontdb = fontdb::Database::new();
+    fontdb.load_system_fonts();
+
+    for dir in &fonts.font_dirs {
+        fontdb.load_fonts_dir(dir);
+    }
+
+    fontdb.set_serif_family("Times New Roman");
+    fontdb.set_sans_serif_family("Arial");
+    fontdb.set_cursive_family("Comic Sans MS");
+    fontdb.set_fantasy_family("Impact");
+    fontdb.set_monospace_family("Courier New");
+
+    // usvg uses the serif family when none of the font families of some text
+    // are available.
+    if let Some(family) = &fonts.fallback_font {
+        fontdb.set_serif_family(family);
+    }
+
+    fontdb
+}
+
+/// Use the fallback font for text without a font family.
+#[cfg(feature = "text")]
+fn set_fallback_font(options: &mut usvg::Options, fonts: &FontArgs) {
+    if let Some(family) = &fonts.fallback_font {
+        options.font_family = family.clone();
+    }
+}
+
 // Taken from resvg
 /// A simple stderr logger.
 static LOGGER: SimpleLogger = SimpleLogger;
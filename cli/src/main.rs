@@ -21,23 +21,64 @@ fn main() {
 fn run() -> Result<(), String> {
     let args = CliArguments::parse();
 
-    // If an input argument was provided, convert the svg file to pdf.
-    if let Some(input) = args.input {
-        let conversion_options = ConversionOptions {
-            compress: true,
-            embed_text: !args.text_to_paths,
-            raster_scale: args.raster_scale,
-        };
+    let conversion_options = ConversionOptions {
+        compress: true,
+        embed_text: !args.text_to_paths,
+        raster_scale: args.raster_scale,
+        ..ConversionOptions::default()
+    };
+
+    let page_options = PageOptions {
+        dpi: args.dpi,
+        page_size: args.page_size,
+        fit: args.fit.into(),
+        margin: args.margin,
+    };
 
-        let page_options = PageOptions { dpi: args.dpi };
+    let default_size = usvg::Size::from_wh(args.default_width, args.default_height)
+        .ok_or("the default size must be positive")?;
 
-        return convert::convert_(&input, args.output, conversion_options, page_options);
+    // With `--multipage`, all positional arguments are SVG files.
+    if args.multipage {
+        let inputs: Vec<_> = args
+            .input
+            .into_iter()
+            .chain(args.output)
+            .chain(args.more_inputs)
+            .collect();
+        return convert::convert_multipage(
+            &inputs,
+            args.multipage_output,
+            conversion_options,
+            page_options,
+            default_size,
+            &args.fonts,
+        );
+    }
+
+    // If an input argument was provided, convert the svg file to pdf.
+    if let Some(input) = args.input {
+        return convert::convert_(
+            &input,
+            args.output,
+            conversion_options,
+            page_options,
+            default_size,
+            &args.fonts,
+        );
     };
 
     // Otherwise execute the command provided if any.
     if let Some(command) = args.command {
         match command {
             Command::Fonts(command) => crate::fonts::fonts(&command)?,
+            Command::Batch(command) => crate::convert::convert_many(
+                &command,
+                conversion_options,
+                page_options,
+                default_size,
+                &args.fonts,
+            )?,
         }
     } else {
         return Err("no command was provided".to_string());
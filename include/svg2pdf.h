@@ -0,0 +1,41 @@
+#ifndef SVG2PDF_H
+#define SVG2PDF_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SVG2PDF_OK 0
+#define SVG2PDF_ERROR_INVALID_ARGUMENT 1
+#define SVG2PDF_ERROR_INVALID_SVG 2
+#define SVG2PDF_ERROR_INVALID_IMAGE 3
+#define SVG2PDF_ERROR_UNKNOWN 4
+#define SVG2PDF_ERROR_SUBSET 5
+#define SVG2PDF_ERROR_INVALID_FONT 6
+#define SVG2PDF_ERROR_LIMIT_EXCEEDED 7
+#define SVG2PDF_ERROR_MISSING_FEATURE 8
+#define SVG2PDF_ERROR_PANIC 9
+
+typedef struct svg2pdf_options {
+    bool compress;
+    bool embed_text;
+    float raster_scale;
+    float dpi;
+} svg2pdf_options;
+
+void svg2pdf_default_options(svg2pdf_options *options);
+
+int32_t svg2pdf_convert(const uint8_t *svg, size_t len, const svg2pdf_options *options,
+                        uint8_t **out, size_t *out_len);
+
+void svg2pdf_free(uint8_t *ptr, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
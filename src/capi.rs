@@ -0,0 +1,182 @@
+/*! A C API for svg2pdf.
+
+The functions in this module are exported with C linkage, so that svg2pdf can be
+used from other languages. To get a library you can link against, build the
+crate with the `capi` feature and the crate type of your choice, e.g.
+
+```sh
+cargo rustc --release --features capi --crate-type cdylib
+```
+
+The corresponding declarations can be found in `include/svg2pdf.h`.
+
+Note that no system fonts are loaded, so text is only converted if it uses
+fonts that are embedded into the SVG via `@font-face` rules with `data:` URLs.
+*/
+
+use std::panic;
+use std::ptr;
+use std::slice;
+
+use crate::{ConversionError, ConversionOptions, PageOptions, PreprocessOptions};
+
+/// The conversion succeeded.
+pub const SVG2PDF_OK: i32 = 0;
+/// A pointer that was passed in was null or the SVG wasn't valid UTF-8.
+pub const SVG2PDF_ERROR_INVALID_ARGUMENT: i32 = 1;
+/// The SVG couldn't be parsed.
+pub const SVG2PDF_ERROR_INVALID_SVG: i32 = 2;
+/// See [`ConversionError::InvalidImage`].
+pub const SVG2PDF_ERROR_INVALID_IMAGE: i32 = 3;
+/// See [`ConversionError::UnknownError`].
+pub const SVG2PDF_ERROR_UNKNOWN: i32 = 4;
+/// See [`ConversionError::SubsetError`].
+pub const SVG2PDF_ERROR_SUBSET: i32 = 5;
+/// See [`ConversionError::InvalidFont`].
+pub const SVG2PDF_ERROR_INVALID_FONT: i32 = 6;
+/// See [`ConversionError::LimitExceeded`].
+pub const SVG2PDF_ERROR_LIMIT_EXCEEDED: i32 = 7;
+/// See [`ConversionError::MissingFeature`].
+pub const SVG2PDF_ERROR_MISSING_FEATURE: i32 = 8;
+/// The conversion panicked. Panics must not unwind into the caller, so they
+/// are caught and reported with this code instead.
+pub const SVG2PDF_ERROR_PANIC: i32 = 9;
+
+/// The options for a conversion through the C API.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Svg2pdfOptions {
+    /// See [`ConversionOptions::compress`].
+    pub compress: bool,
+    /// See [`ConversionOptions::embed_text`].
+    pub embed_text: bool,
+    /// See [`ConversionOptions::raster_scale`].
+    pub raster_scale: f32,
+    /// See [`PageOptions::dpi`].
+    pub dpi: f32,
+}
+
+impl Default for Svg2pdfOptions {
+    fn default() -> Self {
+        let conversion_options = ConversionOptions::default();
+        Self {
+            compress: conversion_options.compress,
+            embed_text: conversion_options.embed_text,
+            raster_scale: conversion_options.raster_scale,
+            dpi: PageOptions::default().dpi,
+        }
+    }
+}
+
+/// Write the default options into `options`.
+///
+/// # Safety
+/// `options` must be a valid pointer to an `Svg2pdfOptions` struct.
+#[no_mangle]
+pub unsafe extern "C" fn svg2pdf_default_options(options: *mut Svg2pdfOptions) {
+    if let Some(options) = options.as_mut() {
+        *options = Svg2pdfOptions::default();
+    }
+}
+
+/// Convert an SVG into a standalone PDF.
+///
+/// On success, `SVG2PDF_OK` is returned and `out` and `out_len` are set to a
+/// buffer with the PDF, which must be released with [`svg2pdf_free`]. Otherwise,
+/// one of the error codes is returned and `out` is set to null. If `options` is
+/// null, the default options are used. Panics during the conversion are caught
+/// and reported as `SVG2PDF_ERROR_PANIC`, unless the library is built with
+/// `panic = "abort"`.
+///
+/// # Safety
+/// `svg` must point to `len` readable bytes, `options` must be null or point to
+/// a valid `Svg2pdfOptions` struct and `out` and `out_len` must be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn svg2pdf_convert(
+    svg: *const u8,
+    len: usize,
+    options: *const Svg2pdfOptions,
+    out: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    if svg.is_null() || out.is_null() || out_len.is_null() {
+        return SVG2PDF_ERROR_INVALID_ARGUMENT;
+    }
+
+    *out = ptr::null_mut();
+    *out_len = 0;
+
+    let options = options.as_ref().copied().unwrap_or_default();
+    let Ok(svg) = std::str::from_utf8(slice::from_raw_parts(svg, len)) else {
+        return SVG2PDF_ERROR_INVALID_ARGUMENT;
+    };
+
+    match panic::catch_unwind(|| convert(svg, options)) {
+        Ok(Ok(pdf)) => {
+            let pdf = pdf.into_boxed_slice();
+            *out_len = pdf.len();
+            *out = Box::into_raw(pdf) as *mut u8;
+            SVG2PDF_OK
+        }
+        Ok(Err(code)) => code,
+        Err(_) => SVG2PDF_ERROR_PANIC,
+    }
+}
+
+/// Release a buffer that was returned by [`svg2pdf_convert`].
+///
+/// # Safety
+/// `ptr` and `len` must have been returned by [`svg2pdf_convert`] and the
+/// buffer must not have been released before. Passing null is allowed.
+#[no_mangle]
+pub unsafe extern "C" fn svg2pdf_free(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)));
+    }
+}
+
+/// Parse and convert an SVG, mapping errors to the error codes of the C API.
+fn convert(svg: &str, options: Svg2pdfOptions) -> Result<Vec<u8>, i32> {
+    let conversion_options = ConversionOptions {
+        compress: options.compress,
+        embed_text: options.embed_text,
+        raster_scale: options.raster_scale,
+        ..ConversionOptions::default()
+    };
+
+    #[allow(unused_mut)]
+    let mut usvg_options = usvg::Options::default();
+
+    #[cfg(feature = "text")]
+    crate::load_font_faces(svg, usvg_options.fontdb_mut());
+
+    let preprocessed =
+        crate::preprocess(svg, &PreprocessOptions::default()).map_err(error_code)?;
+    let tree = usvg::Tree::from_str(&preprocessed.svg, &usvg_options)
+        .map_err(|_| SVG2PDF_ERROR_INVALID_SVG)?;
+    let page_options = PageOptions { dpi: options.dpi, ..PageOptions::default() };
+
+    crate::to_pdf(&tree, conversion_options, page_options).map_err(error_code)
+}
+
+/// Map a conversion error to its error code.
+fn error_code(error: ConversionError) -> i32 {
+    match error {
+        ConversionError::InvalidImage => SVG2PDF_ERROR_INVALID_IMAGE,
+        ConversionError::UnknownError => SVG2PDF_ERROR_UNKNOWN,
+        #[cfg(feature = "text")]
+        ConversionError::SubsetError(_) => SVG2PDF_ERROR_SUBSET,
+        #[cfg(feature = "text")]
+        ConversionError::InvalidFont(_) => SVG2PDF_ERROR_INVALID_FONT,
+        ConversionError::LimitExceeded => SVG2PDF_ERROR_LIMIT_EXCEEDED,
+        ConversionError::MissingFeature(_) => SVG2PDF_ERROR_MISSING_FEATURE,
+        ConversionError::InvalidSvg | ConversionError::UnsupportedForeignObject => {
+            SVG2PDF_ERROR_INVALID_SVG
+        }
+        // Files are never read and page ranges never passed through the C API.
+        ConversionError::ReadError | ConversionError::InvalidPageRange => {
+            SVG2PDF_ERROR_UNKNOWN
+        }
+    }
+}
@@ -2,7 +2,8 @@
 
 This crate allows to convert static (i.e. non-interactive) SVG files to
 either standalone PDF files or Form XObjects that can be embedded in another
-PDF file and used just like images.
+PDF file and used just like images. Animations are not evaluated, so animated
+attributes like a `stroke-dashoffset` keep their static value.
 
 The conversion will translate the SVG content to PDF without rasterizing them
 (the only exception being objects with filters on them, but in this case only
@@ -31,6 +32,26 @@ std::fs::write(output, pdf)?;
 # Ok(()) }
 ```
 
+## Fonts
+svg2pdf never loads fonts or accesses the file system by itself, it only uses
+the fonts in the font database of the [`usvg::Options`] the tree was parsed
+with. In environments without file system access (like WebAssembly), fonts can
+be loaded from memory using [`fontdb::Database::load_font_data`](usvg::fontdb::Database::load_font_data).
+
+## Cargo features
+- `text` (default): Embeds text as actual text with subsetted fonts. Without it,
+  text is skipped.
+- `image` (default): Embeds raster images, which requires decoding them with the
+  `image` crate. Without it, converting an SVG that contains raster images fails
+  with [`ConversionError::MissingFeature`]. Nested SVG images are still supported.
+- `filters` (default): Rasterizes groups with filters. Implies `image`. Without it,
+  converting an SVG with filters fails with [`ConversionError::MissingFeature`].
+- `icc` (default): Bundles the sRGB and gray ICC profiles that colors are
+  specified in. Without it, the profiles are not compiled into the binary and
+  colors are always written as `DeviceRGB` and `DeviceGray`, as if
+  [`ConversionOptions::embed_icc`] was disabled.
+- `capi`: Exposes a C API in the `capi` module.
+
 ## Supported features
 In general, a very large part of the SVG specification is supported, including
 but not limited to:
@@ -46,53 +67,180 @@ but not limited to:
 
 ## Unsupported features
 Among the unsupported features are currently:
-- The `spreadMethod` attribute of gradients
 - Raster images are not color managed but use PDF's DeviceRGB color space
+- ICC profiles referenced with `color-profile`, since usvg drops them and the
+  `icc-color()` values of paints while parsing, so the sRGB fallback colors are used
+- Percentages in `letter-spacing` in trees that were not parsed from a source
+  prepared with [`preprocess`], since usvg resolves them against the size of the
+  viewport instead of the font size
+- Variable fonts, which are always embedded with the outlines of their default instance
+- `image-orientation: none`, since usvg doesn't preserve the property, so the EXIF
+  orientation of JPEG images is always applied as with `from-image`
 - A number of features that were added in SVG2, See
     [here](https://github.com/RazrFalcon/resvg/blob/master/docs/svg2-changelog.md) for a more
     comprehensive list.
  */
 
+#[cfg(feature = "capi")]
+pub mod capi;
 mod render;
 mod util;
 
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::path::PathBuf;
 pub use usvg;
 
+pub use crate::util::context::Context;
+#[cfg(feature = "text")]
+pub use crate::util::font_face::{load_font_faces, FontFaces};
+pub use crate::util::preprocess::{preprocess, Preprocessed};
+pub use crate::util::resources::ResourceContainer;
+pub use crate::util::symbols::symbol_sheet;
+
 use crate::ConversionError::UnknownError;
+#[cfg(feature = "icc")]
 use once_cell::sync::Lazy;
-use pdf_writer::{Chunk, Content, Filter, Finish, Pdf, Ref, TextStr};
-use usvg::{Size, Transform, Tree};
+use pdf_writer::{Chunk, Content, Filter, Finish, Name, Pdf, Ref, TextStr};
+use usvg::{Group, Node, NonZeroRect, Rect, Size, Transform, Tree};
 
-use crate::render::{tree_to_stream, tree_to_xobject};
-use crate::util::context::Context;
-use crate::util::helper::{deflate, RectExt, TransformExt};
-use crate::util::resources::ResourceContainer;
+#[cfg(feature = "icc")]
+use crate::util::helper::deflate;
+use crate::util::helper::{clip_to_rect, count_nodes, RectExt, TransformExt};
+use crate::util::linearize::linearize;
+use crate::util::marks::{draw_crop_marks, MARK_MARGIN};
+#[cfg(feature = "text")]
+use crate::util::watermark::watermark_tree;
 
 // The ICC profiles.
-static SRGB_ICC_DEFLATED: Lazy<Vec<u8>> =
-    Lazy::new(|| deflate(include_bytes!("icc/sRGB-v4.icc")));
-static GRAY_ICC_DEFLATED: Lazy<Vec<u8>> =
-    Lazy::new(|| deflate(include_bytes!("icc/sGrey-v4.icc")));
+#[cfg(feature = "icc")]
+static SRGB_ICC: &[u8] = include_bytes!("icc/sRGB-v4.icc");
+#[cfg(feature = "icc")]
+static GRAY_ICC: &[u8] = include_bytes!("icc/sGrey-v4.icc");
+#[cfg(feature = "icc")]
+static SRGB_ICC_DEFLATED: Lazy<Vec<u8>> = Lazy::new(|| deflate(SRGB_ICC));
+#[cfg(feature = "icc")]
+static GRAY_ICC_DEFLATED: Lazy<Vec<u8>> = Lazy::new(|| deflate(GRAY_ICC));
 
 /// Options for the resulting PDF file.
-#[derive(Copy, Clone)]
+///
+/// By default, the size of the page is determined by the size of the [`Tree`].
+/// SVGs with a relative size (e.g. `width="100%"`) and no `viewBox` don't have
+/// an intrinsic size, so `usvg` uses [`usvg::Options::default_size`] when
+/// parsing them. Set that option to control the page size of such SVGs.
+///
+/// The size of the content is the size of the tree scaled by `72 / dpi`. The
+/// transform of the root group, which includes the mapping from the `viewBox`
+/// to the size of the tree, only affects the content of the page. So if you
+/// pre-scale a tree by giving it a larger size than its `viewBox`, the page
+/// grows accordingly, but the scale is not applied a second time on top of the
+/// DPI. If a [`page_size`](Self::page_size) is set, the content is then fitted
+/// into that page according to [`fit`](Self::fit).
+///
+/// Pages that would be wider or higher than 14400 points, the largest size
+/// that PDF viewers need to support, are scaled down along with their content
+/// and a warning is emitted.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct PageOptions {
-    /// The DPI that should be assumed for the conversion to PDF.
+    /// The DPI that should be assumed for the conversion to PDF. One unit of
+    /// the tree's size corresponds to one pixel at this DPI.
     ///
     /// _Default:_ 72.0
     pub dpi: f32,
+
+    /// The size of the page in points. If this is `None`, the page has the
+    /// size of the content plus the margin.
+    ///
+    /// _Default:_ `None`
+    pub page_size: Option<Size>,
+
+    /// How the content is fitted into the page if a
+    /// [`page_size`](Self::page_size) is set. The content is always centered
+    /// within the margins.
+    ///
+    /// _Default:_ [`Fit::Contain`]
+    pub fit: Fit,
+
+    /// The margin around the content in points.
+    ///
+    /// _Default:_ 0.0
+    pub margin: f32,
 }
 
 impl Default for PageOptions {
     fn default() -> Self {
-        Self { dpi: 72.0 }
+        Self {
+            dpi: 72.0,
+            page_size: None,
+            fit: Fit::Contain,
+            margin: 0.0,
+        }
     }
 }
 
-/// A error that can appear during conversion.
+impl PageOptions {
+    /// Create a builder for page options, starting from the defaults.
+    pub fn builder() -> PageOptionsBuilder {
+        PageOptionsBuilder { options: Self::default() }
+    }
+}
+
+/// A builder for [`PageOptions`]. See the fields of [`PageOptions`] for the
+/// meaning of each setting.
 #[derive(Copy, Clone, Debug)]
+pub struct PageOptionsBuilder {
+    options: PageOptions,
+}
+
+impl PageOptionsBuilder {
+    /// Set [`PageOptions::dpi`].
+    pub fn dpi(mut self, dpi: f32) -> Self {
+        self.options.dpi = dpi;
+        self
+    }
+
+    /// Set [`PageOptions::page_size`].
+    pub fn page_size(mut self, page_size: Size) -> Self {
+        self.options.page_size = Some(page_size);
+        self
+    }
+
+    /// Set [`PageOptions::fit`].
+    pub fn fit(mut self, fit: Fit) -> Self {
+        self.options.fit = fit;
+        self
+    }
+
+    /// Set [`PageOptions::margin`].
+    pub fn margin(mut self, margin: f32) -> Self {
+        self.options.margin = margin;
+        self
+    }
+
+    /// Create the page options.
+    pub fn build(self) -> PageOptions {
+        self.options
+    }
+}
+
+/// How content is fitted into a page with a fixed size.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Fit {
+    /// Scale the content uniformly so that it fits completely into the page.
+    #[default]
+    Contain,
+    /// Scale the content uniformly so that it covers the whole page. Content
+    /// that overflows the margins is clipped.
+    Cover,
+    /// Scale the content non-uniformly so that it exactly fills the page.
+    Fill,
+    /// Don't scale the content. Content that overflows the margins is clipped.
+    None,
+}
+
+/// A error that can appear during conversion.
+#[derive(Clone, Debug)]
 pub enum ConversionError {
     /// The SVG image contains an unrecognized type of image.
     InvalidImage,
@@ -101,10 +249,28 @@ pub enum ConversionError {
     UnknownError,
     /// An error occurred while subsetting a font.
     #[cfg(feature = "text")]
-    SubsetError(fontdb::ID),
+    SubsetError(FontError),
     /// An error occurred while reading a font.
     #[cfg(feature = "text")]
-    InvalidFont(fontdb::ID),
+    InvalidFont(FontError),
+    /// The conversion exceeded one of the limits configured in the
+    /// [`ConversionOptions`].
+    LimitExceeded,
+    /// The SVG contains raster images or filters, but the cargo feature that
+    /// is needed to convert them (`image` or `filters`) was disabled.
+    MissingFeature(&'static str),
+    /// An SVG file couldn't be read. Only returned by [`convert_many`].
+    ReadError,
+    /// An SVG file couldn't be parsed. Only returned by [`convert_many`],
+    /// [`Converter::convert_data`] and [`preprocess`].
+    InvalidSvg,
+    /// The SVG contains a `foreignObject` and the
+    /// [`PreprocessOptions::foreign_object`] policy is
+    /// [`ForeignObjectPolicy::Error`]. Only returned by [`preprocess`].
+    UnsupportedForeignObject,
+    /// The page range passed to [`to_multipage_pdf_range`] is empty or out of
+    /// bounds, or no trees were passed to [`to_multipage_pdf`].
+    InvalidPageRange,
 }
 
 impl Display for ConversionError {
@@ -113,10 +279,43 @@ impl Display for ConversionError {
             Self::InvalidImage => f.write_str("An unknown type of image appears in the SVG."),
             Self::UnknownError => f.write_str("An unknown error occurred during the conversion. This could indicate a bug in svg2pdf"),
             #[cfg(feature = "text")]
-            Self::SubsetError(_) => f.write_str("An error occurred while subsetting a font."),
+            Self::SubsetError(err) => write!(f, "An error occurred while subsetting the font {err}."),
             #[cfg(feature = "text")]
-            Self::InvalidFont(_) => f.write_str("An error occurred while reading a font."),
+            Self::InvalidFont(err) => write!(f, "An error occurred while reading the font {err}."),
+            Self::LimitExceeded => f.write_str("The conversion exceeded the configured limits."),
+            Self::MissingFeature(feature) => write!(f, "The SVG can't be converted without the `{feature}` feature."),
+            Self::ReadError => f.write_str("The SVG file couldn't be read."),
+            Self::InvalidSvg => f.write_str("The SVG file couldn't be parsed."),
+            Self::UnsupportedForeignObject => f.write_str("The SVG contains a foreignObject, which is not supported."),
+            Self::InvalidPageRange => f.write_str("The page range is empty or out of bounds."),
+        }
+    }
+}
+
+/// The font that caused a [`ConversionError::SubsetError`] or
+/// [`ConversionError::InvalidFont`].
+#[cfg(feature = "text")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FontError {
+    /// The ID of the font in the font database.
+    pub id: fontdb::ID,
+    /// The family name of the font. Empty if the font database doesn't know it.
+    pub family: String,
+    /// The PostScript name of the font. Empty if the font database doesn't
+    /// know it.
+    pub post_script_name: String,
+    /// What went wrong.
+    pub cause: String,
+}
+
+#[cfg(feature = "text")]
+impl Display for FontError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "\"{}\"", self.family)?;
+        if !self.post_script_name.is_empty() {
+            write!(f, " ({})", self.post_script_name)?;
         }
+        write!(f, ": {}", self.cause)
     }
 }
 
@@ -124,42 +323,506 @@ impl Display for ConversionError {
 type Result<T> = std::result::Result<T, ConversionError>;
 
 /// Options for the PDF conversion.
-#[derive(Copy, Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ConversionOptions {
-    /// Whether the content streams should be compressed.
+    /// Whether the streams of the PDF should be compressed.
     ///
     /// The smaller PDFs generated by this are generally more practical, but it
-    /// might increase run-time a bit.
+    /// might increase run-time a bit. Without compression, content streams,
+    /// images, fonts and ICC profiles are written as-is.
     ///
     /// _Default:_ `true`.
     pub compress: bool,
 
+    /// Whether the PDF should be written in a way that can be read in a text
+    /// editor, which is useful for debugging. Content streams are never
+    /// compressed then, regardless of [`compress`](Self::compress), and binary
+    /// streams like images, fonts and ICC profiles are written uncompressed and
+    /// hex-encoded. Only JPEG images are still embedded as-is.
+    ///
+    /// _Default:_ `false`.
+    pub debug_readable: bool,
+
     /// How much raster images of rasterized effects should be scaled up.
     ///
     /// Higher values will lead to better quality, but will increase the size of
-    /// the pdf.
+    /// the pdf. Rasterized effects are always encoded losslessly, so colors
+    /// are never blurred by chroma subsampling.
     ///
     /// _Default:_ 1.5
     pub raster_scale: f32,
 
+    /// Whether groups whose only filter is a `feDropShadow` should be kept as
+    /// vector graphics. Only the shadow is rasterized then and drawn below the
+    /// group, instead of rasterizing the group as a whole. The shadow is
+    /// blurred with the box blur approximation of the specification, so it
+    /// differs slightly from the one of a fully rasterized group.
+    ///
+    /// _Default:_ `false`.
+    pub vector_drop_shadows: bool,
+
     /// Whether text should be embedded as actual selectable text inside
     /// the PDF. If this option is disabled, text will be converted into paths
-    /// before rendering.
+    /// before rendering. The paths keep the exact curves of the glyph outlines,
+    /// so they are as precise as possible regardless of `text-rendering`.
     ///
     /// _Default:_ `true`.
     pub embed_text: bool,
+
+    /// Fonts whose glyphs should be converted into paths even though
+    /// [`ConversionOptions::embed_text`] is enabled. This is useful if the
+    /// license of some font doesn't allow embedding it, while text in all other
+    /// fonts should stay selectable.
+    ///
+    /// _Default:_ empty.
+    #[cfg(feature = "text")]
+    pub outline_fonts: Vec<fontdb::ID>,
+
+    /// The maximum number of bytes the resulting PDF may take up. If the
+    /// conversion produces more output than that, it is aborted with
+    /// [`ConversionError::LimitExceeded`].
+    ///
+    /// This is useful to protect services that convert untrusted SVGs
+    /// from exhausting their memory. To that end, the limit is also checked
+    /// with the uncompressed size of images before they are decoded and of
+    /// filters before they are rasterized, and content streams count towards
+    /// it while they are still being written.
+    ///
+    /// _Default:_ `None`.
+    pub max_output_bytes: Option<usize>,
+
+    /// The maximum number of nodes the tree may contain, including the
+    /// nodes in patterns, clip paths, masks and nested SVG images. Trees
+    /// with more nodes are rejected with [`ConversionError::LimitExceeded`]
+    /// before anything is written.
+    ///
+    /// _Default:_ `None`.
+    pub max_nodes: Option<usize>,
+
+    /// The minimum width of strokes, in user units of the root SVG. Strokes
+    /// that would end up thinner than this after all transforms have been
+    /// applied are widened to this width, so that hairlines don't vanish in
+    /// viewers that don't render very thin lines.
+    ///
+    /// _Default:_ `None`.
+    pub min_stroke_width: Option<f32>,
+
+    /// The natural language of the text in the SVG as a BCP 47 language tag
+    /// (e.g. `"fr"` or `"en-US"`). If set, embedded text is marked with this
+    /// language, which helps screen readers and text extraction.
+    ///
+    /// All text is marked with the same language. The `xml:lang` and `lang`
+    /// attributes of `text` elements and their spans are not taken into
+    /// account, since usvg doesn't preserve them.
+    ///
+    /// _Default:_ `None`.
+    pub language: Option<String>,
+
+    /// The natural language of the whole document as a BCP 47 language tag.
+    /// It is written to the document catalog, which is required for tagged
+    /// PDFs. If it isn't set, [`language`](Self::language) is used instead.
+    ///
+    /// usvg doesn't preserve the `xml:lang` attribute, but [`convert_many`]
+    /// and [`Converter::convert_data`] use the one of the root `svg` element
+    /// from [`Preprocessed::language`] if this isn't set.
+    ///
+    /// _Default:_ `None`.
+    pub document_language: Option<String>,
+
+    /// Whether the content of each element with an ID should be wrapped in a
+    /// marked-content sequence with the tag `Span` and the ID as the `ID`
+    /// property. This allows tools to correlate regions of the PDF with the
+    /// elements of the SVG without producing a full tagged PDF.
+    ///
+    /// _Default:_ `false`.
+    pub tag_ids: bool,
+
+    /// Whether each top-level group of the SVG should become a layer, i.e. an
+    /// optional content group that can be toggled in viewers. Layers are named
+    /// after the ID of their group. Each layer is drawn with a Form XObject of
+    /// its own, so that it can be replaced without re-rendering the whole
+    /// page. Elements that are not inside a group are drawn without a layer.
+    ///
+    /// When rendering with a [`Context`], the layers are available through
+    /// [`Context::layers`].
+    ///
+    /// _Default:_ `false`.
+    pub layers_from_groups: bool,
+
+    /// Whether the SVG should additionally be embedded as a raster image, for
+    /// viewers that struggle with complex vector content. The vector content
+    /// and the image are put into two layers named "Vector" and "Raster", of
+    /// which only one can be visible at a time. The image is rasterized with
+    /// [`raster_scale`](Self::raster_scale) and hidden by default. Takes
+    /// precedence over [`layers_from_groups`](Self::layers_from_groups).
+    /// Requires the `filters` feature.
+    ///
+    /// _Default:_ `false`.
+    pub raster_fallback: bool,
+
+    /// Whether transparency should be flattened for viewers and printers that
+    /// don't support it. If enabled and the SVG uses any kind of transparency
+    /// (including opacities, masks, blend modes and soft-masked clip paths), it
+    /// is rasterized onto a white background, so that the PDF contains no soft
+    /// masks or transparency groups. Requires the `filters` feature.
+    ///
+    /// Note that the whole page is rasterized in that case, not just the parts
+    /// that are transparent, since those would need to be composited with the
+    /// content below them anyway. This means that all text becomes
+    /// unselectable and that the quality of the page depends on
+    /// [`ConversionOptions::raster_scale`].
+    ///
+    /// _Default:_ `false`.
+    pub flatten_transparency: bool,
+
+    /// Whether colors should be specified in terms of an embedded sRGB ICC
+    /// profile. This applies to fills, strokes, gradients and the color space
+    /// in which transparency groups are blended. If disabled, `DeviceRGB` is
+    /// used instead, which makes the PDF a bit smaller, but leaves the
+    /// interpretation of the colors up to the viewer.
+    ///
+    /// Colors are always written as RGB (or gray for masks), so they are never
+    /// converted to a narrower color space like CMYK and don't need to be
+    /// clamped. Converting them for print is left to the consumer of the PDF.
+    ///
+    /// Has no effect if the `icc` feature is disabled.
+    ///
+    /// _Default:_ `true`.
+    pub embed_icc: bool,
+
+    /// A text that is drawn over the content of each page, e.g. to mark a
+    /// document as a draft. The source SVG is left untouched. Requires the
+    /// `text` feature, without it the watermark is skipped with a warning.
+    ///
+    /// The watermark is always drawn as vector content on top of the page. It
+    /// is not rasterized by [`ConversionOptions::flatten_transparency`] or
+    /// [`ConversionOptions::raster_fallback`] and not tagged with
+    /// [`ConversionOptions::tag_ids`].
+    ///
+    /// _Default:_ `None`.
+    pub watermark: Option<Watermark>,
+
+    /// Whether to draw crop marks and registration marks for printing. The
+    /// page is surrounded by a margin of half an inch that holds the marks,
+    /// and the area of the original page is written as the trim box of the
+    /// page.
+    ///
+    /// _Default:_ `false`.
+    pub crop_marks: bool,
+
+    /// Whether to linearize the PDF, so that viewers can display the first
+    /// page before the whole file has been downloaded. This is useful for large
+    /// documents that are served over the web. The file is rewritten once it
+    /// is complete, which takes a bit of extra time and memory.
+    ///
+    /// _Default:_ `false`.
+    pub linearize: bool,
+}
+
+/// A text that is drawn diagonally across the center of each page. See
+/// [`ConversionOptions::watermark`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Watermark {
+    /// The text of the watermark. It is set in a bold sans-serif font from the
+    /// font database of the converted tree, sized relative to the page.
+    pub text: String,
+    /// The color of the text.
+    pub color: usvg::Color,
+    /// The opacity of the text, from 0 to 1.
+    pub opacity: f32,
+    /// The angle of the text in degrees, counter-clockwise from the
+    /// horizontal.
+    pub angle: f32,
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self {
+            text: "DRAFT".to_string(),
+            color: usvg::Color::new_rgb(128, 128, 128),
+            opacity: 0.3,
+            angle: 45.0,
+        }
+    }
+}
+
+/// The media type to resolve `@media` rules for.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum MediaType {
+    /// Apply the styles for screens.
+    #[default]
+    Screen,
+    /// Apply the styles for printing.
+    Print,
+}
+
+/// The color scheme to resolve `prefers-color-scheme` media queries for.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ColorScheme {
+    /// Apply the styles for a light color scheme.
+    #[default]
+    Light,
+    /// Apply the styles for a dark color scheme.
+    Dark,
+}
+
+/// How `foreignObject` elements are handled.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ForeignObjectPolicy {
+    /// Skip them, leaving an empty area.
+    #[default]
+    Skip,
+    /// Draw a gray placeholder rectangle in their place.
+    Placeholder,
+    /// Fail with [`ConversionError::UnsupportedForeignObject`].
+    Error,
+}
+
+/// Options for [`preprocess`], which handles the features of an SVG source
+/// that usvg drops or ignores while parsing.
+///
+/// Since they act on the source, these options have no effect on
+/// [`to_pdf`] and the other functions that convert an already parsed tree.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PreprocessOptions {
+    /// The media type for which `@media` rules in the style sheets of the SVG
+    /// are resolved.
+    ///
+    /// _Default:_ [`MediaType::Screen`].
+    pub media: MediaType,
+
+    /// The color scheme for which `@media (prefers-color-scheme: ...)` rules
+    /// are resolved.
+    ///
+    /// _Default:_ [`ColorScheme::Light`].
+    pub color_scheme: ColorScheme,
+
+    /// How `foreignObject` elements, which are not supported, are handled.
+    ///
+    /// _Default:_ [`ForeignObjectPolicy::Skip`].
+    pub foreign_object: ForeignObjectPolicy,
+
+    /// The language as a BCP 47 language tag against which the
+    /// `systemLanguage` attributes of the SVG are evaluated, on any element
+    /// and not just the children of `<switch>`. If it isn't set, usvg
+    /// evaluates them against [`usvg::Options::languages`] while parsing.
+    ///
+    /// _Default:_ `None`.
+    pub language: Option<String>,
+
+    /// Loads the files that `use` elements refer to, like
+    /// `<use href="icons.svg#star"/>`. If it isn't set, such references are
+    /// left to usvg, which doesn't resolve them.
+    ///
+    /// _Default:_ `None`.
+    pub resource_resolver: Option<ResourceResolver>,
+}
+
+/// Loads the SVG files that `use` elements refer to for [`preprocess`].
+///
+/// It is called with the path of a reference, i.e. `icons.svg` for
+/// `<use href="icons.svg#star"/>`, and returns the source of the file, or
+/// `None` if it can't or shouldn't be loaded. Since SVGs may come from
+/// untrusted sources, it's up to the resolver to decide which files they may
+/// refer to.
+#[derive(Clone)]
+pub struct ResourceResolver(std::sync::Arc<dyn Fn(&str) -> Option<String> + Send + Sync>);
+
+impl ResourceResolver {
+    /// Create a resolver that loads files with a function.
+    pub fn new(resolve: impl Fn(&str) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(resolve))
+    }
+
+    /// Create a resolver that reads files relative to a directory. Only
+    /// relative paths are resolved, and only if the file they lead to is inside
+    /// of the directory after following symbolic links.
+    pub fn from_dir(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        Self::new(move |path| util::external_use::read(&dir, path))
+    }
+
+    /// Load the file with the given path.
+    pub(crate) fn resolve(&self, path: &str) -> Option<String> {
+        (self.0)(path)
+    }
+}
+
+impl fmt::Debug for ResourceResolver {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("ResourceResolver(..)")
+    }
+}
+
+impl PartialEq for ResourceResolver {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 impl Default for ConversionOptions {
     fn default() -> Self {
         Self {
             compress: true,
+            debug_readable: false,
             raster_scale: 1.5,
+            vector_drop_shadows: false,
             embed_text: true,
+            #[cfg(feature = "text")]
+            outline_fonts: vec![],
+            max_output_bytes: None,
+            max_nodes: None,
+            min_stroke_width: None,
+            language: None,
+            document_language: None,
+            tag_ids: false,
+            layers_from_groups: false,
+            raster_fallback: false,
+            flatten_transparency: false,
+            embed_icc: true,
+            watermark: None,
+            crop_marks: false,
+            linearize: false,
         }
     }
 }
 
+impl ConversionOptions {
+    /// Create a builder for conversion options, starting from the defaults.
+    pub fn builder() -> ConversionOptionsBuilder {
+        ConversionOptionsBuilder { options: Self::default() }
+    }
+}
+
+/// A builder for [`ConversionOptions`]. See the fields of
+/// [`ConversionOptions`] for the meaning of each setting.
+#[derive(Clone, Debug)]
+pub struct ConversionOptionsBuilder {
+    options: ConversionOptions,
+}
+
+impl ConversionOptionsBuilder {
+    /// Set [`ConversionOptions::compress`].
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.options.compress = compress;
+        self
+    }
+
+    /// Set [`ConversionOptions::debug_readable`].
+    pub fn debug_readable(mut self, debug_readable: bool) -> Self {
+        self.options.debug_readable = debug_readable;
+        self
+    }
+
+    /// Set [`ConversionOptions::raster_scale`].
+    pub fn raster_scale(mut self, raster_scale: f32) -> Self {
+        self.options.raster_scale = raster_scale;
+        self
+    }
+
+    /// Set [`ConversionOptions::vector_drop_shadows`].
+    pub fn vector_drop_shadows(mut self, vector_drop_shadows: bool) -> Self {
+        self.options.vector_drop_shadows = vector_drop_shadows;
+        self
+    }
+
+    /// Set [`ConversionOptions::embed_text`].
+    pub fn embed_text(mut self, embed_text: bool) -> Self {
+        self.options.embed_text = embed_text;
+        self
+    }
+
+    /// Set [`ConversionOptions::outline_fonts`].
+    #[cfg(feature = "text")]
+    pub fn outline_fonts(mut self, fonts: impl IntoIterator<Item = fontdb::ID>) -> Self {
+        self.options.outline_fonts = fonts.into_iter().collect();
+        self
+    }
+
+    /// Set [`ConversionOptions::max_output_bytes`].
+    pub fn max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.options.max_output_bytes = Some(max_output_bytes);
+        self
+    }
+
+    /// Set [`ConversionOptions::max_nodes`].
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.options.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Set [`ConversionOptions::min_stroke_width`].
+    pub fn min_stroke_width(mut self, min_stroke_width: f32) -> Self {
+        self.options.min_stroke_width = Some(min_stroke_width);
+        self
+    }
+
+    /// Set [`ConversionOptions::language`].
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.options.language = Some(language.into());
+        self
+    }
+
+    /// Set [`ConversionOptions::document_language`].
+    pub fn document_language(mut self, document_language: impl Into<String>) -> Self {
+        self.options.document_language = Some(document_language.into());
+        self
+    }
+
+    /// Set [`ConversionOptions::tag_ids`].
+    pub fn tag_ids(mut self, tag_ids: bool) -> Self {
+        self.options.tag_ids = tag_ids;
+        self
+    }
+
+    /// Set [`ConversionOptions::layers_from_groups`].
+    pub fn layers_from_groups(mut self, layers_from_groups: bool) -> Self {
+        self.options.layers_from_groups = layers_from_groups;
+        self
+    }
+
+    /// Set [`ConversionOptions::raster_fallback`].
+    pub fn raster_fallback(mut self, raster_fallback: bool) -> Self {
+        self.options.raster_fallback = raster_fallback;
+        self
+    }
+
+    /// Set [`ConversionOptions::flatten_transparency`].
+    pub fn flatten_transparency(mut self, flatten_transparency: bool) -> Self {
+        self.options.flatten_transparency = flatten_transparency;
+        self
+    }
+
+    /// Set [`ConversionOptions::embed_icc`].
+    pub fn embed_icc(mut self, embed_icc: bool) -> Self {
+        self.options.embed_icc = embed_icc;
+        self
+    }
+
+    /// Set [`ConversionOptions::watermark`].
+    pub fn watermark(mut self, watermark: Option<Watermark>) -> Self {
+        self.options.watermark = watermark;
+        self
+    }
+
+    /// Set [`ConversionOptions::crop_marks`].
+    pub fn crop_marks(mut self, crop_marks: bool) -> Self {
+        self.options.crop_marks = crop_marks;
+        self
+    }
+
+    /// Set [`ConversionOptions::linearize`].
+    pub fn linearize(mut self, linearize: bool) -> Self {
+        self.options.linearize = linearize;
+        self
+    }
+
+    /// Create the conversion options.
+    pub fn build(self) -> ConversionOptions {
+        self.options
+    }
+}
+
 /// Convert a [`usvg` tree](Tree) into a standalone PDF buffer.
 ///
 /// ## Example
@@ -190,60 +853,586 @@ pub fn to_pdf(
     conversion_options: ConversionOptions,
     page_options: PageOptions,
 ) -> Result<Vec<u8>> {
-    let mut ctx = Context::new(tree, conversion_options);
-    let mut pdf = Pdf::new();
+    to_multipage_pdf(std::slice::from_ref(tree), conversion_options, page_options)
+}
 
-    let dpi_ratio = 72.0 / page_options.dpi;
-    let dpi_transform = Transform::from_scale(dpi_ratio, dpi_ratio);
-    let page_size =
-        Size::from_wh(tree.size().width() * dpi_ratio, tree.size().height() * dpi_ratio)
-            .ok_or(UnknownError)?;
+/// Convert a [`usvg` tree](Tree) into a standalone PDF buffer, like
+/// [`to_pdf`], and additionally return where each element with an ID ended up
+/// on the page.
+///
+/// The rectangles are the bounding boxes of the elements in the coordinate
+/// system of the PDF page, i.e. in points with the origin in the bottom-left
+/// corner. This can be used to place interactive overlays on top of the PDF.
+/// Elements in patterns, masks and clip paths are not included.
+pub fn to_pdf_with_map(
+    tree: &Tree,
+    conversion_options: ConversionOptions,
+    page_options: PageOptions,
+) -> Result<(Vec<u8>, Vec<(String, Rect)>)> {
+    let crop_marks = conversion_options.crop_marks;
+    let pdf = to_pdf(tree, conversion_options, page_options)?;
+    Ok((pdf, element_map(tree, &page_options, crop_marks)?))
+}
+
+/// The result of [`convert`].
+#[derive(Clone, Debug)]
+pub struct Output {
+    /// The PDF file.
+    pub pdf: Vec<u8>,
+    /// The warnings that were emitted during the conversion, e.g. because a
+    /// group with filters was rasterized. They are also logged with the `log`
+    /// crate. When converting with [`Converter::convert_data`], the warnings of
+    /// [`preprocess`] come first. Warnings that usvg emits while parsing are not
+    /// included.
+    pub warnings: Vec<String>,
+    /// Whether any part of the SVG had to be rasterized. See
+    /// [`Context::rasterized`].
+    pub rasterized: bool,
+    /// Where each element with an ID ended up on the page. See
+    /// [`to_pdf_with_map`].
+    pub element_map: Vec<(String, Rect)>,
+    /// Statistics about the converted tree.
+    pub stats: Stats,
+}
+
+/// Statistics about a conversion, see [`Output::stats`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// The number of nodes in the tree, including the ones in patterns, masks,
+    /// clip paths and nested SVGs.
+    pub nodes: usize,
+    /// The number of fonts that were embedded into the PDF. Fonts that were
+    /// only used for outlined text don't count.
+    pub fonts: usize,
+}
+
+/// Convert a [`usvg` tree](Tree) into a standalone PDF buffer, like
+/// [`to_pdf`], and return everything that is known about the conversion along
+/// with it.
+///
+/// ## Example
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use svg2pdf::{ConversionOptions, PageOptions};
+///
+/// let svg = std::fs::read_to_string("tests/svg/custom/integration/matplotlib/stairs.svg")?;
+/// let tree = svg2pdf::usvg::Tree::from_str(&svg, &svg2pdf::usvg::Options::default())?;
+///
+/// let output = svg2pdf::convert(&tree, ConversionOptions::default(), PageOptions::default())?;
+/// for warning in &output.warnings {
+///     eprintln!("warning: {warning}");
+/// }
+/// std::fs::write("target/stairs.pdf", output.pdf)?;
+/// # Ok(()) }
+/// ```
+pub fn convert(
+    tree: &Tree,
+    conversion_options: ConversionOptions,
+    page_options: PageOptions,
+) -> Result<Output> {
+    let mut ctx = Context::new(conversion_options);
+    let pdf = write_pdf(std::slice::from_ref(tree), &mut ctx, page_options)?;
+
+    let stats = Stats {
+        nodes: count_nodes(tree.root()),
+        fonts: ctx.embedded_fonts(),
+    };
+
+    Ok(Output {
+        pdf,
+        warnings: ctx.warnings,
+        rasterized: ctx.rasterized,
+        element_map: element_map(tree, &page_options, ctx.options.crop_marks)?,
+        stats,
+    })
+}
 
+/// Determine where each element with an ID ends up on the page.
+fn element_map(
+    tree: &Tree,
+    page_options: &PageOptions,
+    crop_marks: bool,
+) -> Result<Vec<(String, Rect)>> {
+    let (_, transform, _, _) =
+        page_layout(tree.size(), page_options).ok_or(UnknownError)?;
+    let offset = if crop_marks { MARK_MARGIN } else { 0.0 };
+    // From the SVG coordinate system to the PDF coordinate system.
+    let transform = Transform::from_translate(offset, offset)
+        .pre_concat(transform)
+        .pre_concat(Transform::from_row(1.0, 0.0, 0.0, -1.0, 0.0, tree.size().height()));
+
+    let mut map = vec![];
+    element_rects(tree.root(), transform, &mut map);
+    Ok(map)
+}
+
+/// Collect the bounding boxes of all elements with an ID in a group.
+fn element_rects(group: &Group, transform: Transform, map: &mut Vec<(String, Rect)>) {
+    for child in group.children() {
+        if !child.id().is_empty() {
+            if let Some(rect) = child.abs_bounding_box().transform(transform) {
+                map.push((child.id().to_string(), rect));
+            }
+        }
+
+        if let Node::Group(group) = child {
+            element_rects(group, transform, map);
+        }
+    }
+}
+
+/// Convert multiple [`usvg` trees](Tree) into a standalone PDF buffer with one
+/// page per tree.
+///
+/// The pages appear in the same order as the trees and each page is sized
+/// according to its tree, just like with [`to_pdf`]. Fonts and ICC profiles
+/// that are used on multiple pages are only embedded once. Fails with
+/// [`ConversionError::InvalidPageRange`] if there are no trees, since a PDF
+/// needs at least one page.
+///
+/// ## Example
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use svg2pdf::{ConversionOptions, PageOptions};
+///
+/// let options = svg2pdf::usvg::Options::default();
+/// let trees = [
+///     "tests/svg/resvg/shapes/rect/simple-case.svg",
+///     "tests/svg/resvg/shapes/circle/simple-case.svg",
+/// ]
+/// .into_iter()
+/// .map(|path| {
+///     let svg = std::fs::read_to_string(path)?;
+///     Ok(svg2pdf::usvg::Tree::from_str(&svg, &options)?)
+/// })
+/// .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+///
+/// let pdf = svg2pdf::to_multipage_pdf(
+///     &trees,
+///     ConversionOptions::default(),
+///     PageOptions::default(),
+/// )?;
+/// std::fs::write("target/multipage.pdf", pdf)?;
+/// # Ok(()) }
+/// ```
+pub fn to_multipage_pdf(
+    trees: &[Tree],
+    conversion_options: ConversionOptions,
+    page_options: PageOptions,
+) -> Result<Vec<u8>> {
+    if trees.is_empty() {
+        return Err(ConversionError::InvalidPageRange);
+    }
+
+    let mut ctx = Context::new(conversion_options);
+    write_pdf(trees, &mut ctx, page_options)
+}
+
+/// Convert a range of [`usvg` trees](Tree) into a standalone PDF buffer with
+/// one page per tree, e.g. to split a large set of pages into chunks that are
+/// served separately.
+///
+/// The range refers to the indices of the trees and works just like slicing,
+/// so `1..3` converts the second and third tree. Fails with
+/// [`ConversionError::InvalidPageRange`] if the range is empty or out of
+/// bounds. Otherwise, this is the same as calling [`to_multipage_pdf`] with the
+/// trees in the range.
+pub fn to_multipage_pdf_range(
+    trees: &[Tree],
+    pages: Range<usize>,
+    conversion_options: ConversionOptions,
+    page_options: PageOptions,
+) -> Result<Vec<u8>> {
+    let trees = trees
+        .get(pages)
+        .filter(|trees| !trees.is_empty())
+        .ok_or(ConversionError::InvalidPageRange)?;
+    to_multipage_pdf(trees, conversion_options, page_options)
+}
+
+/// Write a PDF with one page per tree into an empty PDF.
+pub(crate) fn write_pdf(
+    trees: &[Tree],
+    ctx: &mut Context,
+    page_options: PageOptions,
+) -> Result<Vec<u8>> {
+    let mut pdf = Pdf::new();
     let catalog_ref = ctx.alloc_ref();
     let page_tree_ref = ctx.alloc_ref();
-    let page_ref = ctx.alloc_ref();
-    let content_ref = ctx.alloc_ref();
-
-    pdf.catalog(catalog_ref).pages(page_tree_ref);
-    pdf.pages(page_tree_ref).count(1).kids([page_ref]);
-
-    // Generate main content
-    let mut rc = ResourceContainer::new();
-    let mut content = Content::new();
-    content.save_state();
-    content.transform(dpi_transform.to_pdf_transform());
-    tree_to_stream(tree, &mut pdf, &mut content, &mut ctx, &mut rc)?;
-    content.restore_state();
-    let content_stream = ctx.finish_content(content);
-    let mut stream = pdf.stream(content_ref, &content_stream);
-
-    if ctx.options.compress {
-        stream.filter(Filter::FlateDecode);
-    }
-    stream.finish();
-
-    let mut page = pdf.page(page_ref);
-    let mut page_resources = page.resources();
-    rc.finish(&mut page_resources);
-    page_resources.finish();
-
-    page.media_box(page_size.to_non_zero_rect(0.0, 0.0).to_pdf_rect());
-    page.parent(page_tree_ref);
-    page.group()
-        .transparency()
-        .isolated(true)
-        .knockout(false)
-        .color_space()
-        .icc_based(ctx.srgb_ref());
-    page.contents(content_ref);
-    page.finish();
+    let page_refs: Vec<Ref> = trees.iter().map(|_| ctx.alloc_ref()).collect();
+
+    pdf.pages(page_tree_ref)
+        .count(page_refs.len() as i32)
+        .kids(page_refs.iter().copied());
+
+    for (tree, &page_ref) in trees.iter().zip(&page_refs) {
+        let (page_size, transform, clip, clamped) =
+            page_layout(tree.size(), &page_options).ok_or(UnknownError)?;
+        if clamped {
+            ctx.warn(format!(
+                "The page exceeds the maximum size of {MAX_PAGE_SIZE} points and was \
+                 scaled down to {}x{} points.",
+                page_size.width(),
+                page_size.height()
+            ));
+        }
+        let content_ref = ctx.alloc_ref();
+
+        // With crop marks, the page is surrounded by a margin for them and
+        // becomes the trim box.
+        let offset = if ctx.options.crop_marks { MARK_MARGIN } else { 0.0 };
+        let trim = page_size.to_non_zero_rect(offset, offset);
+        let media_size = Size::from_wh(
+            page_size.width() + 2.0 * offset,
+            page_size.height() + 2.0 * offset,
+        )
+        .ok_or(UnknownError)?;
+        let translation = Transform::from_translate(offset, offset);
+
+        // Generate main content
+        let mut rc = ResourceContainer::new();
+        let mut content = Content::new();
+        content.save_state();
+        if let Some(clip) = clip {
+            clip_to_rect(
+                clip.translate(offset, offset).ok_or(UnknownError)?,
+                &mut content,
+            );
+        }
+        content.transform(translation.pre_concat(transform).to_pdf_transform());
+        ctx.render_tree(tree, &mut pdf, &mut content, &mut rc)?;
+        content.restore_state();
+        if ctx.options.crop_marks {
+            let registration = rc.add_color_space(ctx.registration_ref());
+            draw_crop_marks(&mut content, trim, &registration);
+        }
+        if let Some(watermark) = ctx.options.watermark.clone() {
+            #[cfg(feature = "text")]
+            {
+                let watermark =
+                    watermark_tree(&watermark, page_size, tree.fontdb().clone())?;
+                ctx.render_overlay(
+                    &watermark,
+                    &mut pdf,
+                    &mut content,
+                    &mut rc,
+                    translation,
+                )?;
+            }
+            #[cfg(not(feature = "text"))]
+            ctx.warn(format!(
+                "Failed to draw the watermark \"{}\" because the text feature was \
+                 disabled. Skipping.",
+                watermark.text
+            ));
+        }
+        let content_stream = ctx.finish_content(content);
+        ctx.check_output_size(pdf.len() + content_stream.len())?;
+        let mut stream = pdf.stream(content_ref, &content_stream);
+
+        if ctx.options.compress {
+            stream.filter(Filter::FlateDecode);
+        }
+        stream.finish();
+
+        let mut page = pdf.page(page_ref);
+        let mut page_resources = page.resources();
+        rc.finish(&mut page_resources);
+        page_resources.finish();
+
+        page.media_box(media_size.to_non_zero_rect(0.0, 0.0).to_pdf_rect());
+        if ctx.options.crop_marks {
+            page.trim_box(trim.to_pdf_rect());
+        }
+        page.parent(page_tree_ref);
+        if !ctx.options.flatten_transparency {
+            let color_space = page
+                .group()
+                .transparency()
+                .isolated(true)
+                .knockout(false)
+                .color_space();
+            ctx.write_rgb_color_space(color_space);
+        }
+        page.contents(content_ref);
+        page.finish();
+    }
+
+    let mut catalog = pdf.catalog(catalog_ref);
+    catalog.pages(page_tree_ref);
+    let language = ctx
+        .options
+        .document_language
+        .as_ref()
+        .or(ctx.options.language.as_ref());
+    if let Some(language) = language {
+        catalog.lang(TextStr(language));
+    }
+    if !ctx.layers.is_empty() {
+        let mut oc_properties = catalog.insert(Name(b"OCProperties")).dict();
+        oc_properties
+            .insert(Name(b"OCGs"))
+            .array()
+            .items(ctx.layers.iter().copied());
+        let mut config = oc_properties.insert(Name(b"D")).dict();
+        config
+            .insert(Name(b"Order"))
+            .array()
+            .items(ctx.layers.iter().copied());
+        if !ctx.hidden_layers.is_empty() {
+            config
+                .insert(Name(b"OFF"))
+                .array()
+                .items(ctx.hidden_layers.iter().copied());
+        }
+        if !ctx.exclusive_layers.is_empty() {
+            let mut groups = config.insert(Name(b"RBGroups")).array();
+            for layers in &ctx.exclusive_layers {
+                groups.push().array().items(layers.iter().copied());
+            }
+        }
+    }
+    catalog.finish();
 
     ctx.write_global_objects(&mut pdf)?;
 
     let document_info_id = ctx.alloc_ref();
     pdf.document_info(document_info_id).producer(TextStr("svg2pdf"));
 
-    Ok(pdf.finish())
+    let mut pdf = pdf.finish();
+    if ctx.options.linearize {
+        pdf = linearize(&pdf, ctx.options.compress).ok_or(UnknownError)?;
+    }
+    ctx.check_output_size(pdf.len())?;
+
+    Ok(pdf)
+}
+
+/// The largest width or height of a page in points that PDF viewers need to
+/// support.
+const MAX_PAGE_SIZE: f32 = 14400.0;
+
+/// Determine the size of a page, the transform that places the content on it
+/// and the area the content needs to be clipped to, if any. Pages that exceed
+/// [`MAX_PAGE_SIZE`] are scaled down along with their content, which is
+/// indicated by the last value.
+fn page_layout(
+    tree_size: Size,
+    page_options: &PageOptions,
+) -> Option<(Size, Transform, Option<NonZeroRect>, bool)> {
+    let (page_size, transform, clip) = fit_page(tree_size, page_options)?;
+
+    let longest = page_size.width().max(page_size.height());
+    if longest <= MAX_PAGE_SIZE {
+        return Some((page_size, transform, clip, false));
+    }
+
+    // Multiplying first keeps the longer side at exactly the maximum size.
+    let scale = MAX_PAGE_SIZE / longest;
+    let page_size = Size::from_wh(
+        page_size.width() * MAX_PAGE_SIZE / longest,
+        page_size.height() * MAX_PAGE_SIZE / longest,
+    )?;
+    let transform = transform.post_scale(scale, scale);
+    let clip = match clip {
+        Some(clip) => Some(clip.transform(Transform::from_scale(scale, scale))?),
+        None => None,
+    };
+    Some((page_size, transform, clip, true))
+}
+
+/// Fit the content into a page according to the page options, regardless of
+/// the limits of PDF.
+fn fit_page(
+    tree_size: Size,
+    page_options: &PageOptions,
+) -> Option<(Size, Transform, Option<NonZeroRect>)> {
+    let dpi_ratio = 72.0 / page_options.dpi;
+    let margin = page_options.margin;
+    let content_size =
+        Size::from_wh(tree_size.width() * dpi_ratio, tree_size.height() * dpi_ratio)?;
+
+    let Some(page_size) = page_options.page_size else {
+        let page_size = Size::from_wh(
+            content_size.width() + 2.0 * margin,
+            content_size.height() + 2.0 * margin,
+        )?;
+        let transform =
+            Transform::from_row(dpi_ratio, 0.0, 0.0, dpi_ratio, margin, margin);
+        return Some((page_size, transform, None));
+    };
+
+    let available = NonZeroRect::from_xywh(
+        margin,
+        margin,
+        page_size.width() - 2.0 * margin,
+        page_size.height() - 2.0 * margin,
+    )?;
+
+    let sx = available.width() / content_size.width();
+    let sy = available.height() / content_size.height();
+    let (sx, sy) = match page_options.fit {
+        Fit::Contain => (sx.min(sy), sx.min(sy)),
+        Fit::Cover => (sx.max(sy), sx.max(sy)),
+        Fit::Fill => (sx, sy),
+        Fit::None => (1.0, 1.0),
+    };
+
+    let width = content_size.width() * sx;
+    let height = content_size.height() * sy;
+    let transform = Transform::from_row(
+        dpi_ratio * sx,
+        0.0,
+        0.0,
+        dpi_ratio * sy,
+        available.x() + (available.width() - width) / 2.0,
+        available.y() + (available.height() - height) / 2.0,
+    );
+
+    let overflows = width > available.width() || height > available.height();
+    Some((page_size, transform, overflows.then_some(available)))
+}
+
+/// Convert many SVG files into standalone PDF buffers.
+///
+/// All files are parsed with the same `usvg` options, so the font database in
+/// them (which can be expensive to build when system fonts are loaded) is
+/// shared by all conversions. Each file is converted on its own and results in
+/// its own entry in the returned vector, so a file that can't be read, parsed
+/// or converted doesn't affect the others.
+///
+/// Uncompressed files are prepared with [`preprocess`] and the given options
+/// before parsing them. Note that fonts embedded via `@font-face` rules are not
+/// loaded, use [`to_pdf`] together with `load_font_faces` for such files.
+pub fn convert_many(
+    inputs: &[PathBuf],
+    usvg_options: &usvg::Options,
+    preprocess_options: &PreprocessOptions,
+    conversion_options: &ConversionOptions,
+    page_options: PageOptions,
+) -> Vec<Result<Vec<u8>>> {
+    inputs
+        .iter()
+        .map(|input| {
+            let data = std::fs::read(input).map_err(|_| ConversionError::ReadError)?;
+            let mut conversion_options = conversion_options.clone();
+            let (tree, _) = parse_svg(
+                &data,
+                usvg_options,
+                preprocess_options,
+                &mut conversion_options,
+            )?;
+            to_pdf(&tree, conversion_options, page_options)
+        })
+        .collect()
+}
+
+/// Parse an SVG file like [`convert_many`] does and return it along with the
+/// warnings of preprocessing it. Fills in the document language of the options
+/// from the file if it isn't set.
+fn parse_svg(
+    data: &[u8],
+    usvg_options: &usvg::Options,
+    preprocess_options: &PreprocessOptions,
+    conversion_options: &mut ConversionOptions,
+) -> Result<(Tree, Vec<String>)> {
+    let (tree, warnings) = match std::str::from_utf8(data) {
+        Ok(svg) => {
+            let preprocessed = preprocess(svg, preprocess_options)?;
+            if conversion_options.document_language.is_none() {
+                conversion_options.document_language = preprocessed.language;
+            }
+            (Tree::from_str(&preprocessed.svg, usvg_options), preprocessed.warnings)
+        }
+        // Compressed SVGs.
+        Err(_) => (Tree::from_data(data, usvg_options), vec![]),
+    };
+
+    Ok((tree.map_err(|_| ConversionError::InvalidSvg)?, warnings))
+}
+
+/// Converts SVGs one after another with a shared font database. This is useful
+/// for long-running services that convert many small SVGs, since the font
+/// database can be expensive to build.
+///
+/// Only the font database is shared. Everything else, including the buffer
+/// that a PDF is written into, is allocated anew for each conversion, so a
+/// large PDF doesn't make the converter hold on to its memory. Trees that were
+/// already parsed can be converted with [`to_pdf`] or [`convert`](crate::convert)
+/// directly.
+///
+/// ## Example
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use svg2pdf::{ConversionOptions, Converter, PageOptions, PreprocessOptions};
+///
+/// let converter = Converter::new();
+/// for path in [
+///     "tests/svg/resvg/shapes/rect/simple-case.svg",
+///     "tests/svg/resvg/shapes/circle/simple-case.svg",
+/// ] {
+///     let svg = std::fs::read(path)?;
+///     let output = converter.convert_data(
+///         &svg,
+///         &PreprocessOptions::default(),
+///         ConversionOptions::default(),
+///         PageOptions::default(),
+///     )?;
+///     assert!(output.pdf.starts_with(b"%PDF"));
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Converter {
+    #[cfg(feature = "text")]
+    fontdb: std::sync::Arc<fontdb::Database>,
+}
+
+impl Default for Converter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Converter {
+    /// Create a new converter with an empty font database.
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "text")]
+            fontdb: std::sync::Arc::new(fontdb::Database::new()),
+        }
+    }
+
+    /// Create a new converter that parses SVGs with the given font database.
+    #[cfg(feature = "text")]
+    pub fn with_fontdb(fontdb: std::sync::Arc<fontdb::Database>) -> Self {
+        Self { fontdb }
+    }
+
+    /// Parse an SVG file with the font database of the converter and convert
+    /// it like [`convert`](crate::convert). The file is preprocessed like in
+    /// [`convert_many`].
+    pub fn convert_data(
+        &self,
+        data: &[u8],
+        preprocess_options: &PreprocessOptions,
+        mut conversion_options: ConversionOptions,
+        page_options: PageOptions,
+    ) -> Result<Output> {
+        #[allow(unused_mut)]
+        let mut usvg_options = usvg::Options::default();
+        #[cfg(feature = "text")]
+        {
+            usvg_options.fontdb = self.fontdb.clone();
+        }
+
+        let (tree, mut warnings) =
+            parse_svg(data, &usvg_options, preprocess_options, &mut conversion_options)?;
+        let mut output = convert(&tree, conversion_options, page_options)?;
+        warnings.append(&mut output.warnings);
+        output.warnings = warnings;
+        Ok(output)
+    }
 }
 
 /// Convert a [Tree] into a [`Chunk`].
@@ -259,6 +1448,11 @@ pub fn to_pdf(
 /// The resulting object can be used by embedding the chunk into your existing chunk
 /// and renumbering it appropriately.
 ///
+/// Each chunk contains its own copy of the fonts and ICC profiles that are used
+/// by the SVG. If you want to embed many SVGs into the same PDF, use a shared
+/// [`Context`] and [`Context::render_xobject`] instead, so that they are only
+/// written once.
+///
 /// ## Example
 /// Write a PDF file with some text and an SVG graphic.
 ///
@@ -347,8 +1541,36 @@ pub fn to_chunk(
 ) -> Result<(Chunk, Ref)> {
     let mut chunk = Chunk::new();
 
-    let mut ctx = Context::new(tree, conversion_options);
-    let x_ref = tree_to_xobject(tree, &mut chunk, &mut ctx)?;
+    let mut ctx = Context::new(conversion_options);
+    let x_ref = ctx.render_xobject(tree, &mut chunk)?;
     ctx.write_global_objects(&mut chunk)?;
+    ctx.check_output_size(chunk.len())?;
     Ok((chunk, x_ref))
 }
+
+/// Estimate the size in bytes of the PDF that [`to_pdf`] would produce for a
+/// tree, without converting it.
+///
+/// The estimate is based on the amount of geometry, the size of the embedded
+/// images, the number of glyphs and fonts and the area of filtered groups, so it
+/// grows with the complexity of the SVG. It can be off by quite a bit in either
+/// direction, but is cheap to compute. This makes it useful for rejecting overly
+/// large jobs early; use [`ConversionOptions::max_output_bytes`] to enforce a
+/// hard limit.
+pub fn estimate_size(tree: &Tree, conversion_options: &ConversionOptions) -> usize {
+    util::estimate::estimate_size(tree.root(), conversion_options)
+}
+
+/// Reject trees that have more nodes than allowed by the options.
+pub(crate) fn check_node_limit(
+    tree: &Tree,
+    conversion_options: &ConversionOptions,
+) -> Result<()> {
+    if let Some(max_nodes) = conversion_options.max_nodes {
+        if count_nodes(tree.root()) > max_nodes {
+            return Err(ConversionError::LimitExceeded);
+        }
+    }
+
+    Ok(())
+}
@@ -39,23 +39,31 @@ pub fn render(
     // of more complex clipping paths, even if this means that Safari will in some cases not
     // display them correctly.
 
-    let is_simple_clip_path = is_simple_clip_path(clip_path.root());
-    let clip_rules = collect_clip_rules(clip_path.root());
-
-    if is_simple_clip_path
-        && (clip_rules.iter().all(|f| *f == FillRule::NonZero)
-        // For even odd, there must be at most one shape in the group, because
-        // overlapping shapes with evenodd render differently in PDF
-            || (clip_rules.iter().all(|f| *f == FillRule::EvenOdd)
-                && clip_rules.len() == 1))
-    {
+    if !needs_soft_mask(clip_path) {
+        let clip_rules = collect_clip_rules(clip_path.root());
         create_simple_clip_path(
             clip_path,
             content,
             clip_rules.first().copied().unwrap_or(FillRule::NonZero),
         );
     } else {
-        let clip_path_ref = create_complex_clip_path(group, clip_path, chunk, ctx)?;
+        // Complex clip paths only depend on the clip path itself and the bounding box
+        // of the element, so elements that share them can also share the soft mask.
+        let bbox = bbox_to_non_zero_rect(Some(group.bounding_box()));
+        let key = (
+            clip_path as *const ClipPath as usize,
+            [bbox.x(), bbox.y(), bbox.width(), bbox.height()].map(f32::to_bits),
+        );
+
+        let clip_path_ref = match ctx.clip_paths.get(&key) {
+            Some(clip_path_ref) => *clip_path_ref,
+            None => {
+                let clip_path_ref =
+                    create_complex_clip_path(group, clip_path, chunk, ctx)?;
+                ctx.clip_paths.insert(key, clip_path_ref);
+                clip_path_ref
+            }
+        };
         let clip_path_name = rc.add_graphics_state(clip_path_ref);
         content.set_parameters(clip_path_name.to_pdf_name());
     }
@@ -63,6 +71,20 @@ pub fn render(
     Ok(())
 }
 
+/// Whether a clip path is too complex to be represented with native PDF clip
+/// paths, meaning that it needs to be converted into a soft mask.
+pub fn needs_soft_mask(clip_path: &ClipPath) -> bool {
+    let is_simple_clip_path = is_simple_clip_path(clip_path.root());
+    let clip_rules = collect_clip_rules(clip_path.root());
+
+    !(is_simple_clip_path
+        && (clip_rules.iter().all(|f| *f == FillRule::NonZero)
+        // For even odd, there must be at most one shape in the group, because
+        // overlapping shapes with evenodd render differently in PDF
+            || (clip_rules.iter().all(|f| *f == FillRule::EvenOdd)
+                && clip_rules.len() == 1)))
+}
+
 fn is_simple_clip_path(group: &Group) -> bool {
     group.children().iter().all(|n| {
         match n {
@@ -212,13 +234,13 @@ fn create_complex_clip_path(
 
     rc.finish(&mut x_object.resources());
 
-    x_object
+    let color_space = x_object
         .group()
         .transparency()
         .isolated(false)
         .knockout(false)
-        .color_space()
-        .icc_based(ctx.srgb_ref());
+        .color_space();
+    ctx.write_rgb_color_space(color_space);
 
     x_object.bbox(pdf_bbox);
     x_object.finish();
@@ -1,21 +1,41 @@
-use crate::render::image;
+use crate::render::{group, image, Render};
 use crate::util::context::Context;
+use crate::util::helper::{clip_to_rect, TransformExt};
 use crate::util::resources::ResourceContainer;
 use crate::ConversionError::UnknownError;
 use crate::Result;
 use pdf_writer::{Chunk, Content};
 use std::sync::Arc;
-use tiny_skia::{Size, Transform};
-use usvg::{Group, ImageKind, Node};
+use tiny_skia::{PremultipliedColorU8, Size, Transform};
+use usvg::filter::{DropShadow, Input, Kind};
+use usvg::{BlendMode, Group, ImageKind, ImageRendering, Node, Opacity, Rect, Tree};
 
 /// Render a group with filters as an image.
+///
+/// Filter inputs that depend on the backdrop (`BackgroundImage` and
+/// `BackgroundAlpha`) are replaced with `SourceGraphic` by usvg, which warns
+/// about them while parsing. The group is rasterized like any other filtered
+/// group then, so that it doesn't end up blank.
 pub fn render(
     group: &Group,
     chunk: &mut Chunk,
     content: &mut Content,
     ctx: &mut Context,
+    accumulated_transform: Transform,
     rc: &mut ResourceContainer,
 ) -> Result<()> {
+    if let Some(shadow) = drop_shadow(group).filter(|_| ctx.options.vector_drop_shadows) {
+        return render_drop_shadow(
+            group,
+            shadow,
+            chunk,
+            content,
+            ctx,
+            accumulated_transform,
+            rc,
+        );
+    }
+
     // TODO: Add a check so that huge regions don't crash svg2pdf (see huge-region.svg test case)
     let layer_bbox = group
         .layer_bounding_box()
@@ -27,11 +47,7 @@ pub fn render(
     )
     .ok_or(UnknownError)?;
 
-    let mut pixmap = tiny_skia::Pixmap::new(
-        pixmap_size.width().round() as u32,
-        pixmap_size.height().round() as u32,
-    )
-    .ok_or(UnknownError)?;
+    let mut pixmap = new_pixmap(pixmap_size.width(), pixmap_size.height(), chunk, ctx)?;
 
     let initial_transform =
         Transform::from_scale(ctx.options.raster_scale, ctx.options.raster_scale)
@@ -51,10 +67,27 @@ pub fn render(
     );
 
     let encoded_image = pixmap.encode_png().map_err(|_| UnknownError)?;
+    ctx.rasterized = true;
+    ctx.warn(rasterized_warning(group));
+
+    // The image covers the bounding box of the transformed filter region, which is
+    // larger than the region itself if the group is rotated or skewed. So we clip
+    // to the actual region to make sure that nothing bleeds out of it. Otherwise,
+    // the image already covers exactly the region.
+    let clip = group.transform().has_skew();
+    if clip {
+        content.save_state();
+        content.transform(group.transform().to_pdf_transform());
+        clip_to_rect(group.layer_bounding_box(), content);
+        content.transform(
+            group.transform().invert().ok_or(UnknownError)?.to_pdf_transform(),
+        );
+    }
 
     image::render(
         true,
         &ImageKind::PNG(Arc::new(encoded_image)),
+        ImageRendering::OptimizeQuality,
         Some(layer_bbox.to_rect()),
         chunk,
         content,
@@ -62,5 +95,305 @@ pub fn render(
         rc,
     )?;
 
+    if clip {
+        content.restore_state();
+    }
+
+    Ok(())
+}
+
+/// The warning that is emitted when the filters of a group are rasterized.
+fn rasterized_warning(group: &Group) -> String {
+    if group.id().is_empty() {
+        "A group with filters was rasterized.".to_string()
+    } else {
+        format!("The group `{}` was rasterized because it has filters.", group.id())
+    }
+}
+
+/// Return the drop shadow of a group if it is the only filter primitive and its
+/// result can be drawn below the unfiltered group.
+fn drop_shadow(group: &Group) -> Option<&DropShadow> {
+    let [filter] = group.filters() else { return None };
+    let [primitive] = filter.primitives() else { return None };
+    let Kind::DropShadow(shadow) = primitive.kind() else { return None };
+
+    // The opacity, mask and clip path need to be applied to the shadow and the
+    // group at once, so those cases still need to be rasterized as a whole.
+    let is_simple = group.opacity() == Opacity::ONE
+        && group.mask().is_none()
+        && group.clip_path().is_none()
+        && group.blend_mode() == BlendMode::Normal
+        && primitive.rect() == filter.rect()
+        && matches!(shadow.input(), Input::SourceGraphic);
+
+    is_simple.then_some(shadow)
+}
+
+/// Render a group with a drop shadow. Only the shadow is rasterized, the group
+/// itself is drawn as a vector graphic on top of it.
+fn render_drop_shadow(
+    group: &Group,
+    shadow: &DropShadow,
+    chunk: &mut Chunk,
+    content: &mut Content,
+    ctx: &mut Context,
+    accumulated_transform: Transform,
+    rc: &mut ResourceContainer,
+) -> Result<()> {
+    // The filter region is in the coordinate system of the group, which is also
+    // the one the blur is defined in.
+    let region = group.filters()[0].rect();
+    let raster_scale = ctx.options.raster_scale;
+    let mut pixmap = new_pixmap(
+        region.width() * raster_scale,
+        region.height() * raster_scale,
+        chunk,
+        ctx,
+    )?;
+
+    let base_transform = Transform::from_scale(raster_scale, raster_scale)
+        .pre_translate(-region.x(), -region.y());
+    for child in group.children() {
+        // Same hack as above to counter the translation of `render_node`.
+        let Some(bbox) = child.abs_layer_bounding_box() else { continue };
+        let transform = base_transform.pre_translate(bbox.x(), bbox.y());
+        resvg::render_node(child, transform, &mut pixmap.as_mut());
+    }
+
+    let width = pixmap.width() as usize;
+    let height = pixmap.height() as usize;
+    let mut alpha = vec![0; width * height];
+    let offset_x = (shadow.dx() * raster_scale).round() as isize;
+    let offset_y = (shadow.dy() * raster_scale).round() as isize;
+    for (i, pixel) in pixmap.pixels().iter().enumerate() {
+        let x = (i % width) as isize + offset_x;
+        let y = (i / width) as isize + offset_y;
+        if (0..width as isize).contains(&x) && (0..height as isize).contains(&y) {
+            alpha[y as usize * width + x as usize] = pixel.alpha();
+        }
+    }
+
+    box_blur(
+        &mut alpha,
+        width,
+        height,
+        shadow.std_dev_x().get() * raster_scale,
+        shadow.std_dev_y().get() * raster_scale,
+    );
+
+    let color = shadow.color();
+    let opacity = shadow.opacity().get();
+    for (pixel, &a) in pixmap.pixels_mut().iter_mut().zip(&alpha) {
+        let a = (a as f32 * opacity).round() as u8;
+        let premultiply = |c: u8| (c as u16 * a as u16 / 255) as u8;
+        *pixel = PremultipliedColorU8::from_rgba(
+            premultiply(color.red),
+            premultiply(color.green),
+            premultiply(color.blue),
+            a,
+        )
+        .ok_or(UnknownError)?;
+    }
+
+    let encoded_image = pixmap.encode_png().map_err(|_| UnknownError)?;
+    ctx.rasterized = true;
+    ctx.warn(rasterized_warning(group));
+
+    content.save_state();
+    content.transform(group.transform().to_pdf_transform());
+    clip_to_rect(region, content);
+
+    image::render(
+        true,
+        &ImageKind::PNG(Arc::new(encoded_image)),
+        ImageRendering::OptimizeQuality,
+        Some(region.to_rect()),
+        chunk,
+        content,
+        ctx,
+        rc,
+    )?;
+
+    let accumulated_transform = accumulated_transform.pre_concat(group.transform());
+    for child in group.children() {
+        child.render(chunk, content, ctx, accumulated_transform, rc)?;
+    }
+
+    content.restore_state();
+
+    Ok(())
+}
+
+/// Approximate a gaussian blur with three box blurs in each direction, like the
+/// `feGaussianBlur` section of the specification suggests.
+fn box_blur(
+    data: &mut [u8],
+    width: usize,
+    height: usize,
+    std_dev_x: f32,
+    std_dev_y: f32,
+) {
+    let mut line = vec![];
+
+    if let Some(boxes) = boxes(std_dev_x) {
+        for box_ in boxes {
+            for y in 0..height {
+                box_blur_line(data, y * width, 1, width, box_, &mut line);
+            }
+        }
+    }
+
+    if let Some(boxes) = boxes(std_dev_y) {
+        for box_ in boxes {
+            for x in 0..width {
+                box_blur_line(data, x, width, height, box_, &mut line);
+            }
+        }
+    }
+}
+
+/// The size and the number of values to the left of the center of the three
+/// boxes for a standard deviation, or `None` if the blur has no effect.
+fn boxes(std_dev: f32) -> Option<[(usize, usize); 3]> {
+    let d = (std_dev * 3.0 * (2.0 * std::f32::consts::PI).sqrt() / 4.0 + 0.5) as usize;
+    if d <= 1 {
+        return None;
+    }
+
+    Some(if d % 2 == 1 {
+        [(d, d / 2); 3]
+    } else {
+        // An even box can't be centered on a value, so the first one is
+        // centered between the value and its left neighbor, the second one
+        // between the value and its right neighbor, and the third one is
+        // centered, but one value larger.
+        [(d, d / 2), (d, d / 2 - 1), (d + 1, d / 2)]
+    })
+}
+
+/// Blur a single row or column with a box of the given size that extends
+/// `left` values to the left. Values outside of the data are treated as
+/// transparent.
+fn box_blur_line(
+    data: &mut [u8],
+    start: usize,
+    step: usize,
+    len: usize,
+    (size, left): (usize, usize),
+    line: &mut Vec<u8>,
+) {
+    line.clear();
+    line.extend((0..len).map(|i| data[start + i * step]));
+
+    let right = size - left - 1;
+    let mut sum: u32 = line.iter().take(right + 1).map(|&v| v as u32).sum();
+
+    for i in 0..len {
+        data[start + i * step] = (sum / size as u32) as u8;
+
+        if let Some(&next) = line.get(i + right + 1) {
+            sum += next as u32;
+        }
+
+        if i >= left {
+            sum -= line[i - left] as u32;
+        }
+    }
+}
+
+/// Render a whole tree as an opaque image on a white background. Used for
+/// flattening transparency.
+pub fn render_flattened(
+    tree: &Tree,
+    chunk: &mut Chunk,
+    content: &mut Content,
+    ctx: &mut Context,
+    rc: &mut ResourceContainer,
+) -> Result<()> {
+    ctx.rasterized = true;
+    ctx.warn("The SVG was rasterized to flatten its transparency.".to_string());
+    render_rasterized(tree, chunk, content, ctx, rc, Some(tiny_skia::Color::WHITE))
+}
+
+/// Render a tree both as vectors and as an image, each in a layer of its own,
+/// for viewers that struggle with complex vector content. Only one of the
+/// layers can be visible at a time, and the image is hidden by default.
+pub fn render_with_raster_fallback(
+    tree: &Tree,
+    chunk: &mut Chunk,
+    content: &mut Content,
+    ctx: &mut Context,
+    initial_transform: Transform,
+    rc: &mut ResourceContainer,
+) -> Result<()> {
+    let vector = ctx.add_layer(chunk, "Vector");
+    let raster = ctx.add_layer(chunk, "Raster");
+    ctx.hidden_layers.push(raster);
+    ctx.exclusive_layers.push(vec![vector, raster]);
+
+    group::begin_layer(content, rc, vector);
+    group::render(tree.root(), chunk, content, ctx, initial_transform, None, rc)?;
+    content.end_marked_content();
+
+    group::begin_layer(content, rc, raster);
+    render_rasterized(tree, chunk, content, ctx, rc, None)?;
+    content.end_marked_content();
+
     Ok(())
 }
+
+/// Rasterize a whole tree and draw it as an image with the size of the tree.
+fn render_rasterized(
+    tree: &Tree,
+    chunk: &mut Chunk,
+    content: &mut Content,
+    ctx: &mut Context,
+    rc: &mut ResourceContainer,
+    background: Option<tiny_skia::Color>,
+) -> Result<()> {
+    let raster_scale = ctx.options.raster_scale;
+    let mut pixmap = new_pixmap(
+        tree.size().width() * raster_scale,
+        tree.size().height() * raster_scale,
+        chunk,
+        ctx,
+    )?;
+
+    if let Some(background) = background {
+        pixmap.fill(background);
+    }
+    resvg::render(
+        tree,
+        Transform::from_scale(raster_scale, raster_scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let encoded_image = pixmap.encode_png().map_err(|_| UnknownError)?;
+    let view_box = Rect::from_xywh(0.0, 0.0, tree.size().width(), tree.size().height())
+        .ok_or(UnknownError)?;
+
+    image::render(
+        true,
+        &ImageKind::PNG(Arc::new(encoded_image)),
+        ImageRendering::OptimizeQuality,
+        Some(view_box),
+        chunk,
+        content,
+        ctx,
+        rc,
+    )
+}
+
+/// Allocate a pixmap with the given size, unless its pixels would already exceed
+/// [`ConversionOptions::max_output_bytes`](crate::ConversionOptions::max_output_bytes).
+fn new_pixmap(
+    width: f32,
+    height: f32,
+    chunk: &Chunk,
+    ctx: &Context,
+) -> Result<tiny_skia::Pixmap> {
+    let (width, height) = (width.round() as u32, height.round() as u32);
+    ctx.check_allocation(chunk, u64::from(width) * u64::from(height) * 4)?;
+    tiny_skia::Pixmap::new(width, height).ok_or(UnknownError)
+}
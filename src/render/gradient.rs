@@ -1,6 +1,7 @@
 use pdf_writer::types::{FunctionShadingType, MaskType};
 use pdf_writer::{Chunk, Content, Filter, Finish, Name, Ref};
-use usvg::{Paint, Rect, Transform};
+use usvg::tiny_skia_path::Point;
+use usvg::{Paint, Rect, SpreadMethod, Transform};
 
 use crate::util::context::Context;
 use crate::util::helper::{
@@ -16,30 +17,125 @@ pub struct Stop<const COUNT: usize> {
     pub offset: f32,
 }
 
+/// The maximum number of times the stops of a gradient are repeated to cover
+/// a shape with `spreadMethod="repeat"` or `spreadMethod="reflect"`.
+const MAX_PERIODS: f32 = 256.0;
+
 struct GradientProperties {
     coords: Vec<f32>,
     shading_type: FunctionShadingType,
     stops: Vec<usvg::Stop>,
     transform: Transform,
+    spread_method: SpreadMethod,
+    /// The range of the gradient parameter that is covered by the shading,
+    /// where each unit corresponds to one repetition of the stops.
+    domain: [f32; 2],
 }
 
 impl GradientProperties {
-    fn try_from_paint(paint: &Paint) -> Option<Self> {
-        match paint {
-            Paint::LinearGradient(l) => Some(Self {
+    fn try_from_paint(paint: &Paint, bbox: Rect) -> Option<Self> {
+        let mut properties = match paint {
+            Paint::LinearGradient(l) => Self {
                 coords: vec![l.x1(), l.y1(), l.x2(), l.y2()],
                 shading_type: FunctionShadingType::Axial,
                 stops: Vec::from(l.stops()),
                 transform: l.transform(),
-            }),
-            Paint::RadialGradient(r) => Some(Self {
+                spread_method: l.spread_method(),
+                domain: [0.0, 1.0],
+            },
+            Paint::RadialGradient(r) => Self {
                 coords: vec![r.fx(), r.fy(), 0.0, r.cx(), r.cy(), r.r().get()],
                 shading_type: FunctionShadingType::Radial,
                 stops: Vec::from(r.stops()),
                 transform: r.transform(),
-            }),
-            _ => None,
+                spread_method: r.spread_method(),
+                domain: [0.0, 1.0],
+            },
+            _ => return None,
+        };
+
+        if properties.spread_method != SpreadMethod::Pad {
+            properties.spread(bbox);
+        }
+
+        Some(properties)
+    }
+
+    /// PDF shadings can only pad their ends, so repeating and reflecting
+    /// gradients are drawn by stretching the shading over the whole shape and
+    /// repeating the stops in its function as often as needed.
+    fn spread(&mut self, bbox: Rect) {
+        let Some(inverse) = self.transform.invert() else { return };
+        let mut corners = [
+            Point::from_xy(bbox.left(), bbox.top()),
+            Point::from_xy(bbox.right(), bbox.top()),
+            Point::from_xy(bbox.right(), bbox.bottom()),
+            Point::from_xy(bbox.left(), bbox.bottom()),
+        ];
+        inverse.map_points(&mut corners);
+
+        // The gradient parameter of each corner. Because the parameter is a
+        // convex function of the position, the corners bound it for the
+        // whole shape.
+        let params: Vec<f32> = match self.shading_type {
+            FunctionShadingType::Axial => {
+                let [x1, y1, x2, y2] = self.coords[..] else { return };
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                let length = dx * dx + dy * dy;
+                if length == 0.0 {
+                    return;
+                }
+
+                corners
+                    .iter()
+                    .map(|p| ((p.x - x1) * dx + (p.y - y1) * dy) / length)
+                    .collect()
+            }
+            _ => {
+                // The circles of a radial gradient grow from the focal point
+                // towards the end circle and beyond, so a point lies on the
+                // circle with the parameter `s` if |p - f - s * e| = s * r.
+                let [fx, fy, _, cx, cy, r] = self.coords[..] else { return };
+                let (ex, ey) = (cx - fx, cy - fy);
+                let a = ex * ex + ey * ey - r * r;
+                if a >= 0.0 {
+                    return;
+                }
+
+                corners
+                    .iter()
+                    .map(|p| {
+                        let (qx, qy) = (p.x - fx, p.y - fy);
+                        let b = -2.0 * (qx * ex + qy * ey);
+                        let c = qx * qx + qy * qy;
+                        (-b - (b * b - 4.0 * a * c).sqrt()) / (2.0 * a)
+                    })
+                    .collect()
+            }
+        };
+
+        let min = params.iter().copied().fold(0.0, f32::min).floor().max(-MAX_PERIODS);
+        let max = params.iter().copied().fold(1.0, f32::max).ceil().min(MAX_PERIODS);
+        if !min.is_finite() || !max.is_finite() {
+            return;
         }
+
+        self.coords = match self.coords[..] {
+            [x1, y1, x2, y2] => {
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                vec![x1 + min * dx, y1 + min * dy, x1 + max * dx, y1 + max * dy]
+            }
+            [fx, fy, fr, cx, cy, r] => {
+                vec![fx, fy, fr, fx + max * (cx - fx), fy + max * (cy - fy), max * r]
+            }
+            _ => return,
+        };
+
+        // Radial gradients start at the focal point, which has the parameter 0.
+        self.domain = match self.shading_type {
+            FunctionShadingType::Axial => [min, max],
+            _ => [0.0, max],
+        };
     }
 }
 
@@ -51,8 +147,9 @@ pub fn create_shading_pattern(
     chunk: &mut Chunk,
     ctx: &mut Context,
     accumulated_transform: &Transform,
+    bbox: Rect,
 ) -> Ref {
-    let properties = GradientProperties::try_from_paint(paint).unwrap();
+    let properties = GradientProperties::try_from_paint(paint, bbox).unwrap();
     shading_pattern(&properties, chunk, ctx, accumulated_transform)
 }
 
@@ -65,7 +162,7 @@ pub fn create_shading_soft_mask(
     ctx: &mut Context,
     bbox: Rect,
 ) -> Option<Ref> {
-    let properties = GradientProperties::try_from_paint(paint).unwrap();
+    let properties = GradientProperties::try_from_paint(paint, bbox).unwrap();
     if properties.stops.iter().any(|stop| stop.opacity().get() < 1.0) {
         Some(shading_soft_mask(&properties, chunk, ctx, bbox))
     } else {
@@ -114,13 +211,13 @@ fn shading_soft_mask(
     let mut x_object = chunk.form_xobject(x_object_id, &content_stream);
     rc.finish(&mut x_object.resources());
 
-    x_object
+    let color_space = x_object
         .group()
         .transparency()
         .isolated(false)
         .knockout(false)
-        .color_space()
-        .icc_based(ctx.sgray_ref());
+        .color_space();
+    ctx.write_gray_color_space(color_space);
 
     if ctx.options.compress {
         x_object.filter(Filter::FlateDecode);
@@ -146,18 +243,29 @@ fn shading_function(
     use_opacities: bool,
 ) -> Ref {
     let shading_ref = ctx.alloc_ref();
-    let function_ref = function(&properties.stops, chunk, ctx, use_opacities);
+    let mut function_ref = function(&properties.stops, chunk, ctx, use_opacities);
+    if properties.domain != [0.0, 1.0] {
+        function_ref = spread_function(
+            function_ref,
+            properties.domain,
+            properties.spread_method,
+            chunk,
+            ctx,
+            if use_opacities { 1 } else { 3 },
+        );
+    }
 
     let mut shading = chunk.function_shading(shading_ref);
     shading.shading_type(properties.shading_type);
     if use_opacities {
-        shading.color_space().icc_based(ctx.sgray_ref());
+        ctx.write_gray_color_space(shading.color_space());
     } else {
-        shading.color_space().icc_based(ctx.srgb_ref());
+        ctx.write_rgb_color_space(shading.color_space());
     }
 
     shading.function(function_ref);
     shading.coords(properties.coords.iter().copied());
+    shading.domain(properties.domain);
     shading.extend([true, true]);
     shading.finish();
     shading_ref
@@ -248,6 +356,37 @@ fn stitching_function<const COUNT: usize>(
     reference
 }
 
+/// Repeat a function with the domain `[0, 1]` for each unit of the given
+/// domain, mirroring every second repetition for reflecting gradients.
+fn spread_function(
+    function_ref: Ref,
+    domain: [f32; 2],
+    spread_method: SpreadMethod,
+    chunk: &mut Chunk,
+    ctx: &mut Context,
+    count: usize,
+) -> Ref {
+    let reference = ctx.alloc_ref();
+    let [start, end] = domain.map(|d| d as i32);
+
+    let mut encode = vec![];
+    for period in start..end {
+        if spread_method == SpreadMethod::Reflect && period.rem_euclid(2) == 1 {
+            encode.extend([1.0, 0.0]);
+        } else {
+            encode.extend([0.0, 1.0]);
+        }
+    }
+
+    let mut stitching_function = chunk.stitching_function(reference);
+    stitching_function.domain(domain);
+    stitching_function.range(get_function_range(count));
+    stitching_function.functions(std::iter::repeat(function_ref).take(encode.len() / 2));
+    stitching_function.bounds((start + 1..end).map(|b| b as f32));
+    stitching_function.encode(encode);
+    reference
+}
+
 fn exponential_function<const COUNT: usize>(
     first_stop: &Stop<COUNT>,
     second_stop: &Stop<COUNT>,
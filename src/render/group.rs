@@ -1,7 +1,7 @@
 use crate::ConversionError::UnknownError;
-use pdf_writer::{Chunk, Content, Filter, Finish, Ref};
+use pdf_writer::{Chunk, Content, Filter, Finish, Name, Ref};
 use std::ops::Mul;
-use usvg::{Opacity, Transform};
+use usvg::{BlendMode, Node, Opacity, Paint, Transform};
 
 #[cfg(feature = "filters")]
 use super::filter;
@@ -20,27 +20,88 @@ pub fn render(
     accumulated_transform: Transform,
     initial_opacity: Option<Opacity>,
     rc: &mut ResourceContainer,
+) -> Result<()> {
+    render_group(
+        group,
+        chunk,
+        content,
+        ctx,
+        accumulated_transform,
+        initial_opacity,
+        rc,
+        false,
+    )
+}
+
+/// Render the root group of a tree like [`render`], but draw each of its child
+/// groups as a layer. A layer is an optional content group that can be toggled
+/// in viewers, and its content is written into a Form XObject of its own so
+/// that it can be replaced without touching the rest of the page.
+pub fn render_layers(
+    root: &usvg::Group,
+    chunk: &mut Chunk,
+    content: &mut Content,
+    ctx: &mut Context,
+    accumulated_transform: Transform,
+    rc: &mut ResourceContainer,
+) -> Result<()> {
+    render_group(root, chunk, content, ctx, accumulated_transform, None, rc, true)
+}
+
+/// Render a group, drawing its child groups as layers if `layers` is set.
+#[allow(clippy::too_many_arguments)]
+fn render_group(
+    group: &usvg::Group,
+    chunk: &mut Chunk,
+    content: &mut Content,
+    ctx: &mut Context,
+    accumulated_transform: Transform,
+    initial_opacity: Option<Opacity>,
+    rc: &mut ResourceContainer,
+    layers: bool,
 ) -> Result<()> {
     #[cfg(feature = "filters")]
     if !group.filters().is_empty() {
-        return filter::render(group, chunk, content, ctx, rc);
+        if layers {
+            ctx.warn(
+                "The root group has a filter, so it can't be split into layers."
+                    .to_string(),
+            );
+        }
+        return filter::render(group, chunk, content, ctx, accumulated_transform, rc);
     }
 
     #[cfg(not(feature = "filters"))]
     if !group.filters().is_empty() {
-        log::warn!(
-            "Failed convert filter because the filters feature was disabled. Skipping."
-        )
+        return Err(crate::ConversionError::MissingFeature("filters"));
     }
 
     let initial_opacity = initial_opacity.unwrap_or(Opacity::ONE);
+    let opacity = group.opacity().mul(initial_opacity);
 
-    if group.is_isolated() || initial_opacity.get() != 1.0 {
+    if opacity != Opacity::ONE && can_apply_opacity_directly(group) {
+        // Cheaper than a transparency group, since no XObject is needed.
+        content.save_state();
+        let gs_ref = ctx.alloc_ref();
+        chunk
+            .ext_graphics(gs_ref)
+            .non_stroking_alpha(opacity.get())
+            .stroking_alpha(opacity.get());
+        content.set_parameters(rc.add_graphics_state(gs_ref).to_pdf_name());
+        create_to_stream(group, chunk, content, ctx, accumulated_transform, rc, layers)?;
+        content.restore_state();
+    } else if group.is_isolated() || initial_opacity.get() != 1.0 {
+        // The clip path is applied inside of the XObject, while the mask and
+        // the opacity are applied when drawing it, so they act on the clipped
+        // content as a whole. Since the soft mask and the alpha of the graphics
+        // state are reset at the start of a transparency group, a clip path that
+        // is drawn as a soft mask inside of the XObject doesn't replace the mask
+        // of the group either.
         content.save_state();
         let gs_ref = ctx.alloc_ref();
         let mut gs = chunk.ext_graphics(gs_ref);
-        gs.non_stroking_alpha(group.opacity().mul(initial_opacity).get())
-            .stroking_alpha(group.opacity().mul(initial_opacity).get())
+        gs.non_stroking_alpha(opacity.get())
+            .stroking_alpha(opacity.get())
             .blend_mode(group.blend_mode().to_pdf_blend_mode());
 
         gs.finish();
@@ -62,23 +123,107 @@ pub fn render(
         // XObject, it will be mapped to the coordinate space of where the XObject was invoked, meaning
         // that it will also be affected by the transforms in the content stream. If we passed on the
         // accumulated transform, they would be applied twice.
-        let x_ref = create_x_object(group, chunk, ctx, Transform::default())?;
+        let x_ref = create_x_object(group, chunk, ctx, Transform::default(), layers)?;
         let x_name = rc.add_x_object(x_ref);
         content.x_object(x_name.to_pdf_name());
         content.restore_state();
     } else {
-        create_to_stream(group, chunk, content, ctx, accumulated_transform, rc)?;
+        create_to_stream(group, chunk, content, ctx, accumulated_transform, rc, layers)?;
+    }
+
+    Ok(())
+}
+
+/// Draw a group as a layer.
+fn render_layer(
+    group: &usvg::Group,
+    chunk: &mut Chunk,
+    content: &mut Content,
+    ctx: &mut Context,
+    accumulated_transform: Transform,
+    rc: &mut ResourceContainer,
+) -> Result<()> {
+    // Groups without a bounding box can't be put into an XObject, so they are
+    // drawn like any other content.
+    let Some(bbox) = group.layer_bounding_box().transform(group.transform()) else {
+        return render(group, chunk, content, ctx, accumulated_transform, None, rc);
+    };
+
+    // Same as for transparency groups, the accumulated transform is reset
+    // for the XObject.
+    let x_ref = ctx.alloc_ref();
+    let mut x_rc = ResourceContainer::new();
+    let mut x_content = Content::new();
+    render(group, chunk, &mut x_content, ctx, Transform::default(), None, &mut x_rc)?;
+    let content_stream = ctx.finish_content(x_content);
+
+    let mut x_object = chunk.form_xobject(x_ref, &content_stream);
+    x_rc.finish(&mut x_object.resources());
+    if ctx.options.compress {
+        x_object.filter(Filter::FlateDecode);
     }
+    x_object.bbox(bbox.to_pdf_rect());
+    x_object.finish();
+
+    let layer_name = if group.id().is_empty() {
+        format!("Layer {}", ctx.layers.len() + 1)
+    } else {
+        group.id().to_string()
+    };
+    let oc_ref = ctx.add_layer(chunk, &layer_name);
+
+    begin_layer(content, rc, oc_ref);
+    content.x_object(rc.add_x_object(x_ref).to_pdf_name());
+    content.end_marked_content();
 
     Ok(())
 }
 
+/// Begin a marked-content sequence that belongs to a layer. It needs to be
+/// closed with `end_marked_content`.
+pub fn begin_layer(content: &mut Content, rc: &mut ResourceContainer, layer: Ref) {
+    // The property list is referenced by name, so it can't be written with
+    // `begin_marked_content_with_properties`, which writes it inline.
+    let oc_name = rc.add_properties(layer);
+    content.op("BDC").operand(Name(b"OC")).operand(oc_name.to_pdf_name());
+}
+
+/// Whether the opacity of a group can be set directly in the graphics state
+/// instead of rendering the group as a transparency group. This is the case if
+/// the group only contains a single path with either a fill or a stroke (but not
+/// both, since they would overlap) that doesn't set an opacity of its own.
+fn can_apply_opacity_directly(group: &usvg::Group) -> bool {
+    if group.isolate()
+        || group.mask().is_some()
+        || group.clip_path().is_some()
+        || group.blend_mode() != BlendMode::Normal
+    {
+        return false;
+    }
+
+    let [Node::Path(ref path)] = group.children() else {
+        return false;
+    };
+
+    // Patterns are drawn with their own graphics state.
+    let is_plain = |paint: &Paint, opacity: Opacity| {
+        opacity == Opacity::ONE && !matches!(paint, Paint::Pattern(_))
+    };
+
+    match (path.fill(), path.stroke()) {
+        (Some(fill), None) => is_plain(fill.paint(), fill.opacity()),
+        (None, Some(stroke)) => is_plain(stroke.paint(), stroke.opacity()),
+        _ => false,
+    }
+}
+
 /// Turn a group into an XObject.
 fn create_x_object(
     group: &usvg::Group,
     chunk: &mut Chunk,
     ctx: &mut Context,
     accumulated_transform: Transform,
+    layers: bool,
 ) -> Result<Ref> {
     let x_ref = ctx.alloc_ref();
     let mut rc = ResourceContainer::new();
@@ -91,7 +236,15 @@ fn create_x_object(
 
     let mut content = Content::new();
 
-    create_to_stream(group, chunk, &mut content, ctx, accumulated_transform, &mut rc)?;
+    create_to_stream(
+        group,
+        chunk,
+        &mut content,
+        ctx,
+        accumulated_transform,
+        &mut rc,
+        layers,
+    )?;
 
     let content_stream = ctx.finish_content(content);
 
@@ -102,13 +255,13 @@ fn create_x_object(
         x_object.filter(Filter::FlateDecode);
     }
 
-    x_object
+    let color_space = x_object
         .group()
         .transparency()
         .isolated(group.is_isolated())
         .knockout(false)
-        .color_space()
-        .icc_based(ctx.srgb_ref());
+        .color_space();
+    ctx.write_rgb_color_space(color_space);
 
     x_object.bbox(pdf_bbox);
     x_object.finish();
@@ -117,7 +270,8 @@ fn create_x_object(
 }
 
 /// Write a group into a content stream. Opacities will be ignored. If opacities are needed,
-/// you should use the `create` method instead.
+/// you should use the `create` method instead. If `layers` is set, the child groups
+/// are drawn as layers.
 fn create_to_stream(
     group: &usvg::Group,
     chunk: &mut Chunk,
@@ -125,6 +279,7 @@ fn create_to_stream(
     ctx: &mut Context,
     accumulated_transform: Transform,
     rc: &mut ResourceContainer,
+    layers: bool,
 ) -> Result<()> {
     content.save_state();
     content.transform(group.transform().to_pdf_transform());
@@ -135,7 +290,13 @@ fn create_to_stream(
     }
 
     for child in group.children() {
-        child.render(chunk, content, ctx, accumulated_transform, rc)?;
+        match child {
+            Node::Group(ref child) if layers => {
+                render_layer(child, chunk, content, ctx, accumulated_transform, rc)?
+            }
+            _ => child.render(chunk, content, ctx, accumulated_transform, rc)?,
+        }
+        ctx.check_output_size(chunk.len())?;
     }
 
     content.restore_state();
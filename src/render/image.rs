@@ -1,21 +1,26 @@
+use std::io::Cursor;
 use std::rc::Rc;
 
 use crate::ConversionError::InvalidImage;
-use image::{ColorType, DynamicImage, ImageFormat, Luma, Rgb, Rgba};
-use miniz_oxide::deflate::{compress_to_vec_zlib, CompressionLevel};
-use pdf_writer::{Chunk, Content, Filter, Finish};
-use usvg::{ImageKind, Rect, Size, Transform, Tree};
+use image::codecs::{gif::GifDecoder, jpeg::JpegDecoder, png::PngDecoder};
+use image::{ColorType, DynamicImage, ImageDecoder, ImageFormat, Luma, Rgb, Rgba};
+use pdf_writer::{Chunk, Content, Filter, Finish, Name, Str};
+use usvg::{ImageKind, ImageRendering, Rect, Size, Transform, Tree};
 
 use crate::render::tree_to_xobject;
 use crate::util::context::Context;
-use crate::util::helper::{NameExt, TransformExt};
+use crate::util::helper::{encode_binary, NameExt, TransformExt};
 use crate::util::resources::ResourceContainer;
 use crate::Result;
 
 /// Render an image into a content stream.
+///
+/// Raster images are interpolated when they are scaled, unless the rendering
+/// mode is `optimizeSpeed`.
 pub fn render(
     is_visible: bool,
     kind: &ImageKind,
+    rendering_mode: ImageRendering,
     view_box: Option<Rect>,
     chunk: &mut Chunk,
     content: &mut Content,
@@ -26,9 +31,25 @@ pub fn render(
         return Ok(());
     }
 
+    // Decoding an image can take up far more memory than the encoded data, so the
+    // budget is checked before doing so.
+    if let Some(decoded_size) = decoded_size(kind) {
+        ctx.check_allocation(chunk, decoded_size)?;
+    }
+
+    // JPEGs are embedded as-is, so the orientation stored in their EXIF data needs to be
+    // applied when placing the image. This corresponds to `image-orientation: from-image`,
+    // `none` can't be honored since usvg doesn't preserve the property.
+    let orientation = match kind {
+        ImageKind::JPEG(content) => exif_orientation(content),
+        _ => Transform::default(),
+    };
+
     // Will return the name of the image (in the Resources dictionary) and the dimensions of the
     // actual image (i.e. the actual image size, not the size in the PDF, which will always be 1x1
     // because that's how ImageXObjects are scaled by default.
+    let interpolate = rendering_mode == ImageRendering::OptimizeQuality;
+
     let (image_name, image_size) = match kind {
         ImageKind::JPEG(content) => {
             let dynamic_image =
@@ -39,50 +60,75 @@ pub fn render(
                 chunk,
                 ctx,
                 content,
-                Filter::DctDecode,
+                Some(Filter::DctDecode),
                 &dynamic_image,
                 None,
+                interpolate,
                 rc,
             )
         }
-        ImageKind::PNG(content) => {
-            let dynamic_image =
-                image::load_from_memory_with_format(content, ImageFormat::Png)
-                    .map_err(|_| InvalidImage)?;
-            // Alpha channels need to be written separately as a soft mask, hence the extra processing
-            // step.
-            let (samples, filter, alpha_mask) = handle_transparent_image(&dynamic_image);
-            create_raster_image(
-                chunk,
-                ctx,
-                &samples,
-                filter,
-                &dynamic_image,
-                alpha_mask.as_deref(),
-                rc,
-            )
-        }
-        ImageKind::GIF(content) => {
-            let dynamic_image =
-                image::load_from_memory_with_format(content, ImageFormat::Gif)
-                    .map_err(|_| InvalidImage)?;
-            // Alpha channels need to be written separately as a soft mask, hence the extra processing
-            // step.
-            let (samples, filter, alpha_mask) = handle_transparent_image(&dynamic_image);
-            create_raster_image(
-                chunk,
-                ctx,
-                &samples,
-                filter,
-                &dynamic_image,
-                alpha_mask.as_deref(),
-                rc,
-            )
-        }
+        // Paletted images are embedded with an indexed color space, so that they
+        // don't need to be expanded to full RGB.
+        ImageKind::PNG(content) => match decode_indexed_png(content) {
+            Some(indexed) => create_indexed_image(chunk, ctx, &indexed, interpolate, rc),
+            None => {
+                let dynamic_image =
+                    image::load_from_memory_with_format(content, ImageFormat::Png)
+                        .map_err(|_| InvalidImage)?;
+                // Alpha channels need to be written separately as a soft mask, hence the extra processing
+                // step.
+                let (samples, filter, alpha_mask) =
+                    handle_transparent_image(&dynamic_image, ctx);
+                create_raster_image(
+                    chunk,
+                    ctx,
+                    &samples,
+                    filter,
+                    &dynamic_image,
+                    alpha_mask.as_deref(),
+                    interpolate,
+                    rc,
+                )
+            }
+        },
+        // Paletted images are embedded with an indexed color space, so that they
+        // don't need to be expanded to full RGB.
+        ImageKind::GIF(content) => match decode_indexed_gif(content) {
+            Some(indexed) => create_indexed_image(chunk, ctx, &indexed, interpolate, rc),
+            None => {
+                let dynamic_image =
+                    image::load_from_memory_with_format(content, ImageFormat::Gif)
+                        .map_err(|_| InvalidImage)?;
+                // Alpha channels need to be written separately as a soft mask, hence the extra processing
+                // step.
+                let (samples, filter, alpha_mask) =
+                    handle_transparent_image(&dynamic_image, ctx);
+                create_raster_image(
+                    chunk,
+                    ctx,
+                    &samples,
+                    filter,
+                    &dynamic_image,
+                    alpha_mask.as_deref(),
+                    interpolate,
+                    rc,
+                )
+            }
+        },
         // SVGs just get rendered recursively.
         ImageKind::SVG(tree) => create_svg_image(tree, chunk, ctx, rc)?,
     };
 
+    // Orientations 5 to 8 rotate the image by 90 degrees, which swaps its width and
+    // height. Note that usvg lays out the image with the size that is stored in the
+    // file, so an image with an explicit width and height is still fitted into them
+    // as if it wasn't rotated.
+    let image_size = if orientation.has_skew() {
+        Size::from_wh(image_size.height(), image_size.width()).unwrap()
+    } else {
+        image_size
+    };
+
     let view_box = view_box.unwrap_or(
         Rect::from_xywh(0.0, 0.0, image_size.width(), image_size.height()).unwrap(),
     );
@@ -96,15 +142,15 @@ pub fn render(
 
     // Scale the image from 1x1 to the actual dimensions.
     content.transform(
-        Transform::from_row(
-            view_box.width(),
-            0.0,
-            0.0,
-            -view_box.height(),
-            0.0,
-            view_box.height(),
-        )
-        .to_pdf_transform(),
+        Transform::from_scale(view_box.width(), view_box.height()).to_pdf_transform(),
+    );
+
+    // Rotate/mirror the unit square according to the orientation of the image and then
+    // flip it, since images in PDF are drawn from bottom to top.
+    content.transform(
+        orientation
+            .pre_concat(Transform::from_row(1.0, 0.0, 0.0, -1.0, 0.0, 1.0))
+            .to_pdf_transform(),
     );
     content.x_object(image_name.to_pdf_name());
     content.restore_state();
@@ -112,7 +158,87 @@ pub fn render(
     Ok(())
 }
 
-fn handle_transparent_image(image: &DynamicImage) -> (Vec<u8>, Filter, Option<Vec<u8>>) {
+/// Read the EXIF orientation of a JPEG and return the transform that needs to be
+/// applied to the unit square the image is drawn into so that it appears upright.
+fn exif_orientation(data: &[u8]) -> Transform {
+    let ts = |sx, ky, kx, sy, tx, ty| Transform::from_row(sx, ky, kx, sy, tx, ty);
+
+    match read_exif_orientation(data) {
+        Some(2) => ts(-1.0, 0.0, 0.0, 1.0, 1.0, 0.0),
+        Some(3) => ts(-1.0, 0.0, 0.0, -1.0, 1.0, 1.0),
+        Some(4) => ts(1.0, 0.0, 0.0, -1.0, 0.0, 1.0),
+        Some(5) => ts(0.0, 1.0, 1.0, 0.0, 0.0, 0.0),
+        Some(6) => ts(0.0, 1.0, -1.0, 0.0, 1.0, 0.0),
+        Some(7) => ts(0.0, -1.0, -1.0, 0.0, 1.0, 1.0),
+        Some(8) => ts(0.0, -1.0, 1.0, 0.0, 0.0, 1.0),
+        _ => Transform::default(),
+    }
+}
+
+/// Find the value of the orientation tag in the EXIF segment of a JPEG, if any.
+fn read_exif_orientation(data: &[u8]) -> Option<u16> {
+    const ORIENTATION_TAG: u16 = 0x0112;
+
+    let read_u16 = |bytes: &[u8], at: usize, big_endian: bool| {
+        let bytes: [u8; 2] = bytes.get(at..at + 2)?.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+
+    let read_u32 = |bytes: &[u8], at: usize, big_endian: bool| {
+        let bytes: [u8; 4] = bytes.get(at..at + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    // Walk through the segments until we find the APP1 segment containing the EXIF data.
+    let mut pos = 2;
+    let exif = loop {
+        let marker = read_u16(data, pos, true)?;
+        let len = read_u16(data, pos + 2, true)? as usize;
+        let segment = data.get(pos + 4..pos + 2 + len)?;
+
+        match marker {
+            0xFFE1 if segment.starts_with(b"Exif\0\0") => break &segment[6..],
+            // Start of scan, no more metadata will follow.
+            0xFFDA => return None,
+            _ => pos += 2 + len,
+        }
+    };
+
+    let big_endian = match exif.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+
+    let ifd = read_u32(exif, 4, big_endian)? as usize;
+    let entries = read_u16(exif, ifd, big_endian)? as usize;
+
+    (0..entries).find_map(|i| {
+        let entry = ifd + 2 + i * 12;
+        if read_u16(exif, entry, big_endian)? == ORIENTATION_TAG {
+            read_u16(exif, entry + 8, big_endian)
+        } else {
+            None
+        }
+    })
+}
+
+fn handle_transparent_image(
+    image: &DynamicImage,
+    ctx: &Context,
+) -> (Vec<u8>, Option<Filter>, Option<Vec<u8>>) {
     let color = image.color();
     let bits = color.bits_per_pixel();
     let channels = color.channel_count() as u16;
@@ -157,28 +283,29 @@ fn handle_transparent_image(image: &DynamicImage) -> (Vec<u8>, Filter, Option<Ve
         None
     };
 
-    let compression_level = CompressionLevel::DefaultLevel as u8;
-    let compressed_image = compress_to_vec_zlib(&encoded_image, compression_level);
-    let compressed_mask =
-        encoded_mask.map(|m| compress_to_vec_zlib(&m, compression_level));
+    let (compressed_image, filter) = encode_binary(&encoded_image, &ctx.options);
+    let compressed_mask = encoded_mask.map(|m| encode_binary(&m, &ctx.options).0);
 
-    (compressed_image, Filter::FlateDecode, compressed_mask)
+    (compressed_image, filter, compressed_mask)
 }
 
 fn create_raster_image(
     chunk: &mut Chunk,
     ctx: &mut Context,
     samples: &[u8],
-    filter: Filter,
+    filter: Option<Filter>,
     dynamic_image: &DynamicImage,
     alpha_mask: Option<&[u8]>,
+    interpolate: bool,
     rc: &mut ResourceContainer,
 ) -> (Rc<String>, Size) {
     let color = dynamic_image.color();
     let alpha_mask = alpha_mask.map(|mask_bytes| {
         let soft_mask_id = ctx.alloc_ref();
         let mut s_mask = chunk.image_xobject(soft_mask_id, mask_bytes);
-        s_mask.filter(filter);
+        if let Some(filter) = filter {
+            s_mask.filter(filter);
+        }
         s_mask.width(dynamic_image.width() as i32);
         s_mask.height(dynamic_image.height() as i32);
         s_mask.color_space().device_gray();
@@ -193,7 +320,9 @@ fn create_raster_image(
     let image_name = rc.add_x_object(image_ref);
 
     let mut image_x_object = chunk.image_xobject(image_ref, samples);
-    image_x_object.filter(filter);
+    if let Some(filter) = filter {
+        image_x_object.filter(filter);
+    }
     image_x_object.width(dynamic_image.width() as i32);
     image_x_object.height(dynamic_image.height() as i32);
 
@@ -205,6 +334,180 @@ fn create_raster_image(
     }
 
     image_x_object.bits_per_component(calculate_bits_per_component(color));
+    image_x_object.interpolate(interpolate);
+    if let Some(soft_mask_id) = alpha_mask {
+        image_x_object.s_mask(soft_mask_id);
+    }
+    image_x_object.finish();
+    (image_name, image_size)
+}
+
+/// A paletted image whose samples are indices into its palette.
+struct IndexedImage {
+    width: u32,
+    height: u32,
+    bits_per_component: u8,
+    /// The indices, packed according to the bit depth, with each row starting
+    /// at a byte boundary.
+    samples: Vec<u8>,
+    /// The RGB triples of the palette.
+    palette: Vec<u8>,
+    /// The alpha value of each palette entry, if any entry isn't opaque.
+    alpha: Option<Vec<u8>>,
+}
+
+/// The number of bytes a raster image takes up once it is decoded, as stated in
+/// its header.
+fn decoded_size(kind: &ImageKind) -> Option<u64> {
+    let size = match kind {
+        ImageKind::JPEG(data) => {
+            JpegDecoder::new(Cursor::new(data.as_slice())).ok()?.total_bytes()
+        }
+        ImageKind::PNG(data) => {
+            PngDecoder::new(Cursor::new(data.as_slice())).ok()?.total_bytes()
+        }
+        ImageKind::GIF(data) => {
+            GifDecoder::new(Cursor::new(data.as_slice())).ok()?.total_bytes()
+        }
+        ImageKind::SVG(_) => return None,
+    };
+    Some(size)
+}
+
+/// Decode a PNG without expanding it, if it uses a palette.
+fn decode_indexed_png(data: &[u8]) -> Option<IndexedImage> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::IDENTITY);
+    let mut reader = decoder.read_info().ok()?;
+
+    let info = reader.info();
+    if info.color_type != png::ColorType::Indexed {
+        return None;
+    }
+
+    let palette = info.palette.as_deref()?.to_vec();
+    let alpha = info.trns.as_deref().map(|trns| {
+        // Entries without a tRNS value are opaque.
+        let mut alpha = vec![u8::MAX; palette.len() / 3];
+        for (a, &t) in alpha.iter_mut().zip(trns) {
+            *a = t;
+        }
+        alpha
+    });
+    let bits_per_component = info.bit_depth as u8;
+
+    let mut samples = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut samples).ok()?;
+    samples.truncate(frame.buffer_size());
+
+    Some(IndexedImage {
+        width: frame.width,
+        height: frame.height,
+        bits_per_component,
+        samples,
+        palette,
+        alpha: alpha.filter(|alpha| alpha.iter().any(|&a| a != u8::MAX)),
+    })
+}
+
+/// Decode the first frame of a GIF without expanding it, if it covers the
+/// whole image.
+fn decode_indexed_gif(data: &[u8]) -> Option<IndexedImage> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(data).ok()?;
+
+    let width = decoder.width();
+    let height = decoder.height();
+    let global_palette = decoder.global_palette().map(<[u8]>::to_vec);
+    let frame = decoder.read_next_frame().ok()??;
+
+    // Frames that only cover a part of the image leave the rest transparent,
+    // so they are left to the regular path.
+    if frame.left != 0 || frame.top != 0 || frame.width != width || frame.height != height
+    {
+        return None;
+    }
+
+    let palette = frame.palette.clone().or(global_palette)?;
+    let alpha = frame.transparent.map(|transparent| {
+        let mut alpha = vec![u8::MAX; palette.len() / 3];
+        if let Some(a) = alpha.get_mut(transparent as usize) {
+            *a = 0;
+        }
+        alpha
+    });
+
+    Some(IndexedImage {
+        width: width as u32,
+        height: height as u32,
+        bits_per_component: 8,
+        samples: frame.buffer.to_vec(),
+        palette,
+        alpha,
+    })
+}
+
+fn create_indexed_image(
+    chunk: &mut Chunk,
+    ctx: &mut Context,
+    image: &IndexedImage,
+    interpolate: bool,
+    rc: &mut ResourceContainer,
+) -> (Rc<String>, Size) {
+    // PDF doesn't support transparent palette entries, so the alpha values
+    // are looked up for each pixel and written as a soft mask.
+    let alpha_mask = image.alpha.as_ref().map(|alpha| {
+        let bits = image.bits_per_component as usize;
+        let row_len = (image.width as usize * bits + 7) / 8;
+        let mask: Vec<u8> = image
+            .samples
+            .chunks(row_len)
+            .flat_map(|row| {
+                (0..image.width as usize).map(move |x| {
+                    let bit = x * bits;
+                    let index =
+                        (row[bit / 8] >> (8 - bits - bit % 8)) & ((1 << bits) - 1) as u8;
+                    alpha.get(index as usize).copied().unwrap_or(u8::MAX)
+                })
+            })
+            .collect();
+
+        let soft_mask_id = ctx.alloc_ref();
+        let (encoded, filter) = encode_binary(&mask, &ctx.options);
+        let mut s_mask = chunk.image_xobject(soft_mask_id, &encoded);
+        if let Some(filter) = filter {
+            s_mask.filter(filter);
+        }
+        s_mask.width(image.width as i32);
+        s_mask.height(image.height as i32);
+        s_mask.color_space().device_gray();
+        s_mask.bits_per_component(8);
+        soft_mask_id
+    });
+
+    let image_size = Size::from_wh(image.width as f32, image.height as f32).unwrap();
+    let image_ref = ctx.alloc_ref();
+    let image_name = rc.add_x_object(image_ref);
+
+    let (encoded, filter) = encode_binary(&image.samples, &ctx.options);
+    let mut image_x_object = chunk.image_xobject(image_ref, &encoded);
+    if let Some(filter) = filter {
+        image_x_object.filter(filter);
+    }
+    image_x_object.width(image.width as i32);
+    image_x_object.height(image.height as i32);
+
+    let hival = (image.palette.len() / 3).saturating_sub(1) as i32;
+    let mut color_space = image_x_object.insert(Name(b"ColorSpace")).array();
+    color_space.item(Name(b"Indexed"));
+    color_space.item(Name(b"DeviceRGB"));
+    color_space.item(hival);
+    color_space.item(Str(&image.palette));
+    color_space.finish();
+
+    image_x_object.bits_per_component(image.bits_per_component as i32);
+    image_x_object.interpolate(interpolate);
     if let Some(soft_mask_id) = alpha_mask {
         image_x_object.s_mask(soft_mask_id);
     }
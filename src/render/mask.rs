@@ -66,13 +66,13 @@ pub fn create(
         x_object.filter(Filter::FlateDecode);
     }
 
-    x_object
+    let color_space = x_object
         .group()
         .transparency()
         .isolated(false)
         .knockout(false)
-        .color_space()
-        .icc_based(ctx.srgb_ref());
+        .color_space();
+    ctx.write_rgb_color_space(color_space);
 
     x_object.bbox(rect.to_pdf_rect());
     x_object.finish();
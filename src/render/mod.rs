@@ -1,5 +1,5 @@
-use pdf_writer::{Chunk, Content, Filter, Finish, Ref};
-use usvg::{Node, Transform, Tree};
+use pdf_writer::{Chunk, Content, Filter, Finish, Name, Ref, TextStr};
+use usvg::{BlendMode, Group, ImageKind, Node, Opacity, Paint, Transform, Tree};
 
 use crate::util::context::Context;
 use crate::util::helper::{RectExt, TransformExt};
@@ -27,6 +27,23 @@ pub fn tree_to_stream(
     content: &mut Content,
     ctx: &mut Context,
     rc: &mut ResourceContainer,
+) -> Result<()> {
+    // The soft masks of complex clip paths are cached by the address of the clip
+    // path, which is only unique while its tree is alive. So each tree starts with
+    // an empty cache and the one of an enclosing tree is restored afterwards.
+    let clip_paths = std::mem::take(&mut ctx.clip_paths);
+    let result = render_tree(tree, chunk, content, ctx, rc);
+    ctx.clip_paths = clip_paths;
+    result
+}
+
+/// Write a tree into a stream, see [`tree_to_stream`].
+fn render_tree(
+    tree: &Tree,
+    chunk: &mut Chunk,
+    content: &mut Content,
+    ctx: &mut Context,
+    rc: &mut ResourceContainer,
 ) -> Result<()> {
     content.save_state();
 
@@ -36,12 +53,68 @@ pub fn tree_to_stream(
 
     content.transform(initial_transform.to_pdf_transform());
 
-    group::render(tree.root(), chunk, content, ctx, initial_transform, None, rc)?;
+    if ctx.options.flatten_transparency && has_transparency(tree.root()) {
+        #[cfg(feature = "filters")]
+        filter::render_flattened(tree, chunk, content, ctx, rc)?;
+        #[cfg(not(feature = "filters"))]
+        return Err(crate::ConversionError::MissingFeature("filters"));
+    } else if ctx.options.raster_fallback {
+        #[cfg(feature = "filters")]
+        filter::render_with_raster_fallback(
+            tree,
+            chunk,
+            content,
+            ctx,
+            initial_transform,
+            rc,
+        )?;
+        #[cfg(not(feature = "filters"))]
+        return Err(crate::ConversionError::MissingFeature("filters"));
+    } else if ctx.options.layers_from_groups {
+        group::render_layers(tree.root(), chunk, content, ctx, initial_transform, rc)?;
+    } else {
+        group::render(tree.root(), chunk, content, ctx, initial_transform, None, rc)?;
+    }
+
     content.restore_state();
 
     Ok(())
 }
 
+/// Write a tree on top of an existing stream under a transform, without any of
+/// the page-level options like transparency flattening, raster fallback or
+/// layers. This is used for overlays like watermarks, which should always be
+/// drawn as plain vector content.
+#[cfg(feature = "text")]
+pub fn overlay_to_stream(
+    tree: &Tree,
+    chunk: &mut Chunk,
+    content: &mut Content,
+    ctx: &mut Context,
+    rc: &mut ResourceContainer,
+    transform: Transform,
+) -> Result<()> {
+    let clip_paths = std::mem::take(&mut ctx.clip_paths);
+    content.save_state();
+
+    // From PDF coordinate system to SVG coordinate system
+    let initial_transform = transform.pre_concat(Transform::from_row(
+        1.0,
+        0.0,
+        0.0,
+        -1.0,
+        0.0,
+        tree.size().height(),
+    ));
+
+    content.transform(initial_transform.to_pdf_transform());
+    let result =
+        group::render(tree.root(), chunk, content, ctx, initial_transform, None, rc);
+    content.restore_state();
+    ctx.clip_paths = clip_paths;
+    result
+}
+
 /// Convert a tree into a XObject of size 1x1, similar to an image.
 pub fn tree_to_xobject(tree: &Tree, chunk: &mut Chunk, ctx: &mut Context) -> Result<Ref> {
     let bbox = tree.size().to_non_zero_rect(0.0, 0.0);
@@ -70,6 +143,53 @@ pub fn tree_to_xobject(tree: &Tree, chunk: &mut Chunk, ctx: &mut Context) -> Res
     Ok(x_ref)
 }
 
+/// Whether rendering a group requires any kind of transparency in the PDF.
+fn has_transparency(group: &Group) -> bool {
+    let paint_has_transparency = |paint: &Paint, opacity: Opacity| {
+        opacity.get() != 1.0
+            || match paint {
+                Paint::Color(_) => false,
+                Paint::LinearGradient(lg) => {
+                    lg.stops().iter().any(|s| s.opacity().get() != 1.0)
+                }
+                Paint::RadialGradient(rg) => {
+                    rg.stops().iter().any(|s| s.opacity().get() != 1.0)
+                }
+                Paint::Pattern(pattern) => has_transparency(pattern.root()),
+            }
+    };
+
+    if group.opacity().get() != 1.0
+        || group.mask().is_some()
+        || group.blend_mode() != BlendMode::Normal
+        || !group.filters().is_empty()
+        || group.clip_path().is_some_and(clip_path::needs_soft_mask)
+    {
+        return true;
+    }
+
+    group.children().iter().any(|child| match child {
+        Node::Group(group) => has_transparency(group),
+        Node::Path(path) => {
+            path.fill()
+                .is_some_and(|f| paint_has_transparency(f.paint(), f.opacity()))
+                || path
+                    .stroke()
+                    .is_some_and(|s| paint_has_transparency(s.paint(), s.opacity()))
+        }
+        Node::Image(image) => match image.kind() {
+            ImageKind::JPEG(_) => false,
+            ImageKind::SVG(tree) => has_transparency(tree.root()),
+            // PNGs and GIFs can have an alpha channel, which is embedded as a soft mask.
+            _ => true,
+        },
+        #[cfg(feature = "text")]
+        Node::Text(text) => has_transparency(text.flattened()),
+        #[cfg(not(feature = "text"))]
+        Node::Text(_) => false,
+    })
+}
+
 trait Render {
     fn render(
         &self,
@@ -90,7 +210,16 @@ impl Render for Node {
         accumulated_transform: Transform,
         rc: &mut ResourceContainer,
     ) -> Result<()> {
-        match self {
+        // Elements with an ID are marked so that their content can be traced
+        // back to the SVG.
+        let tag_id = ctx.options.tag_ids && !self.id().is_empty();
+        if tag_id {
+            content
+                .begin_marked_content_with_properties(Name(b"Span"))
+                .pair(Name(b"ID"), TextStr(self.id()));
+        }
+
+        let result = match self {
             Node::Path(ref path) => {
                 path::render(path, chunk, content, ctx, rc, accumulated_transform)
             }
@@ -101,6 +230,7 @@ impl Render for Node {
             Node::Image(ref image) => image::render(
                 image.is_visible(),
                 image.kind(),
+                image.rendering_mode(),
                 None,
                 chunk,
                 content,
@@ -108,10 +238,29 @@ impl Render for Node {
                 rc,
             ),
             #[cfg(not(feature = "image"))]
-            Node::Image(_) => {
-                log::warn!("Failed convert image because the image feature was disabled. Skipping.");
-                Ok(())
-            }
+            Node::Image(ref image) => match image.kind() {
+                // Nested SVGs don't need to be decoded, so we can still draw them by
+                // rendering their root, clipped to their size.
+                usvg::ImageKind::SVG(tree) if image.is_visible() => {
+                    content.save_state();
+                    content.rect(0.0, 0.0, tree.size().width(), tree.size().height());
+                    content.clip_nonzero();
+                    content.end_path();
+                    group::render(
+                        tree.root(),
+                        chunk,
+                        content,
+                        ctx,
+                        accumulated_transform,
+                        None,
+                        rc,
+                    )?;
+                    content.restore_state();
+                    Ok(())
+                }
+                usvg::ImageKind::SVG(_) => Ok(()),
+                _ => Err(crate::ConversionError::MissingFeature("image")),
+            },
             #[cfg(feature = "text")]
             Node::Text(ref text) => {
                 if ctx.options.embed_text {
@@ -130,9 +279,15 @@ impl Render for Node {
             }
             #[cfg(not(feature = "text"))]
             Node::Text(_) => {
-                log::warn!("Failed convert text because the text feature was disabled. Skipping.");
+                ctx.warn("Failed convert text because the text feature was disabled. Skipping.".to_string());
                 Ok(())
             }
+        };
+
+        if tag_id {
+            content.end_marked_content();
         }
+
+        result
     }
 }
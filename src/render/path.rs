@@ -1,9 +1,10 @@
-use pdf_writer::types::ColorSpaceOperand;
 use pdf_writer::types::ColorSpaceOperand::Pattern;
+use pdf_writer::types::{ColorSpaceOperand, LineCapStyle};
 use pdf_writer::{Chunk, Content, Finish};
-use usvg::tiny_skia_path::PathSegment;
+use std::rc::Rc;
+use usvg::tiny_skia_path::{PathSegment, Point};
 use usvg::Path;
-use usvg::{Fill, FillRule, Opacity, Paint, PaintOrder, Rect};
+use usvg::{Fill, FillRule, LineCap, Opacity, Paint, PaintOrder, Rect};
 use usvg::{Stroke, Transform};
 
 use super::{gradient, pattern};
@@ -12,6 +13,9 @@ use crate::util::helper::{ColorExt, LineCapExt, LineJoinExt, NameExt};
 use crate::util::resources::ResourceContainer;
 use crate::Result;
 
+/// The approximate size of a single point of a path in a content stream.
+const POINT_SIZE: usize = 16;
+
 /// Render a path into a content stream.
 pub fn render(
     path: &Path,
@@ -25,6 +29,11 @@ pub fn render(
         return Ok(());
     }
 
+    // The content stream is only measured once it's finished, so until then, its
+    // size is estimated to enforce the output limit.
+    let paints = path.fill().is_some() as usize + path.stroke().is_some() as usize;
+    ctx.pending_content += paints * path.data().points().len() * POINT_SIZE;
+
     // In order to support different stroke and fill orders as well as "advanced" paths
     // such as pattern fills/strokes with opacities and linear gradient strokes/fills with
     // stop opacities, we always render strokes and fills separately, at the cost of slightly
@@ -97,16 +106,43 @@ pub(crate) fn stroke_path(
     rc: &mut ResourceContainer,
     accumulated_transform: Transform,
 ) -> Result<()> {
-    if path.data().bounds().width() == 0.0 && path.data().bounds().height() == 0.0 {
-        return Ok(());
-    }
+    // A degenerate subpath (i.e. one that collapses to a single point) is only visible if it
+    // has round or square caps, in which case a dot with the size of the stroke width is drawn.
+    let (segments, dots) = match path.stroke() {
+        Some(stroke) if stroke.linecap() == LineCap::Square => split_degenerate(path),
+        _ => (vec![], vec![]),
+    };
 
     let operation = |content: &mut Content, stroke: &Stroke| {
-        draw_path(path.data().segments(), content);
-        finish_path(Some(stroke), None, content);
+        if stroke.linecap() != LineCap::Square || dots.is_empty() {
+            draw_path(path.data().segments(), content);
+            finish_path(Some(stroke), None, content);
+            return;
+        }
+
+        if !segments.is_empty() {
+            draw_path(segments.iter().copied(), content);
+            finish_path(Some(stroke), None, content);
+        }
+
+        // PDF only draws dots for degenerate subpaths with round caps, so we emulate
+        // the square caps with lines that are exactly as long as the stroke is wide.
+        let half_width = stroke.width().get() / 2.0;
+        content.set_line_cap(LineCapStyle::ButtCap);
+        for dot in &dots {
+            content.move_to(dot.x - half_width, dot.y);
+            content.line_to(dot.x + half_width, dot.y);
+        }
+        content.stroke();
     };
 
     if let Some(path_stroke) = path.stroke() {
+        // Subpaths without any extent are invisible with butt caps, so we don't
+        // need to emit anything if the path only consists of such subpaths.
+        if path_stroke.linecap() == LineCap::Butt && !has_extent(path) {
+            return Ok(());
+        }
+
         stroke(
             path_stroke,
             chunk,
@@ -115,6 +151,7 @@ pub(crate) fn stroke_path(
             rc,
             operation,
             accumulated_transform,
+            path.abs_transform(),
             path.stroke_bounding_box(),
         )?;
     }
@@ -122,6 +159,67 @@ pub(crate) fn stroke_path(
     Ok(())
 }
 
+/// Whether any subpath of a path covers more than a single point.
+fn has_extent(path: &Path) -> bool {
+    let mut start = None;
+
+    for segment in path.data().segments() {
+        let points = match segment {
+            PathSegment::MoveTo(p) => {
+                start = Some(p);
+                continue;
+            }
+            PathSegment::LineTo(p) => [p, p, p],
+            PathSegment::QuadTo(p1, p2) => [p1, p2, p2],
+            PathSegment::CubicTo(p1, p2, p3) => [p1, p2, p3],
+            PathSegment::Close => continue,
+        };
+
+        if start.is_some_and(|start| points.iter().any(|p| *p != start)) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Split a path into the segments of its subpaths that have an extent and the
+/// points that its degenerate subpaths collapse to.
+fn split_degenerate(path: &Path) -> (Vec<PathSegment>, Vec<Point>) {
+    let segments: Vec<PathSegment> = path.data().segments().collect();
+    let mut extended = vec![];
+    let mut dots = vec![];
+
+    let mut start = 0;
+    while start < segments.len() {
+        let len = segments[start + 1..]
+            .iter()
+            .position(|segment| matches!(segment, PathSegment::MoveTo(_)))
+            .map_or(segments.len() - start, |i| i + 1);
+        let subpath = &segments[start..start + len];
+        match degenerate_point(subpath) {
+            Some(point) => dots.push(point),
+            None => extended.extend_from_slice(subpath),
+        }
+        start += len;
+    }
+
+    (extended, dots)
+}
+
+/// The point a subpath collapses to, if it is degenerate. A lone move isn't
+/// drawn at all, so it doesn't count as a degenerate subpath.
+fn degenerate_point(subpath: &[PathSegment]) -> Option<Point> {
+    let [PathSegment::MoveTo(start), rest @ ..] = subpath else { return None };
+    let mut points = rest.iter().flat_map(|segment| match *segment {
+        PathSegment::MoveTo(p) | PathSegment::LineTo(p) => vec![p],
+        PathSegment::QuadTo(p1, p2) => vec![p1, p2],
+        PathSegment::CubicTo(p1, p2, p3) => vec![p1, p2, p3],
+        PathSegment::Close => vec![],
+    });
+    (!rest.is_empty() && points.all(|p| p == *start)).then_some(*start)
+}
+
 /// Prepare the stroke color and then perform some operation (either drawing text or
 /// drawing a path).
 #[allow(clippy::too_many_arguments)]
@@ -133,6 +231,7 @@ pub(crate) fn stroke(
     rc: &mut ResourceContainer,
     operation: impl Fn(&mut Content, &Stroke),
     accumulated_transform: Transform,
+    abs_transform: Transform,
     bbox: Rect,
 ) -> Result<()> {
     let paint = &stroke.paint();
@@ -142,9 +241,11 @@ pub(crate) fn stroke(
     match paint {
         Paint::Color(c) => {
             set_opacity_gs(chunk, content, ctx, Some(stroke.opacity()), None, rc);
-            let srgb_name = rc.add_color_space(ctx.srgb_ref());
-            let srgb_name = ColorSpaceOperand::Named(srgb_name.to_pdf_name());
-            content.set_stroke_color_space(srgb_name);
+            let srgb_name = rgb_color_space(ctx, rc);
+            content.set_stroke_color_space(match &srgb_name {
+                Some(name) => ColorSpaceOperand::Named(name.to_pdf_name()),
+                None => ColorSpaceOperand::DeviceRgb,
+            });
             content.set_stroke_color(c.to_pdf_color());
         }
         Paint::Pattern(p) => {
@@ -188,6 +289,7 @@ pub(crate) fn stroke(
                 chunk,
                 ctx,
                 &accumulated_transform,
+                bbox,
             );
             let pattern_name = rc.add_pattern(pattern_ref);
             content.set_stroke_color_space(Pattern);
@@ -195,13 +297,13 @@ pub(crate) fn stroke(
         }
     }
 
-    content.set_line_width(stroke.width().get());
+    content.set_line_width(line_width(stroke, ctx, abs_transform));
     content.set_miter_limit(stroke.miterlimit().get());
     content.set_line_cap(stroke.linecap().to_pdf_line_cap());
     content.set_line_join(stroke.linejoin().to_pdf_line_join());
 
-    if let Some(dasharray) = &stroke.dasharray() {
-        content.set_dash_pattern(dasharray.iter().cloned(), stroke.dashoffset());
+    if let Some((dash_array, dash_phase)) = dash_pattern(stroke) {
+        content.set_dash_pattern(dash_array, dash_phase);
     } else {
         content.set_dash_pattern(vec![], 0.0);
     }
@@ -213,6 +315,57 @@ pub(crate) fn stroke(
     Ok(())
 }
 
+/// Determine the line width of a stroke, taking the minimum stroke width of the
+/// conversion options into account.
+fn line_width(stroke: &Stroke, ctx: &Context, abs_transform: Transform) -> f32 {
+    let width = stroke.width().get();
+
+    let Some(min_stroke_width) = ctx.options.min_stroke_width else {
+        return width;
+    };
+
+    // The stroke is scaled by the transform it is drawn with, so we compare the
+    // width after the transform has been applied. For non-uniform scales, we use
+    // the smaller factor so that the stroke doesn't vanish in either direction.
+    let (sx, sy) = abs_transform.get_scale();
+    let scale = sx.min(sy);
+
+    if scale <= 0.0 || !scale.is_finite() || width * scale >= min_stroke_width {
+        return width;
+    }
+
+    min_stroke_width / scale
+}
+
+/// Add the color space that RGB colors are specified in to the resources and
+/// return its name, or `None` if `DeviceRGB` is used.
+fn rgb_color_space(ctx: &mut Context, rc: &mut ResourceContainer) -> Option<Rc<String>> {
+    ctx.options.embed_icc.then(|| rc.add_color_space(ctx.srgb_ref()))
+}
+
+/// Convert the dash array and dash offset of a stroke into the array and phase
+/// of the PDF `d` operator. Returns `None` if the stroke should be solid.
+fn dash_pattern(stroke: &Stroke) -> Option<(Vec<f32>, f32)> {
+    let mut dash_array = stroke.dasharray()?.to_vec();
+
+    // An odd number of values is repeated to yield an even number of values.
+    if dash_array.len() % 2 == 1 {
+        dash_array.extend_from_within(..);
+    }
+
+    // A dash array that only consists of zeros renders as a solid line.
+    let length: f32 = dash_array.iter().sum();
+    if length <= 0.0 || !length.is_finite() {
+        return None;
+    }
+
+    // Viewers disagree on how to handle negative phases, so we normalize the
+    // offset to a position within the first repetition of the pattern.
+    let dash_phase = stroke.dashoffset().rem_euclid(length);
+
+    Some((dash_array, dash_phase))
+}
+
 /// Draws a filled path into the content stream.
 pub(crate) fn fill_path(
     path: &Path,
@@ -267,9 +420,11 @@ pub(crate) fn fill(
     match paint {
         Paint::Color(c) => {
             set_opacity_gs(chunk, content, ctx, None, Some(fill.opacity()), rc);
-            let srgb_name = rc.add_color_space(ctx.srgb_ref());
-            let srgb_name = ColorSpaceOperand::Named(srgb_name.to_pdf_name());
-            content.set_fill_color_space(srgb_name);
+            let srgb_name = rgb_color_space(ctx, rc);
+            content.set_fill_color_space(match &srgb_name {
+                Some(name) => ColorSpaceOperand::Named(name.to_pdf_name()),
+                None => ColorSpaceOperand::DeviceRgb,
+            });
             content.set_fill_color(c.to_pdf_color());
         }
         Paint::Pattern(p) => {
@@ -300,6 +455,7 @@ pub(crate) fn fill(
                 chunk,
                 ctx,
                 &accumulated_transform,
+                bbox,
             );
             let pattern_name = rc.add_pattern(pattern_ref);
             content.set_fill_color_space(Pattern);
@@ -1,15 +1,16 @@
 use crate::render::path;
 use crate::util::allocate::RefAllocator;
 use crate::util::context::Context;
-use crate::util::helper::{deflate, TransformExt};
+use crate::util::helper::{encode_binary, TransformExt};
 use crate::util::resources::ResourceContainer;
 use crate::ConversionError::{InvalidFont, SubsetError, UnknownError};
-use crate::Result;
+use crate::{ConversionOptions, FontError, Result};
 use pdf_writer::types::{
     CidFontType, FontFlags, SystemInfo, TextRenderingMode, UnicodeCmap,
 };
-use pdf_writer::{Chunk, Content, Filter, Finish, Name, Ref, Str};
+use pdf_writer::{Chunk, Content, Filter, Finish, Name, Ref, Str, TextStr};
 use siphasher::sip128::{Hasher128, SipHasher13};
+use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
 use std::sync::Arc;
@@ -26,16 +27,18 @@ const SYSTEM_INFO: SystemInfo = SystemInfo {
     supplement: 0,
 };
 
-/// Write all font objects into the chunk.
+/// Write all font objects into the chunk. Returns a warning if the font can't
+/// be embedded faithfully.
 pub fn write_font(
     chunk: &mut Chunk,
     alloc: &mut RefAllocator,
     font: &mut Font,
-) -> Result<()> {
+    options: &ConversionOptions,
+) -> Result<Option<String>> {
     // We've already parsed all fonts when creating the font objects, so each font
     // should be valid.
     let ttf = Face::parse(&font.face_data, font.face_index)
-        .map_err(|_| InvalidFont(font.id))?;
+        .map_err(|err| InvalidFont(font.error(err)))?;
     let units_per_em = ttf.units_per_em();
 
     let type0_ref = font.reference;
@@ -60,6 +63,13 @@ pub fn write_font(
     let postscript_name = find_name(&ttf, name_id::POST_SCRIPT_NAME)
         .unwrap_or_else(|| "unknown".to_string());
 
+    // Variable fonts are embedded as-is, so viewers will always show the default
+    // instance. Selecting other axis values would require instancing the outlines
+    // before subsetting, which neither usvg nor the subsetter support yet.
+    let warning = ttf.is_variable().then(|| {
+        format!("{postscript_name} is a variable font, using its default instance.")
+    });
+
     let subset_tag = subset_tag(glyph_set)?;
     let base_font = format!("{subset_tag}+{postscript_name}");
     let base_font_type0 =
@@ -153,20 +163,24 @@ pub fn write_font(
 
     font_descriptor.finish();
 
-    let cmap = create_cmap(glyph_set, glyph_remapper).ok_or(SubsetError(font.id))?;
+    let cmap = create_cmap(glyph_set, glyph_remapper)
+        .ok_or_else(|| SubsetError(font.error("a glyph is missing from the subset")))?;
     chunk.cmap(cmap_ref, &cmap.finish());
 
     // Subset and write the font's bytes.
-    let data = subset_font(&font.face_data, font.face_index, glyph_remapper, font.id)?;
+    let data = subset_font(font)?;
+    let (data, filter) = encode_binary(&data, options);
 
     let mut stream = chunk.stream(data_ref, &data);
-    stream.filter(Filter::FlateDecode);
+    if let Some(filter) = filter {
+        stream.filter(filter);
+    }
     if is_cff {
         stream.pair(Name(b"Subtype"), Name(b"CIDFontType0C"));
     }
 
     stream.finish();
-    Ok(())
+    Ok(warning)
 }
 
 /// Create a /ToUnicode CMap.
@@ -186,24 +200,20 @@ fn create_cmap(
     Some(cmap)
 }
 
-fn subset_font(
-    font_data: &[u8],
-    index: u32,
-    glyph_remapper: &GlyphRemapper,
-    id: fontdb::ID,
-) -> Result<Vec<u8>> {
-    let data = font_data;
+fn subset_font(font: &Font) -> Result<Vec<u8>> {
     let subsetted =
-        subsetter::subset(data, index, glyph_remapper).map_err(|_| SubsetError(id))?;
+        subsetter::subset(&font.face_data, font.face_index, &font.glyph_remapper)
+            .map_err(|err| SubsetError(font.error(err)))?;
     let mut data = subsetted.as_ref();
 
     // Extract the standalone CFF font program if applicable.
-    let face = ttf_parser::RawFace::parse(data, 0).map_err(|_| SubsetError(id))?;
+    let face = ttf_parser::RawFace::parse(data, 0)
+        .map_err(|err| SubsetError(font.error(err)))?;
     if let Some(cff) = face.table(CFF) {
         data = cff;
     }
 
-    Ok(deflate(data))
+    Ok(data.to_vec())
 }
 
 /// Render some text into a content stream.
@@ -220,22 +230,69 @@ pub fn render(
     // TODO: Don't clone here...
     let fonts = ctx.fonts.clone();
 
+    // Glyphs of these fonts are drawn as paths instead of text.
+    let outline_fonts = ctx.options.outline_fonts.clone();
+
+    // The faces are needed to determine the advances and outlines of the glyphs.
+    let mut faces = HashMap::new();
+
     for span in text.layouted() {
         for glyph in &span.positioned_glyphs {
-            let Some(font) = ctx.font_ref(glyph.font) else { continue };
-            font_names
-                .entry(font.reference)
-                .or_insert_with(|| rc.add_font(font.reference));
+            let Some(font) = fonts.get(&glyph.font).and_then(|f| f.as_ref()) else {
+                continue;
+            };
+
+            if let Entry::Vacant(entry) = faces.entry(glyph.font) {
+                if let Ok(face) = Face::parse(&font.face_data, font.face_index) {
+                    entry.insert(face);
+                }
+            }
+
+            if !outline_fonts.contains(&glyph.font) {
+                font_names
+                    .entry(font.reference)
+                    .or_insert_with(|| rc.add_font(font.reference));
+            }
         }
     }
 
+    // Tag the text with its language so that screen readers and text extraction
+    // can handle it properly. Note that usvg doesn't preserve the `xml:lang`
+    // attribute of the text or its spans, so all text uses the language from the
+    // conversion options.
+    if let Some(language) = &ctx.options.language {
+        content
+            .begin_marked_content_with_properties(Name(b"Span"))
+            .pair(Name(b"Lang"), TextStr(language));
+    }
+
     for span in text.layouted() {
+        // Spans with `visibility="hidden"` are not painted, but usvg has already
+        // taken them into account when positioning the following glyphs. Spans
+        // with `display="none"` aren't part of the layout at all.
         if !span.visible {
             continue;
         }
 
         let operation = |content: &mut Content| {
+            if span.positioned_glyphs.iter().all(|g| outline_fonts.contains(&g.font)) {
+                return;
+            }
+
+            content.begin_text();
+
+            // Consecutive glyphs of the same font that lie on the same baseline
+            // are shown with a single `TJ` operation, with their deviation from
+            // the natural advance (e.g. because of kerning or letter spacing)
+            // expressed as adjustments.
+            let mut run = TextRun::default();
+            let mut last: Option<(Ref, Transform, f32)> = None;
+
             for glyph in &span.positioned_glyphs {
+                if outline_fonts.contains(&glyph.font) {
+                    continue;
+                }
+
                 let Some(font) = fonts.get(&glyph.font).and_then(|f| f.as_ref()) else {
                     continue;
                 };
@@ -251,24 +308,84 @@ pub fn render(
                     // we want to leverage the native PDF font size feature instead, so we downscale
                     // it to a font size of 1.
                     .pre_scale(1.0 / span.font_size.get(), 1.0 / span.font_size.get());
-                content.save_state();
-                content.begin_text();
-                content.set_text_matrix(ts.to_pdf_transform());
-                content.set_font(Name(name.as_bytes()), span.font_size.get());
-                content.show(Str(&[(cid >> 8) as u8, (cid & 0xff) as u8]));
-                content.end_text();
-                content.restore_state();
+
+                let adjustment = last
+                    .filter(|(reference, _, _)| *reference == font.reference)
+                    .and_then(|(_, last_ts, advance)| {
+                        let offset = baseline_offset(last_ts, ts)?;
+                        Some((advance - offset) * 1000.0 / span.font_size.get())
+                    });
+
+                match adjustment {
+                    Some(adjustment) => run.adjust(adjustment),
+                    None => {
+                        run.finish(content);
+                        content.set_text_matrix(ts.to_pdf_transform());
+                        content.set_font(Name(name.as_bytes()), span.font_size.get());
+                    }
+                }
+
+                run.push(cid);
+
+                let advance = faces
+                    .get(&glyph.font)
+                    .and_then(|face| face.glyph_hor_advance(GlyphId(glyph.id.0)))
+                    .unwrap_or(0);
+                let advance =
+                    advance as f32 / font.units_per_em as f32 * span.font_size.get();
+                last = Some((font.reference, ts, advance));
             }
+
+            run.finish(content);
+            content.end_text();
         };
 
-        let stroke_operation = |content: &mut Content, _: &Stroke| {
-            content.set_text_rendering_mode(TextRenderingMode::Stroke);
-            operation(content);
+        // Draw the outlines of either all glyphs or only those of the outlined
+        // fonts as a path. Returns whether anything was drawn.
+        let draw_outlines = |content: &mut Content, all: bool| {
+            let mut drawn = false;
+            for glyph in &span.positioned_glyphs {
+                if !all && !outline_fonts.contains(&glyph.font) {
+                    continue;
+                }
+
+                let Some(face) = faces.get(&glyph.font) else { continue };
+                let mut writer = OutlineWriter::new(content, glyph.outline_transform());
+                drawn |= face.outline_glyph(GlyphId(glyph.id.0), &mut writer).is_some();
+            }
+
+            drawn
+        };
+
+        let stroke_operation = |content: &mut Content, stroke: &Stroke| {
+            // Viewers don't agree on how dash patterns apply to text that is
+            // stroked via its rendering mode, so we stroke the outlines of all
+            // glyphs like any other path instead.
+            let dashed = stroke.dasharray().is_some();
+
+            if !dashed {
+                content.set_text_rendering_mode(TextRenderingMode::Stroke);
+                operation(content);
+            }
+
+            if draw_outlines(content, dashed) {
+                content.stroke();
+            }
+
+            // Keep the text extractable if it isn't filled anyway.
+            if dashed && span.fill.is_none() {
+                content.set_text_rendering_mode(TextRenderingMode::Invisible);
+                operation(content);
+            }
         };
 
         let fill_operation = |content: &mut Content, _: &Fill| {
             content.set_text_rendering_mode(TextRenderingMode::Fill);
             operation(content);
+
+            if draw_outlines(content, false) {
+                content.fill_nonzero();
+            }
         };
 
         if let Some(overline) = &span.overline {
@@ -301,6 +418,7 @@ pub fn render(
                         rc,
                         stroke_operation,
                         accumulated_transform,
+                        text.abs_transform(),
                         text.bounding_box(),
                     )?;
                 }
@@ -313,6 +431,7 @@ pub fn render(
                         rc,
                         stroke_operation,
                         accumulated_transform,
+                        text.abs_transform(),
                         text.bounding_box(),
                     )?;
                     path::fill(
@@ -336,6 +455,7 @@ pub fn render(
                     rc,
                     stroke_operation,
                     accumulated_transform,
+                    text.abs_transform(),
                     text.bounding_box(),
                 )?;
             }
@@ -364,6 +484,10 @@ pub fn render(
         }
     }
 
+    if ctx.options.language.is_some() {
+        content.end_marked_content();
+    }
+
     Ok(())
 }
 
@@ -468,6 +592,134 @@ where
     }
 }
 
+/// The glyphs and adjustments of a `TJ` operation that is being built.
+#[derive(Default)]
+struct TextRun {
+    items: Vec<TextRunItem>,
+}
+
+enum TextRunItem {
+    Glyphs(Vec<u8>),
+    Adjustment(f32),
+}
+
+impl TextRun {
+    /// Add a glyph to the run.
+    fn push(&mut self, cid: u16) {
+        let bytes = [(cid >> 8) as u8, (cid & 0xff) as u8];
+        match self.items.last_mut() {
+            Some(TextRunItem::Glyphs(glyphs)) => glyphs.extend(bytes),
+            _ => self.items.push(TextRunItem::Glyphs(bytes.to_vec())),
+        }
+    }
+
+    /// Move the next glyph by an amount in thousandths of text space units.
+    /// Positive values move it to the left.
+    fn adjust(&mut self, adjustment: f32) {
+        if adjustment.abs() > 0.01 {
+            self.items.push(TextRunItem::Adjustment(adjustment));
+        }
+    }
+
+    /// Write the run into the content stream and clear it.
+    fn finish(&mut self, content: &mut Content) {
+        match self.items.as_slice() {
+            [] => {}
+            [TextRunItem::Glyphs(glyphs)] => {
+                content.show(Str(glyphs));
+            }
+            items => {
+                let mut show = content.show_positioned();
+                let mut positioned = show.items();
+                for item in items {
+                    match item {
+                        TextRunItem::Glyphs(glyphs) => {
+                            positioned.show(Str(glyphs));
+                        }
+                        TextRunItem::Adjustment(adjustment) => {
+                            positioned.adjust(*adjustment);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.items.clear();
+    }
+}
+
+/// If `next` is `last` moved along its baseline, return the distance between
+/// them in text space units.
+fn baseline_offset(last: Transform, next: Transform) -> Option<f32> {
+    let delta = last.invert()?.pre_concat(next);
+    let same_axes = (delta.sx - 1.0).abs() < 1e-4
+        && delta.kx.abs() < 1e-4
+        && delta.ky.abs() < 1e-4
+        && (delta.sy - 1.0).abs() < 1e-4;
+    (same_axes && delta.ty.abs() < 1e-3).then_some(delta.tx)
+}
+
+/// Writes the outline of a glyph into a content stream, mapping it from font
+/// units into user space.
+struct OutlineWriter<'a> {
+    content: &'a mut Content,
+    ts: Transform,
+    last: (f32, f32),
+}
+
+impl<'a> OutlineWriter<'a> {
+    fn new(content: &'a mut Content, ts: Transform) -> Self {
+        Self { content, ts, last: (0.0, 0.0) }
+    }
+
+    fn map(&self, x: f32, y: f32) -> (f32, f32) {
+        let ts = self.ts;
+        (ts.sx * x + ts.kx * y + ts.tx, ts.ky * x + ts.sy * y + ts.ty)
+    }
+}
+
+impl ttf_parser::OutlineBuilder for OutlineWriter<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.map(x, y);
+        self.content.move_to(x, y);
+        self.last = (x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.map(x, y);
+        self.content.line_to(x, y);
+        self.last = (x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        // PDF has no quadratic curves, so we elevate them to cubic ones.
+        let (x0, y0) = self.last;
+        let (x1, y1) = self.map(x1, y1);
+        let (x, y) = self.map(x, y);
+        self.content.cubic_to(
+            x0 + 2.0 / 3.0 * (x1 - x0),
+            y0 + 2.0 / 3.0 * (y1 - y0),
+            x + 2.0 / 3.0 * (x1 - x),
+            y + 2.0 / 3.0 * (y1 - y),
+            x,
+            y,
+        );
+        self.last = (x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x1, y1) = self.map(x1, y1);
+        let (x2, y2) = self.map(x2, y2);
+        let (x, y) = self.map(x, y);
+        self.content.cubic_to(x1, y1, x2, y2, x, y);
+        self.last = (x, y);
+    }
+
+    fn close(&mut self) {
+        self.content.close_path();
+    }
+}
+
 #[derive(Clone)]
 pub struct Font {
     pub id: fontdb::ID,
@@ -477,6 +729,21 @@ pub struct Font {
     pub face_data: Arc<Vec<u8>>,
     pub units_per_em: u16,
     pub face_index: u32,
+    pub family: String,
+    pub post_script_name: String,
+}
+
+impl Font {
+    /// Describe an error with this font, so that users can tell which of
+    /// their fonts failed.
+    fn error(&self, cause: impl std::fmt::Display) -> FontError {
+        FontError {
+            id: self.id,
+            family: self.family.clone(),
+            post_script_name: self.post_script_name.clone(),
+            cause: cause.to_string(),
+        }
+    }
 }
 
 pub fn fill_fonts(group: &Group, ctx: &mut Context, fontdb: &fontdb::Database) {
@@ -498,6 +765,7 @@ pub fn fill_fonts(group: &Group, ctx: &mut Context, fontdb: &fontdb::Database) {
                                         let reference = allocator.alloc_ref();
                                         let glyph_set = BTreeMap::new();
                                         let glyph_remapper = GlyphRemapper::new();
+                                        let info = fontdb.face(g.font);
                                         return Some(Font {
                                             id: g.font,
                                             reference,
@@ -506,6 +774,13 @@ pub fn fill_fonts(group: &Group, ctx: &mut Context, fontdb: &fontdb::Database) {
                                             glyph_set,
                                             glyph_remapper,
                                             face_index,
+                                            family: info
+                                                .and_then(|info| info.families.first())
+                                                .map(|(family, _)| family.clone())
+                                                .unwrap_or_default(),
+                                            post_script_name: info
+                                                .map(|info| info.post_script_name.clone())
+                                                .unwrap_or_default(),
                                         });
                                     }
 
@@ -514,6 +789,12 @@ pub fn fill_fonts(group: &Group, ctx: &mut Context, fontdb: &fontdb::Database) {
                                 .flatten()
                         });
 
+                        // Outlined fonts are never written, so their glyphs don't
+                        // need to be subsetted.
+                        if ctx.options.outline_fonts.contains(&g.font) {
+                            continue;
+                        }
+
                         if let Some(ref mut font) = font {
                             font.glyph_set.insert(g.id.0, g.text.clone());
                             font.glyph_remapper.remap(g.id.0);
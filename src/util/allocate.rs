@@ -40,6 +40,9 @@ pub struct NameAllocator {
     /// The next number that will be used for the name of a color space in a resource
     /// dictionary, e.g. "cs0".
     next_color_space_num: i32,
+    /// The next number that will be used for the name of a property list in a
+    /// resource dictionary, e.g. "oc0".
+    next_properties_num: i32,
 }
 
 impl NameAllocator {
@@ -84,4 +87,11 @@ impl NameAllocator {
         self.next_color_space_num += 1;
         format!("cs{}", num)
     }
+
+    /// Allocate a new property list name.
+    pub fn alloc_properties_name(&mut self) -> String {
+        let num = self.next_properties_num;
+        self.next_properties_num += 1;
+        format!("oc{}", num)
+    }
 }
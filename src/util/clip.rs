@@ -0,0 +1,186 @@
+use roxmltree::Node;
+
+use super::preprocess::{
+    apply_edits, attributes, content_range, is_svg_element, parse, start_tag,
+    write_attribute,
+};
+use crate::Result;
+
+/// A stand-in for a size that is unknown, e.g. because it is given as a
+/// percentage. Clip rectangles with an `auto` edge then extend far enough to
+/// not clip anything on that side.
+const UNBOUNDED: f32 = 1.0e6;
+
+/// Apply the deprecated `clip` property of `svg` elements to an SVG, by
+/// turning it into an equivalent `clip-path`, since usvg ignores it.
+///
+/// The property is given as `rect(top, right, bottom, left)`, where each value
+/// is an offset from the top-left corner of the viewport of the element or
+/// `auto` for the edge of the viewport. Other shapes and values with units
+/// other than `px` are ignored.
+pub(crate) fn apply_clip_properties(svg: &str) -> Result<String> {
+    if !svg.contains("clip") {
+        return Ok(svg.to_string());
+    }
+
+    let document = parse(svg)?;
+    let root = document.root_element();
+
+    // Edits at the same position are sorted so that the groups are properly
+    // nested: closing ones before opening ones, inner elements closing first
+    // and outer elements opening first.
+    let mut edits = vec![];
+    let mut count = 0;
+    for node in document.descendants().filter(|node| is_svg_element(*node, "svg")) {
+        let Some(content) = content_range(svg, node) else { continue };
+        let Some(clip) = clip_value(node).and_then(parse_clip_rect) else { continue };
+
+        let id = format!("svg2pdf-clip-{count}");
+        count += 1;
+
+        let depth = node.ancestors().count();
+        let (start, end) = (node.range().start, node.range().end);
+        if node == root {
+            // The group of the root element is opened and closed inside of it.
+            let Some((tag, opening, closing)) = clip_root(svg, node, clip, &id) else {
+                continue;
+            };
+            edits.push((
+                (start, 1, depth),
+                start..content.start,
+                format!("{tag}{opening}"),
+            ));
+            edits.push((
+                (content.end, 0, usize::MAX - depth),
+                content.end..content.end,
+                closing,
+            ));
+        } else {
+            let (opening, closing) = clip_nested(node, clip, &id);
+            edits.push(((start, 1, depth), start..start, opening));
+            edits.push(((end, 0, usize::MAX - depth), end..end, closing));
+        }
+    }
+
+    edits.sort_by_key(|(key, _, _)| *key);
+    let edits = edits.into_iter().map(|(_, range, text)| (range, text)).collect();
+    Ok(apply_edits(svg, edits))
+}
+
+/// The offsets of the edges of a clip rectangle. `None` stands for `auto`.
+type ClipRect = [Option<f32>; 4];
+
+/// Read the `clip` property of an element from its attributes or its `style`.
+fn clip_value(node: Node) -> Option<String> {
+    // Declarations in `style` take precedence over attributes.
+    let declared = node.attribute("style").and_then(|style| {
+        style.split(';').find_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            (property.trim() == "clip").then(|| value.trim().to_string())
+        })
+    });
+
+    declared.or_else(|| node.attribute("clip").map(Into::into))
+}
+
+/// Parse a value like `rect(0, 50, 50, 0)` or `rect(0 50 50 auto)`.
+fn parse_clip_rect(value: String) -> Option<ClipRect> {
+    let inner = value.trim().strip_prefix("rect(")?.strip_suffix(')')?;
+    let values: Vec<&str> = inner
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .collect();
+
+    let [top, right, bottom, left] = values[..] else { return None };
+    let parse = |v: &str| match v {
+        "auto" => Some(None),
+        _ => v.strip_suffix("px").unwrap_or(v).parse().ok().map(Some),
+    };
+
+    Some([parse(top)?, parse(right)?, parse(bottom)?, parse(left)?])
+}
+
+/// Read a numeric attribute. Lengths with units are treated as unknown.
+fn number(node: Node, name: &str) -> Option<f32> {
+    let value = node.attribute(name)?;
+    value.strip_suffix("px").unwrap_or(value).trim().parse().ok()
+}
+
+/// Write a clip path with the rectangle of a clip for a viewport at `(x, y)`
+/// with the given size.
+fn clip_path(
+    clip: ClipRect,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    id: &str,
+) -> String {
+    let [top, right, bottom, left] = clip;
+    let top = top.unwrap_or(0.0);
+    let left = left.unwrap_or(0.0);
+    let right = right.unwrap_or(width);
+    let bottom = bottom.unwrap_or(height);
+
+    format!(
+        r#"<clipPath id="{id}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+        x + left,
+        y + top,
+        (right - left).max(0.0),
+        (bottom - top).max(0.0),
+    )
+}
+
+/// Clip a nested `svg` element by wrapping it in a clipped group, which lives
+/// in the same coordinate system as the viewport of the element.
+fn clip_nested(node: Node, clip: ClipRect, id: &str) -> (String, String) {
+    let x = number(node, "x").unwrap_or(0.0);
+    let y = number(node, "y").unwrap_or(0.0);
+    let width = number(node, "width").unwrap_or(UNBOUNDED);
+    let height = number(node, "height").unwrap_or(UNBOUNDED);
+
+    let clip_path = clip_path(clip, x, y, width, height, id);
+    (format!(r#"<g clip-path="url(#{id})">{clip_path}"#), "</g>".to_string())
+}
+
+/// Clip the root `svg` element. The clip is relative to the viewport, while
+/// the content lives in the coordinate system of the view box, so the view box
+/// is moved to a nested `svg` element inside of a clipped group. Returns the
+/// new start tag of the root and the text that goes after it and before its
+/// end tag.
+fn clip_root(
+    svg: &str,
+    node: Node,
+    clip: ClipRect,
+    id: &str,
+) -> Option<(String, String, String)> {
+    let view_box_size = node.attribute("viewBox").and_then(|view_box| {
+        let values: Vec<f32> = view_box
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse().ok())
+            .collect::<Option<_>>()?;
+        Some((*values.get(2)?, *values.get(3)?))
+    });
+
+    let width = number(node, "width").or(view_box_size.map(|(w, _)| w))?;
+    let height = number(node, "height").or(view_box_size.map(|(_, h)| h))?;
+
+    let mut tag = String::from("<svg");
+    let mut inner = String::new();
+    for (name, value) in attributes(start_tag(svg, node)) {
+        match name {
+            "viewBox" | "preserveAspectRatio" => write_attribute(&mut inner, name, value),
+            "width" | "height" => {}
+            _ => write_attribute(&mut tag, name, value),
+        }
+    }
+    tag.push_str(&format!(r#" width="{width}" height="{height}">"#));
+
+    let clip_path = clip_path(clip, 0.0, 0.0, width, height, id);
+    let opening = format!(
+        r#"<g clip-path="url(#{id})">{clip_path}<svg width="{width}" height="{height}"{inner}>"#
+    );
+
+    Some((tag, opening, "</svg></g>".to_string()))
+}
@@ -1,52 +1,180 @@
-use pdf_writer::{Chunk, Content, Filter, Ref};
-use usvg::Tree;
+use pdf_writer::types::DeviceColorSpace;
+use pdf_writer::writers::ColorSpace;
+use pdf_writer::{Chunk, Content, Name, Ref, TextStr};
+use usvg::{Transform, Tree};
+
+use crate::render::{tree_to_stream, tree_to_xobject};
+use crate::util::resources::ResourceContainer;
+
+use std::collections::HashMap;
 
 #[cfg(feature = "text")]
 use {
     crate::render::text,
     crate::render::text::{write_font, Font},
-    std::collections::HashMap,
     usvg::fontdb::ID,
 };
 
-use super::helper::deflate;
+#[cfg(feature = "icc")]
+use {
+    super::helper::encode_binary,
+    crate::{GRAY_ICC, GRAY_ICC_DEFLATED, SRGB_ICC, SRGB_ICC_DEFLATED},
+    pdf_writer::Filter,
+};
+
+use super::helper::{deflate, TransformExt};
 use crate::util::allocate::RefAllocator;
+use crate::ConversionError::LimitExceeded;
 use crate::Result;
-use crate::{ConversionOptions, GRAY_ICC_DEFLATED, SRGB_ICC_DEFLATED};
+use crate::{check_node_limit, ConversionOptions};
 
 /// Holds all of the necessary information for the conversion process.
+///
+/// Most users will want to use [`to_pdf`](crate::to_pdf) or
+/// [`to_chunk`](crate::to_chunk) instead. A context is useful if you want to
+/// render multiple SVGs into the same PDF, in which case objects that are shared
+/// between them (like fonts and ICC profiles) are only written once.
+///
+/// All indirect references in the chunks you write must be allocated with
+/// [`Context::alloc_ref`] so that they don't collide with the ones used by
+/// svg2pdf. After all trees have been rendered, [`Context::finish`] needs to be
+/// called to write the shared objects.
 pub struct Context {
     /// Options that where passed by the user.
-    pub options: ConversionOptions,
+    pub(crate) options: ConversionOptions,
     /// The refs of the fonts
     #[cfg(feature = "text")]
-    pub fonts: HashMap<ID, Option<Font>>,
+    pub(crate) fonts: HashMap<ID, Option<Font>>,
+    /// The soft masks of complex clip paths of the current tree that were
+    /// already written, keyed by the address of the clip path and the bounding
+    /// box they were created for.
+    pub(crate) clip_paths: HashMap<(usize, [u32; 4]), Ref>,
+    /// Whether any part of the rendered trees had to be rasterized.
+    pub(crate) rasterized: bool,
+    /// The warnings that were emitted so far.
+    pub(crate) warnings: Vec<String>,
+    /// The optional content groups that were created for layers.
+    pub(crate) layers: Vec<Ref>,
+    /// The layers that are hidden when the document is opened.
+    pub(crate) hidden_layers: Vec<Ref>,
+    /// Sets of layers of which only one can be visible at a time.
+    pub(crate) exclusive_layers: Vec<Vec<Ref>>,
     srgb_ref: Option<Ref>,
     sgray_ref: Option<Ref>,
-    pub ref_allocator: RefAllocator,
+    registration_ref: Option<Ref>,
+    /// An estimate of the bytes that were written into content streams that
+    /// aren't finished yet. They aren't part of any chunk, but still count
+    /// towards [`ConversionOptions::max_output_bytes`].
+    pub(crate) pending_content: usize,
+    pub(crate) ref_allocator: RefAllocator,
 }
 
 impl Context {
-    pub fn new(
-        #[allow(unused_variables)] tree: &Tree,
-        options: ConversionOptions,
-    ) -> Self {
-        #[allow(unused_mut)]
-        let mut ctx = Self {
+    /// Create a new context with the given options.
+    pub fn new(mut options: ConversionOptions) -> Self {
+        // Readable PDFs never have compressed content streams.
+        options.compress &= !options.debug_readable;
+        // Without the bundled profiles, colors can only be device colors.
+        options.embed_icc &= cfg!(feature = "icc");
+
+        Self {
             ref_allocator: RefAllocator::new(),
             options,
             #[cfg(feature = "text")]
             fonts: HashMap::new(),
+            clip_paths: HashMap::new(),
+            rasterized: false,
+            warnings: vec![],
+            layers: vec![],
+            hidden_layers: vec![],
+            exclusive_layers: vec![],
             srgb_ref: None,
             sgray_ref: None,
-        };
+            registration_ref: None,
+            pending_content: 0,
+        }
+    }
 
+    /// Render a tree into a content stream. The tree is drawn into the
+    /// rectangle from `(0, 0)` to the size of the tree, with one user unit of
+    /// the SVG corresponding to one unit in the content stream. All objects
+    /// that are needed for drawing it are written into the chunk, and the
+    /// resources that are used by the content stream are registered in the
+    /// resource container.
+    pub fn render_tree(
+        &mut self,
+        tree: &Tree,
+        chunk: &mut Chunk,
+        content: &mut Content,
+        rc: &mut ResourceContainer,
+    ) -> Result<()> {
+        check_node_limit(tree, &self.options)?;
+        self.collect_fonts(tree);
+        tree_to_stream(tree, chunk, content, self, rc)
+    }
+
+    /// Render a tree into an existing content stream under a transform. This
+    /// works like [`Context::render_tree`], but the drawing operators are wrapped
+    /// in a `q`/`Q` pair and the tree is transformed by `transform`, so that it
+    /// can be placed anywhere on a page without going through an XObject.
+    ///
+    /// The resources are registered in `rc` with names like `gs0` or `xo1`, so
+    /// if you also use the resource dictionary for your own resources, make sure
+    /// to pick names that can't collide with these.
+    pub fn render_into_content(
+        &mut self,
+        tree: &Tree,
+        chunk: &mut Chunk,
+        content: &mut Content,
+        rc: &mut ResourceContainer,
+        transform: Transform,
+    ) -> Result<()> {
+        content.save_state();
+        content.transform(transform.to_pdf_transform());
+        self.render_tree(tree, chunk, content, rc)?;
+        content.restore_state();
+        Ok(())
+    }
+
+    /// Render an overlay like a watermark on top of a page. Unlike
+    /// [`Context::render_into_content`], this ignores the options that only make
+    /// sense for the page itself, see [`overlay_to_stream`].
+    ///
+    /// [`overlay_to_stream`]: crate::render::overlay_to_stream
+    #[cfg(feature = "text")]
+    pub(crate) fn render_overlay(
+        &mut self,
+        tree: &Tree,
+        chunk: &mut Chunk,
+        content: &mut Content,
+        rc: &mut ResourceContainer,
+        transform: Transform,
+    ) -> Result<()> {
+        self.collect_fonts(tree);
+        crate::render::overlay_to_stream(tree, chunk, content, self, rc, transform)
+    }
+
+    /// Render a tree into a Form XObject of size 1x1, similar to an image.
+    /// Returns the reference of the XObject.
+    pub fn render_xobject(&mut self, tree: &Tree, chunk: &mut Chunk) -> Result<Ref> {
+        check_node_limit(tree, &self.options)?;
+        self.collect_fonts(tree);
+        tree_to_xobject(tree, chunk, self)
+    }
+
+    /// Write all objects that are shared between the rendered trees, such as
+    /// fonts and ICC profiles, into the chunk.
+    pub fn finish(mut self, chunk: &mut Chunk) -> Result<()> {
+        self.write_global_objects(chunk)
+    }
+
+    /// Collect the glyphs of all fonts used in a tree, so that they can be
+    /// subsetted and embedded when writing the global objects.
+    fn collect_fonts(&mut self, #[allow(unused_variables)] tree: &Tree) {
         #[cfg(feature = "text")]
-        if options.embed_text {
-            text::fill_fonts(tree.root(), &mut ctx, tree.fontdb().as_ref());
+        if self.options.embed_text {
+            text::fill_fonts(tree.root(), self, tree.fontdb().as_ref());
         }
-
-        ctx
     }
 
     /// Allocate a new reference.
@@ -54,61 +182,223 @@ impl Context {
         self.ref_allocator.alloc_ref()
     }
 
-    pub fn srgb_ref(&mut self) -> Ref {
+    /// Whether any part of the trees rendered with this context so far had to
+    /// be rasterized, e.g. because it uses filters or because transparency was
+    /// flattened. Raster images that are part of the SVG don't count.
+    pub fn rasterized(&self) -> bool {
+        self.rasterized
+    }
+
+    /// The warnings that were emitted while rendering the trees and writing
+    /// the shared objects so far. Each of them is also logged with the `log`
+    /// crate.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The optional content groups that were created for the layers of the
+    /// trees rendered so far, if [`ConversionOptions::layers_from_groups`] or
+    /// [`ConversionOptions::raster_fallback`] is enabled. They need to be registered in the `OCProperties` of the
+    /// document catalog to show up in viewers.
+    pub fn layers(&self) -> &[Ref] {
+        &self.layers
+    }
+
+    /// The layers among [`Context::layers`] that should be hidden when the
+    /// document is opened, i.e. listed in the `OFF` array of the default
+    /// optional content configuration.
+    pub fn hidden_layers(&self) -> &[Ref] {
+        &self.hidden_layers
+    }
+
+    /// The sets of layers among [`Context::layers`] of which only one should
+    /// be visible at a time, i.e. the `RBGroups` of the default optional
+    /// content configuration.
+    pub fn exclusive_layers(&self) -> &[Vec<Ref>] {
+        &self.exclusive_layers
+    }
+
+    /// Write an optional content group for a layer with the given name.
+    pub(crate) fn add_layer(&mut self, chunk: &mut Chunk, name: &str) -> Ref {
+        let oc_ref = self.alloc_ref();
+        chunk
+            .indirect(oc_ref)
+            .dict()
+            .pair(Name(b"Type"), Name(b"OCG"))
+            .pair(Name(b"Name"), TextStr(name));
+        self.layers.push(oc_ref);
+        oc_ref
+    }
+
+    /// Log a warning and remember it for [`Context::warnings`].
+    pub(crate) fn warn(&mut self, warning: String) {
+        log::warn!("{warning}");
+        self.warnings.push(warning);
+    }
+
+    /// Write the color space that RGB colors are specified in, i.e. either the
+    /// sRGB ICC profile or `DeviceRGB`, depending on
+    /// [`ConversionOptions::embed_icc`].
+    pub(crate) fn write_rgb_color_space(&mut self, color_space: ColorSpace) {
+        if self.options.embed_icc {
+            color_space.icc_based(self.srgb_ref());
+        } else {
+            color_space.device_rgb();
+        }
+    }
+
+    /// Like [`Context::write_rgb_color_space`], but for gray colors.
+    pub(crate) fn write_gray_color_space(&mut self, color_space: ColorSpace) {
+        if self.options.embed_icc {
+            color_space.icc_based(self.sgray_ref());
+        } else {
+            color_space.device_gray();
+        }
+    }
+
+    pub(crate) fn srgb_ref(&mut self) -> Ref {
         let alloc = &mut self.ref_allocator;
         let srgb_ref = &mut self.srgb_ref;
 
         *srgb_ref.get_or_insert_with(|| alloc.alloc_ref())
     }
 
-    pub fn sgray_ref(&mut self) -> Ref {
+    pub(crate) fn sgray_ref(&mut self) -> Ref {
         let alloc = &mut self.ref_allocator;
         let sgray_ref = &mut self.sgray_ref;
 
         *sgray_ref.get_or_insert_with(|| alloc.alloc_ref())
     }
 
+    /// The number of fonts that are embedded, i.e. that were used by text
+    /// which isn't outlined.
     #[cfg(feature = "text")]
-    pub fn font_ref(&self, id: ID) -> Option<&Font> {
+    pub(crate) fn embedded_fonts(&self) -> usize {
+        self.fonts
+            .values()
+            .flatten()
+            .filter(|font| !self.options.outline_fonts.contains(&font.id))
+            .count()
+    }
+
+    /// Without the text feature, no fonts are embedded.
+    #[cfg(not(feature = "text"))]
+    pub(crate) fn embedded_fonts(&self) -> usize {
+        0
+    }
+
+    /// The `Separation` color space with the special colorant `All` that is
+    /// used for registration marks.
+    pub(crate) fn registration_ref(&mut self) -> Ref {
+        let alloc = &mut self.ref_allocator;
+        let registration_ref = &mut self.registration_ref;
+
+        *registration_ref.get_or_insert_with(|| alloc.alloc_ref())
+    }
+
+    #[cfg(feature = "text")]
+    pub(crate) fn font_ref(&self, id: ID) -> Option<&Font> {
         self.fonts.get(&id).and_then(|f| f.as_ref())
     }
 
-    pub fn write_global_objects(&mut self, pdf: &mut Chunk) -> Result<()> {
+    pub(crate) fn write_global_objects(&mut self, pdf: &mut Chunk) -> Result<()> {
         #[cfg(feature = "text")]
         {
             let allocator = &mut self.ref_allocator;
 
             for font in self.fonts.values_mut() {
                 if let Some(font) = font.as_mut() {
-                    write_font(pdf, allocator, font)?
+                    if !self.options.outline_fonts.contains(&font.id) {
+                        let warning = write_font(pdf, allocator, font, &self.options)?;
+                        if let Some(warning) = warning {
+                            log::warn!("{warning}");
+                            self.warnings.push(warning);
+                        }
+                    }
                 }
             }
         }
 
-        if let Some(srgb_ref) = self.srgb_ref {
-            pdf.icc_profile(srgb_ref, &SRGB_ICC_DEFLATED)
-                .n(3)
-                .range([0.0, 1.0, 0.0, 1.0, 0.0, 1.0])
-                .filter(Filter::FlateDecode);
+        if let Some(registration_ref) = self.registration_ref {
+            // A tint of 1 is black on devices that don't print separations.
+            pdf.indirect(registration_ref)
+                .start::<ColorSpace>()
+                .separation(Name(b"All"))
+                .alternate_device(DeviceColorSpace::Gray)
+                .tint_exponential()
+                .domain([0.0, 1.0])
+                .c0([1.0])
+                .c1([0.0])
+                .n(1.0);
         }
 
-        if let Some(sgray_ref) = self.sgray_ref {
-            pdf.icc_profile(sgray_ref, &GRAY_ICC_DEFLATED)
-                .n(1)
-                .range([0.0, 1.0])
-                .filter(Filter::FlateDecode);
+        #[cfg(feature = "icc")]
+        {
+            // The profiles are compressed ahead of time, so they are only
+            // encoded here if they need to be written differently.
+            let options = &self.options;
+            let icc_profile = |data: &[u8], deflated: &[u8]| {
+                if options.compress {
+                    (deflated.to_vec(), Some(Filter::FlateDecode))
+                } else {
+                    encode_binary(data, options)
+                }
+            };
+
+            if let Some(srgb_ref) = self.srgb_ref {
+                let (data, filter) = icc_profile(SRGB_ICC, &SRGB_ICC_DEFLATED);
+                let mut profile = pdf.icc_profile(srgb_ref, &data);
+                profile.n(3).range([0.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+                if let Some(filter) = filter {
+                    profile.filter(filter);
+                }
+            }
+
+            if let Some(sgray_ref) = self.sgray_ref {
+                let (data, filter) = icc_profile(GRAY_ICC, &GRAY_ICC_DEFLATED);
+                let mut profile = pdf.icc_profile(sgray_ref, &data);
+                profile.n(1).range([0.0, 1.0]);
+                if let Some(filter) = filter {
+                    profile.filter(filter);
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Check whether the given number of written bytes, together with the
+    /// content streams that are still being written, is within the budget set
+    /// in the [ConversionOptions] struct.
+    pub(crate) fn check_output_size(&self, len: usize) -> Result<()> {
+        match self.options.max_output_bytes {
+            Some(max_output_bytes)
+                if len.saturating_add(self.pending_content) > max_output_bytes =>
+            {
+                Err(LimitExceeded)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check whether a buffer of the given size still fits into the budget
+    /// before it is allocated, e.g. for decoding or rasterizing an image.
+    #[cfg(feature = "image")]
+    pub(crate) fn check_allocation(&self, chunk: &Chunk, bytes: u64) -> Result<()> {
+        let bytes = usize::try_from(bytes).unwrap_or(usize::MAX);
+        self.check_output_size(chunk.len().saturating_add(bytes))
+    }
+
     /// Just a helper method so that we don't have to manually compress the content if this was
     /// set in the [ConversionOptions] struct.
-    pub fn finish_content(&self, content: Content) -> Vec<u8> {
+    pub(crate) fn finish_content(&mut self, content: Content) -> Vec<u8> {
+        let content = content.finish();
+        // From now on, the stream is measured as part of the chunk it's written to.
+        self.pending_content = self.pending_content.saturating_sub(content.len());
         if self.options.compress {
-            deflate(&content.finish())
+            deflate(&content)
         } else {
-            content.finish()
+            content
         }
     }
 }
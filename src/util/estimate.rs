@@ -0,0 +1,135 @@
+use usvg::{Group, ImageKind, Node, Paint};
+
+use crate::ConversionOptions;
+
+/// The approximate size of the objects every PDF contains, including the
+/// document structure and the ICC profile.
+const BASE_SIZE: usize = 4096;
+/// The approximate overhead of each node, e.g. for the graphics state.
+const NODE_SIZE: usize = 32;
+/// The approximate size of a single point in a path.
+const POINT_SIZE: usize = 16;
+/// The approximate size of a gradient or pattern, excluding its stops.
+const PAINT_SIZE: usize = 512;
+/// The approximate size of a single gradient stop.
+const STOP_SIZE: usize = 64;
+/// The approximate size of an embedded font, excluding its glyphs.
+#[cfg(feature = "text")]
+const FONT_SIZE: usize = 4096;
+/// The approximate size of a single glyph in a font subset and the content stream.
+#[cfg(feature = "text")]
+const GLYPH_SIZE: usize = 160;
+/// The ratio by which streams typically shrink when they are compressed.
+const COMPRESSION_RATIO: usize = 3;
+
+/// Estimate the size of the PDF a tree converts to.
+pub fn estimate_size(root: &Group, options: &ConversionOptions) -> usize {
+    let mut estimate = Estimate::default();
+    estimate.group(root, options);
+
+    let streams = if options.compress && !options.debug_readable {
+        estimate.streams / COMPRESSION_RATIO
+    } else {
+        estimate.streams
+    };
+
+    // Hex-encoding doubles the size of binary streams.
+    if options.debug_readable {
+        estimate.binary *= 2;
+    }
+
+    #[allow(unused_mut)]
+    let mut size = BASE_SIZE + streams + estimate.binary;
+    #[cfg(feature = "text")]
+    {
+        size += estimate.fonts.len() * FONT_SIZE;
+    }
+
+    size
+}
+
+#[derive(Default)]
+struct Estimate {
+    /// The size of content that can still be compressed.
+    streams: usize,
+    /// The size of content that is embedded as-is, like images.
+    binary: usize,
+    /// The fonts that are embedded.
+    #[cfg(feature = "text")]
+    fonts: Vec<fontdb::ID>,
+}
+
+impl Estimate {
+    fn group(&mut self, group: &Group, options: &ConversionOptions) {
+        // Groups with filters are rasterized into an RGBA image.
+        if !group.filters().is_empty() {
+            let bbox = group.layer_bounding_box();
+            let pixels = (bbox.width() * options.raster_scale)
+                * (bbox.height() * options.raster_scale);
+            self.binary += pixels as usize * 4 / COMPRESSION_RATIO;
+            return;
+        }
+
+        for child in group.children() {
+            self.streams += NODE_SIZE;
+
+            match child {
+                Node::Group(ref group) => self.group(group, options),
+                Node::Path(ref path) => {
+                    self.streams += path.data().points().len() * POINT_SIZE;
+                    let fill = path.fill().map(|fill| fill.paint());
+                    let stroke = path.stroke().map(|stroke| stroke.paint());
+                    for paint in fill.into_iter().chain(stroke) {
+                        self.paint(paint);
+                    }
+                }
+                Node::Image(ref image) => match image.kind() {
+                    ImageKind::JPEG(data)
+                    | ImageKind::PNG(data)
+                    | ImageKind::GIF(data) => self.binary += data.len(),
+                    ImageKind::SVG(ref tree) => self.group(tree.root(), options),
+                },
+                #[cfg(feature = "text")]
+                Node::Text(ref text) => {
+                    if options.embed_text {
+                        for span in text.layouted() {
+                            for glyph in &span.positioned_glyphs {
+                                self.streams += GLYPH_SIZE;
+                                // Outlined fonts aren't embedded.
+                                if !self.fonts.contains(&glyph.font)
+                                    && !options.outline_fonts.contains(&glyph.font)
+                                {
+                                    self.fonts.push(glyph.font);
+                                }
+                            }
+                        }
+                    } else {
+                        self.group(text.flattened(), options);
+                    }
+                }
+                #[cfg(not(feature = "text"))]
+                Node::Text(_) => {}
+            }
+
+            // Clip paths, masks and patterns. The subroots of images and text
+            // are handled above.
+            if let Node::Group(_) | Node::Path(_) = child {
+                child.subroots(|subroot| self.group(subroot, options));
+            }
+        }
+    }
+
+    fn paint(&mut self, paint: &Paint) {
+        match paint {
+            Paint::Color(_) => {}
+            Paint::LinearGradient(ref gradient) => {
+                self.streams += PAINT_SIZE + gradient.stops().len() * STOP_SIZE
+            }
+            Paint::RadialGradient(ref gradient) => {
+                self.streams += PAINT_SIZE + gradient.stops().len() * STOP_SIZE
+            }
+            // The content of patterns is visited as a subroot of the path.
+            Paint::Pattern(_) => self.streams += PAINT_SIZE,
+        }
+    }
+}
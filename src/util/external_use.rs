@@ -0,0 +1,311 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Component, Path};
+
+use roxmltree::Node;
+
+use super::preprocess::{
+    apply_edits, attributes, content_range, element_name, is_inside, is_svg_element,
+    parse, start_tag, warn, write_attribute,
+};
+use crate::{ResourceResolver, Result};
+
+/// The namespace of `xlink:href`.
+const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+
+/// The attributes of a `use` element that are not carried over to the group
+/// that replaces it.
+const REPLACED_ATTRIBUTES: &[&str] =
+    &["href", "xlink:href", "x", "y", "width", "height", "transform"];
+
+/// A `use` element that refers to an element of another file.
+struct Reference<'a> {
+    /// The range of the `use` element.
+    range: Range<usize>,
+    /// The start tag of the `use` element.
+    tag: &'a str,
+    href: &'a str,
+    path: &'a str,
+    id: &'a str,
+}
+
+/// Resolve `use` elements that refer to an element of another SVG file, like
+/// `<use href="icons.svg#star"/>`. usvg only resolves references within the
+/// same document and leaves such `use` elements empty.
+///
+/// The files are loaded with the `resolver`. The referenced element is copied
+/// in place of the `use` element, wrapped in a group with the other attributes
+/// of the `use` element that is moved by its `x` and `y`. Referenced `symbol`
+/// and `svg` elements become nested `svg` elements that are sized by the
+/// `width` and `height` of the `use` element. Without a fragment, the root
+/// element of the file is used. Only the referenced element itself is copied,
+/// so it can't refer to other elements of its file, like gradients.
+///
+/// The ids in each copy get a suffix, so that they stay unique if an element is
+/// used more than once, and references between the copied elements are updated
+/// accordingly. The namespaces that are in scope for the referenced element
+/// are declared on the copy, since the document may not bind their prefixes.
+/// For each reference that can't be resolved, a warning is emitted and the `use`
+/// element is kept as-is.
+pub(crate) fn resolve_external_uses(
+    svg: &str,
+    resolver: &ResourceResolver,
+    warnings: &mut Vec<String>,
+) -> Result<String> {
+    if !svg.contains("use") {
+        return Ok(svg.to_string());
+    }
+
+    let document = parse(svg)?;
+    let references: Vec<Reference> = document
+        .descendants()
+        .filter(|node| is_svg_element(*node, "use") && !is_inside(*node, "use"))
+        .filter_map(|node| {
+            let href = node.attribute("href").or(node.attribute((XLINK_NS, "href")))?;
+            let (path, id) = href.split_once('#').unwrap_or((href, ""));
+            (!path.is_empty()).then(|| Reference {
+                range: node.range(),
+                tag: start_tag(svg, node),
+                href,
+                path,
+                id,
+            })
+        })
+        .collect();
+
+    // Each file is read and parsed only once.
+    let mut instances = vec![None; references.len()];
+    let mut files: Vec<&str> = references.iter().map(|r| r.path).collect();
+    files.sort_unstable();
+    files.dedup();
+    for path in files {
+        let Some(file) = resolver.resolve(path) else { continue };
+        let Ok(referenced) = parse(&file) else { continue };
+
+        let ids: HashMap<&str, Node> = referenced
+            .descendants()
+            .filter_map(|node| Some((node.attribute("id")?, node)))
+            .collect();
+
+        for (i, reference) in references.iter().enumerate() {
+            if reference.path != path {
+                continue;
+            }
+
+            let node = match reference.id {
+                "" => Some(referenced.root_element()),
+                id => ids.get(id).copied(),
+            };
+
+            let suffix = format!("-use{i}");
+            instances[i] = node.map(|node| instance(reference.tag, &file, node, &suffix));
+        }
+    }
+
+    let mut edits = vec![];
+    for (reference, instance) in references.iter().zip(instances) {
+        match instance {
+            Some(instance) => edits.push((reference.range.clone(), instance)),
+            None => warn(
+                warnings,
+                format!(
+                    "Failed to resolve the reference \"{}\" of a use element. Skipping.",
+                    reference.href
+                ),
+            ),
+        }
+    }
+
+    Ok(apply_edits(svg, edits))
+}
+
+/// Read a file relative to a directory, if the path stays inside of it. This
+/// is checked after following symbolic links, which could lead anywhere.
+pub(crate) fn read(dir: &Path, path: &str) -> Option<String> {
+    let path = Path::new(path);
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let dir = dir.canonicalize().ok()?;
+    let path = dir.join(path).canonicalize().ok()?;
+    if !path.starts_with(&dir) {
+        return None;
+    }
+
+    std::fs::read_to_string(path).ok()
+}
+
+/// A copy of a referenced element of a file in place of a `use` element with
+/// the given start tag. The ids in the copy get the given suffix.
+fn instance(use_tag: &str, file: &str, referenced: Node, suffix: &str) -> String {
+    let attribute = |name: &str| {
+        attributes(use_tag).find(|(n, _)| *n == name).map(|(_, value)| value)
+    };
+
+    let mut out = String::from("<g");
+    for (name, value) in attributes(use_tag) {
+        if !REPLACED_ATTRIBUTES.contains(&name) {
+            write_attribute(&mut out, name, value);
+        }
+    }
+
+    let mut transform = attribute("transform").unwrap_or_default().to_string();
+    let (x, y) = (attribute("x").unwrap_or("0"), attribute("y").unwrap_or("0"));
+    if x != "0" || y != "0" {
+        transform.push_str(&format!(" translate({x} {y})"));
+    }
+    if !transform.is_empty() {
+        write_attribute(&mut out, "transform", transform.trim());
+    }
+    out.push('>');
+
+    let ids: HashSet<&str> = referenced
+        .descendants()
+        .filter_map(|node| node.attribute("id"))
+        .collect();
+    let tag = start_tag(file, referenced);
+    let declarations = namespace_declarations(tag, referenced);
+
+    // The start tags of all copied elements are rewritten.
+    let mut edits: Vec<_> = referenced
+        .descendants()
+        .filter(|node| node.is_element() && *node != referenced)
+        .map(|node| {
+            let tag = start_tag(file, node);
+            let start = node.range().start;
+            (start..start + tag.len(), rewrite_tag(tag, "", &ids, suffix))
+        })
+        .collect();
+
+    let name = element_name(tag);
+    if name == "symbol" || name == "svg" {
+        // The size of the `use` element takes precedence over the one of a
+        // nested `svg` element.
+        out.push_str("<svg");
+        out.push_str(&declarations);
+        for (name, value) in attributes(tag) {
+            let overridden =
+                (name == "width" || name == "height") && attribute(name).is_some();
+            if name != "id" && !overridden {
+                write_attribute(
+                    &mut out,
+                    name,
+                    &rename_references(name, value, &ids, suffix),
+                );
+            }
+        }
+        for name in ["width", "height"] {
+            if let Some(value) = attribute(name) {
+                write_attribute(&mut out, name, value);
+            }
+        }
+
+        match content_range(file, referenced) {
+            Some(content) => {
+                out.push('>');
+                out.push_str(&copy(file, content, edits));
+                out.push_str("</svg>");
+            }
+            None => out.push_str("/>"),
+        }
+    } else {
+        let start = referenced.range().start;
+        let renamed = rewrite_tag(tag, &declarations, &ids, suffix);
+        edits.insert(0, (start..start + tag.len(), renamed));
+        out.push_str(&copy(file, referenced.range(), edits));
+    }
+
+    out.push_str("</g>");
+    out
+}
+
+/// Copy a range of a file with edits whose ranges are relative to the file.
+fn copy(file: &str, range: Range<usize>, edits: Vec<(Range<usize>, String)>) -> String {
+    let edits = edits
+        .into_iter()
+        .map(|(edit, replacement)| {
+            (edit.start - range.start..edit.end - range.start, replacement)
+        })
+        .collect();
+    apply_edits(&file[range], edits)
+}
+
+/// The declarations of the namespaces that are in scope for an element, but
+/// not declared on its start tag itself.
+fn namespace_declarations(tag: &str, node: Node) -> String {
+    let declared: Vec<&str> = attributes(tag).map(|(name, _)| name).collect();
+    let mut declarations = String::new();
+    for namespace in node.namespaces() {
+        let name = match namespace.name() {
+            Some("xml") => continue,
+            Some(prefix) => format!("xmlns:{prefix}"),
+            None => "xmlns".to_string(),
+        };
+        if !declared.contains(&name.as_str()) {
+            write_attribute(&mut declarations, &name, namespace.uri());
+        }
+    }
+    declarations
+}
+
+/// Rewrite a start tag so that its ids and the references to them get a
+/// suffix, and insert the given namespace declarations.
+fn rewrite_tag(
+    tag: &str,
+    declarations: &str,
+    ids: &HashSet<&str>,
+    suffix: &str,
+) -> String {
+    let mut new = format!("<{}", element_name(tag));
+    new.push_str(declarations);
+    for (name, value) in attributes(tag) {
+        if name == "id" {
+            write_attribute(&mut new, name, &format!("{value}{suffix}"));
+        } else {
+            write_attribute(&mut new, name, &rename_references(name, value, ids, suffix));
+        }
+    }
+    new.push_str(if tag.ends_with("/>") { "/>" } else { ">" });
+    new
+}
+
+/// Add a suffix to the references to the given ids in the value of an
+/// attribute, both as links like `href="#a"` and in `url(#a)`.
+fn rename_references(
+    name: &str,
+    value: &str,
+    ids: &HashSet<&str>,
+    suffix: &str,
+) -> String {
+    let is_href = name == "href" || name.ends_with(":href");
+    if let Some(id) = value.trim().strip_prefix('#').filter(|_| is_href) {
+        return match ids.contains(id) {
+            true => format!("#{id}{suffix}"),
+            false => value.to_string(),
+        };
+    }
+
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(i) = rest.find("url(") {
+        let (before, after) = rest.split_at(i + "url(".len());
+        out.push_str(before);
+        let start = after.len() - after.trim_start_matches([' ', '\'', '"']).len();
+        let len = after[start..]
+            .strip_prefix('#')
+            .and_then(|id| id.find([')', ' ', '\'', '"']))
+            .map_or(0, |len| len + 1);
+        let id = after[start..start + len].trim_start_matches('#');
+        out.push_str(&after[..start + len]);
+        if !id.is_empty() && ids.contains(id) {
+            out.push_str(suffix);
+        }
+        rest = &after[start + len..];
+    }
+    out.push_str(rest);
+    out
+}
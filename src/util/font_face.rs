@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use fontdb::{Database, Language, Source};
+use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+use super::preprocess::warn;
+
+/// The result of [`load_font_faces`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FontFaces {
+    /// The number of font faces that were added to the font database.
+    pub count: usize,
+    /// The warnings for rules whose font couldn't be loaded. They are also
+    /// logged with the `log` crate.
+    pub warnings: Vec<String>,
+}
+
+/// Register all fonts that are embedded into an SVG via `@font-face` rules with
+/// `data:` URLs in the font database.
+///
+/// usvg doesn't support `@font-face` rules, so this needs to be called with the
+/// SVG source before parsing it into a tree. The faces are registered both under
+/// the family names stored in the font and under the `font-family` of the rule,
+/// so that they can be referenced by either. TrueType, OpenType and WOFF fonts
+/// are supported, WOFF2 fonts are skipped.
+pub fn load_font_faces(svg: &str, fontdb: &mut Database) -> FontFaces {
+    let mut faces = FontFaces::default();
+
+    for rule in svg.split("@font-face").skip(1) {
+        let Some(body) = rule
+            .split_once('{')
+            .and_then(|(_, rest)| rest.split_once('}'))
+            .map(|(body, _)| body)
+        else {
+            continue;
+        };
+
+        let family = descriptor(body, "font-family").map(unquote);
+        let Some(url) = descriptor(body, "src").and_then(data_url) else {
+            continue;
+        };
+
+        let Some(data) = decode_data_url(url) else {
+            warn(
+                &mut faces.warnings,
+                "Failed to decode font from @font-face rule. Skipping.".to_string(),
+            );
+            continue;
+        };
+
+        let data = if data.starts_with(b"wOFF") {
+            match decode_woff(&data) {
+                Some(data) => data,
+                None => {
+                    warn(
+                        &mut faces.warnings,
+                        "Failed to decode WOFF font from @font-face rule. Skipping."
+                            .to_string(),
+                    );
+                    continue;
+                }
+            }
+        } else if data.starts_with(b"wOF2") {
+            warn(
+                &mut faces.warnings,
+                "WOFF2 fonts in @font-face rules are not supported. Skipping."
+                    .to_string(),
+            );
+            continue;
+        } else {
+            data
+        };
+
+        for id in fontdb.load_font_source(Source::Binary(Arc::new(data))) {
+            faces.count += 1;
+
+            let Some(family) = family else { continue };
+            let Some(mut info) = fontdb.face(id).cloned() else { continue };
+
+            // Faces can't be modified in-place, so we need to re-insert it
+            // with the additional family name.
+            info.families
+                .insert(0, (family.to_string(), Language::English_UnitedStates));
+            fontdb.remove_face(id);
+            fontdb.push_face_info(info);
+        }
+    }
+
+    faces
+}
+
+/// Find the value of a descriptor in the body of a CSS rule.
+fn descriptor<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    body.split(';').find_map(|declaration| {
+        let (key, value) = declaration.split_once(':')?;
+        (key.trim() == name).then_some(value.trim())
+    })
+}
+
+/// Remove the quotes around a CSS string.
+fn unquote(value: &str) -> &str {
+    value.trim_matches(|c| c == '"' || c == '\'').trim()
+}
+
+/// Extract the first `data:` URL from the value of a `src` descriptor.
+fn data_url(src: &str) -> Option<&str> {
+    let (_, rest) = src.split_once("url(")?;
+    let (url, _) = rest.split_once(')')?;
+    let url = unquote(url);
+    url.starts_with("data:").then_some(url)
+}
+
+/// Decode the payload of a base64-encoded `data:` URL.
+fn decode_data_url(url: &str) -> Option<Vec<u8>> {
+    let (header, payload) = url.split_once(',')?;
+    if !header.ends_with(";base64") {
+        return None;
+    }
+
+    decode_base64(payload)
+}
+
+/// Decode base64 data, ignoring whitespace.
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buf = 0u32;
+    let mut bits = 0;
+
+    for c in data.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+
+        buf = (buf << 6) | value as u32;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+        }
+    }
+
+    Some(out)
+}
+
+/// Convert a WOFF font back into an OpenType font.
+fn decode_woff(data: &[u8]) -> Option<Vec<u8>> {
+    let u16_at =
+        |pos: usize| Some(u16::from_be_bytes(data.get(pos..pos + 2)?.try_into().ok()?));
+    let u32_at =
+        |pos: usize| Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?));
+
+    let flavor = u32_at(4)?;
+    let num_tables = u16_at(12)?;
+
+    let mut tables = vec![];
+    for i in 0..num_tables as usize {
+        let entry = 44 + i * 20;
+        let tag = data.get(entry..entry + 4)?;
+        let offset = u32_at(entry + 4)? as usize;
+        let comp_length = u32_at(entry + 8)? as usize;
+        let orig_length = u32_at(entry + 12)? as usize;
+        let checksum = u32_at(entry + 16)?;
+
+        let compressed = data.get(offset..offset + comp_length)?;
+        let table = if comp_length < orig_length {
+            decompress_to_vec_zlib(compressed).ok()?
+        } else {
+            compressed.to_vec()
+        };
+
+        if table.len() != orig_length {
+            return None;
+        }
+
+        tables.push((tag, checksum, table));
+    }
+
+    // Write the header of the font and the table directory.
+    let entry_selector = num_tables.max(1).ilog2() as u16;
+    let search_range = (1u16 << entry_selector) * 16;
+    let range_shift = num_tables.saturating_mul(16).saturating_sub(search_range);
+
+    let mut font = vec![];
+    font.extend(flavor.to_be_bytes());
+    font.extend(num_tables.to_be_bytes());
+    font.extend(search_range.to_be_bytes());
+    font.extend(entry_selector.to_be_bytes());
+    font.extend(range_shift.to_be_bytes());
+
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, checksum, table) in &tables {
+        font.extend(*tag);
+        font.extend(checksum.to_be_bytes());
+        font.extend((offset as u32).to_be_bytes());
+        font.extend((table.len() as u32).to_be_bytes());
+        offset += (table.len() + 3) & !3;
+    }
+
+    // Write the tables, each padded to a multiple of four bytes.
+    for (_, _, table) in &tables {
+        font.extend(table);
+        font.resize((font.len() + 3) & !3, 0);
+    }
+
+    Some(font)
+}
@@ -0,0 +1,96 @@
+use super::preprocess::{
+    apply_edits, element_name, is_inside, is_svg_element, parse, start_tag, warn,
+};
+use crate::{ConversionError, ForeignObjectPolicy, Result};
+
+/// The attributes of a `foreignObject` that are kept for its placeholder.
+const KEPT_ATTRIBUTES: &[&str] = &[
+    "id",
+    "x",
+    "y",
+    "width",
+    "height",
+    "transform",
+    "requiredExtensions",
+    "requiredFeatures",
+    "systemLanguage",
+];
+
+/// Handle the `foreignObject` elements of an SVG according to a policy.
+///
+/// usvg drops `foreignObject` elements while parsing. Depending on the policy,
+/// they are kept as-is (and thus skipped), replaced by a placeholder rectangle
+/// covering the same area, or the conversion fails with
+/// [`ConversionError::UnsupportedForeignObject`]. A warning is emitted for each
+/// `foreignObject` that is skipped or replaced. Elements nested in another
+/// `foreignObject` belong to it and are not handled on their own.
+///
+/// The placeholder keeps the conditional processing attributes of the
+/// `foreignObject`, so that `<switch>` elements still pick the same fallback.
+pub(crate) fn replace_foreign_objects(
+    svg: &str,
+    policy: ForeignObjectPolicy,
+    warnings: &mut Vec<String>,
+) -> Result<String> {
+    if !svg.contains("foreignObject") {
+        return Ok(svg.to_string());
+    }
+
+    let document = parse(svg)?;
+    let mut edits = vec![];
+    for node in document.descendants() {
+        if !is_svg_element(node, "foreignObject") || is_inside(node, "foreignObject") {
+            continue;
+        }
+
+        match policy {
+            ForeignObjectPolicy::Skip => warn(
+                warnings,
+                "foreignObject elements are not supported. Skipping.".to_string(),
+            ),
+            ForeignObjectPolicy::Placeholder => {
+                warn(
+                    warnings,
+                    "foreignObject elements are not supported. Rendering a placeholder."
+                        .to_string(),
+                );
+                edits.push((node.range(), placeholder(svg, node)));
+            }
+            ForeignObjectPolicy::Error => {
+                return Err(ConversionError::UnsupportedForeignObject)
+            }
+        }
+    }
+
+    Ok(apply_edits(svg, edits))
+}
+
+/// A placeholder rectangle for a `foreignObject`. It uses the same prefix as
+/// the `foreignObject`, so that it ends up in the SVG namespace.
+fn placeholder(svg: &str, node: roxmltree::Node) -> String {
+    let name = element_name(start_tag(svg, node));
+    let prefix = name.strip_suffix("foreignObject").unwrap_or_default();
+    let mut out = format!("<{prefix}rect");
+    for attribute in node.attributes() {
+        if attribute.namespace().is_none() && KEPT_ATTRIBUTES.contains(&attribute.name())
+        {
+            out.push_str(&format!(
+                " {}=\"{}\"",
+                attribute.name(),
+                escape(attribute.value())
+            ));
+        }
+    }
+    out.push_str(
+        " fill=\"#e0e0e0\" stroke=\"#a0a0a0\" stroke-width=\"1\" stroke-dasharray=\"4 2\"/>",
+    );
+    out
+}
+
+/// Escape the value of an attribute that is written in double quotes.
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+}
@@ -1,8 +1,9 @@
 use pdf_writer::types::{BlendMode, LineCapStyle, LineJoinStyle, MaskType};
-use pdf_writer::{Content, Name, Rect};
-use usvg::{LineCap, LineJoin, NonZeroRect, Transform};
+use pdf_writer::{Content, Filter, Name, Rect};
+use usvg::{ImageKind, LineCap, LineJoin, Node, NonZeroRect, Transform};
 
 use crate::render::gradient::Stop;
+use crate::ConversionOptions;
 
 /// Extension trait to convert [Colors](usvg::Color) into PDF colors.
 pub trait ColorExt {
@@ -111,7 +112,9 @@ impl LineJoinExt for LineJoin {
     fn to_pdf_line_join(&self) -> LineJoinStyle {
         match self {
             LineJoin::Miter => LineJoinStyle::MiterJoin,
-            //TODO: is it possible to implement this in PDF?
+            // PDF has no equivalent of `miter-clip`, which clips the miter at the miter
+            // limit instead of falling back to a bevel. A miter join is the closest we can
+            // get, it only differs for joins that exceed the miter limit.
             LineJoin::MiterClip => LineJoinStyle::MiterJoin,
             LineJoin::Round => LineJoinStyle::RoundJoin,
             LineJoin::Bevel => LineJoinStyle::BevelJoin,
@@ -163,12 +166,69 @@ pub fn bbox_to_non_zero_rect(rect: Option<usvg::Rect>) -> NonZeroRect {
         .unwrap_or(NonZeroRect::from_xywh(0.0, 0.0, 1.0, 1.0).unwrap())
 }
 
+/// Count the nodes of a group, including the ones in its subroots and in
+/// nested SVG images.
+pub fn count_nodes(group: &usvg::Group) -> usize {
+    let mut count = 0;
+
+    for child in group.children() {
+        count += 1;
+
+        if let Node::Group(ref group) = child {
+            count += count_nodes(group);
+        }
+
+        if let Node::Image(ref image) = child {
+            if let ImageKind::SVG(ref tree) = image.kind() {
+                count += count_nodes(tree.root());
+            }
+        }
+
+        child.subroots(|subroot| count += count_nodes(subroot));
+    }
+
+    count
+}
+
 /// Compress data using the deflate algorithm.
 pub fn deflate(data: &[u8]) -> Vec<u8> {
     const COMPRESSION_LEVEL: u8 = 6;
     miniz_oxide::deflate::compress_to_vec_zlib(data, COMPRESSION_LEVEL)
 }
 
+/// Encode data as hexadecimal digits for the `ASCIIHexDecode` filter, with a
+/// line break after every 32 bytes.
+pub fn hex_encode(data: &[u8]) -> Vec<u8> {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut encoded = Vec::with_capacity(data.len() * 2 + data.len() / 32 + 1);
+    for (i, &byte) in data.iter().enumerate() {
+        if i > 0 && i % 32 == 0 {
+            encoded.push(b'\n');
+        }
+        encoded.push(DIGITS[(byte >> 4) as usize]);
+        encoded.push(DIGITS[(byte & 0xF) as usize]);
+    }
+    encoded.push(b'>');
+    encoded
+}
+
+/// Encode the data of a binary stream like an image or a font. Returns the
+/// encoded data and the filter that decodes it, if any. Without compression,
+/// the data is written as-is.
+pub fn encode_binary(
+    data: &[u8],
+    options: &ConversionOptions,
+) -> (Vec<u8>, Option<Filter>) {
+    if options.debug_readable {
+        (hex_encode(data), Some(Filter::AsciiHexDecode))
+    } else if options.compress {
+        (deflate(data), Some(Filter::FlateDecode))
+    } else {
+        (data.to_vec(), None)
+    }
+}
+
 pub fn clip_to_rect(rect: NonZeroRect, content: &mut Content) {
     content.rect(rect.x(), rect.y(), rect.width(), rect.height());
     content.close_path();
@@ -0,0 +1,793 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::ops::Range;
+
+use super::helper::deflate;
+
+/// Rewrite a finished PDF as a linearized file, which viewers can start to
+/// display while it is still being downloaded. See Annex F of the PDF
+/// specification.
+///
+/// The objects are renumbered and reordered so that the catalog and
+/// everything the first page needs come first, followed by the objects of the
+/// remaining pages, the objects shared between them and all other objects.
+/// The file starts with a linearization dictionary and a cross-reference table
+/// for the first page, and contains a hint stream with the page offset and
+/// shared object hint tables. Returns `None` if the PDF can't be read, which
+/// doesn't happen for files written by pdf-writer.
+pub fn linearize(pdf: &[u8], compress: bool) -> Option<Vec<u8>> {
+    let file = File::parse(pdf)?;
+    let layout = Layout::new(&file)?;
+    Some(layout.write(compress))
+}
+
+/// The objects and the trailer of a PDF.
+struct File<'a> {
+    data: &'a [u8],
+    /// The end of the header, which is where the first object starts.
+    header_len: usize,
+    objects: HashMap<u32, Object<'a>>,
+    /// The IDs of the objects in the order in which they appear in the file.
+    order: Vec<u32>,
+    /// The trailer dictionary. Its byte ranges are relative to `data`.
+    trailer: Primitive<'a>,
+}
+
+/// An indirect object.
+struct Object<'a> {
+    /// The object without its number and the `obj` and `endobj` keywords.
+    body: &'a [u8],
+    /// The value of the object, without the data if it is a stream. Its byte
+    /// ranges are relative to `body`.
+    value: Primitive<'a>,
+}
+
+/// The parts of a PDF value that matter for linearization, along with the
+/// byte ranges of references.
+enum Primitive<'a> {
+    Ref(u32, Range<usize>),
+    Int(u32),
+    Array(Vec<Primitive<'a>>, Range<usize>),
+    Dict(Vec<(&'a [u8], Primitive<'a>)>),
+    Other,
+}
+
+impl<'a> Primitive<'a> {
+    fn get(&self, key: &[u8]) -> Option<&Self> {
+        match self {
+            Self::Dict(entries) => {
+                entries.iter().find(|(k, _)| *k == key).map(|(_, value)| value)
+            }
+            _ => None,
+        }
+    }
+
+    fn reference(&self) -> Option<u32> {
+        match self {
+            Self::Ref(id, _) => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// Collect all references in the value in the order in which they appear.
+    fn refs(&self, refs: &mut Vec<(u32, Range<usize>)>) {
+        match self {
+            Self::Ref(id, range) => refs.push((*id, range.clone())),
+            Self::Array(items, _) => items.iter().for_each(|item| item.refs(refs)),
+            Self::Dict(entries) => entries.iter().for_each(|(_, value)| value.refs(refs)),
+            _ => {}
+        }
+    }
+}
+
+impl<'a> File<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let start_xref = rfind(data, b"startxref")? + b"startxref".len();
+        let Primitive::Int(xref) = Parser::new(data, start_xref).value()? else {
+            return None;
+        };
+        let xref = xref as usize;
+
+        // Read the offsets of all objects in use from the cross-reference
+        // table.
+        let mut offsets = vec![];
+        let mut lines = data
+            .get(xref..)?
+            .split(|&b| b == b'\n' || b == b'\r')
+            .filter(|line| !line.is_empty());
+        if lines.next()? != b"xref" {
+            return None;
+        }
+        let mut id = 0;
+        for line in lines {
+            let line = std::str::from_utf8(line).ok()?;
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ["trailer", ..] => break,
+                [first, _] => id = first.parse().ok()?,
+                [offset, _, kind] => {
+                    if kind == "n" {
+                        offsets.push((offset.parse::<usize>().ok()?, id));
+                    }
+                    id += 1;
+                }
+                _ => return None,
+            }
+        }
+
+        let trailer = find(&data[xref..], b"trailer")? + xref + b"trailer".len();
+        let trailer = Parser::new(data, trailer).value()?;
+
+        offsets.sort();
+        let header_len = offsets.first()?.0;
+        let mut objects = HashMap::new();
+        let mut order = vec![];
+        for (i, &(offset, id)) in offsets.iter().enumerate() {
+            let end = offsets.get(i + 1).map_or(xref, |&(next, _)| next);
+            objects.insert(id, Object::parse(data.get(offset..end)?)?);
+            order.push(id);
+        }
+
+        Some(Self { data, header_len, objects, order, trailer })
+    }
+
+    /// The IDs of the objects that an object refers to.
+    fn refs(&self, id: u32) -> Vec<u32> {
+        let mut refs = vec![];
+        if let Some(object) = self.objects.get(&id) {
+            object.value.refs(&mut refs);
+        }
+        refs.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// The page objects in order and the nodes of the page tree.
+    fn pages(&self, catalog: u32) -> Option<(Vec<u32>, HashSet<u32>)> {
+        let root = self.objects.get(&catalog)?.value.get(b"Pages")?.reference()?;
+        let mut pages = vec![];
+        let mut nodes = HashSet::new();
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            match self.objects.get(&id)?.value.get(b"Kids") {
+                Some(Primitive::Array(kids, _)) => {
+                    // Guard against cycles in broken page trees.
+                    if !nodes.insert(id) {
+                        return None;
+                    }
+                    stack.extend(kids.iter().rev().filter_map(Primitive::reference));
+                }
+                _ => pages.push(id),
+            }
+        }
+
+        Some((pages, nodes))
+    }
+}
+
+impl<'a> Object<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let start = find(data, b"obj")? + b"obj".len();
+        let end = rfind(data, b"endobj")?;
+        let body = trim(data.get(start..end)?);
+        let value = Parser::new(body, 0).value()?;
+        Some(Self { body, value })
+    }
+}
+
+/// The order of the objects in the linearized file and their new numbers.
+struct Layout<'a> {
+    file: &'a File<'a>,
+    catalog: u32,
+    /// The old IDs of the objects in the section of each page, starting with
+    /// the page object. The section of the first page contains all objects it
+    /// needs.
+    pages: Vec<Vec<u32>>,
+    /// The old IDs of the objects each page refers to that are in the section
+    /// of the first page or the shared objects.
+    page_shared: Vec<Vec<u32>>,
+    /// The old IDs of the objects needed by more than one page other than the
+    /// first one.
+    shared: Vec<u32>,
+    /// The old IDs of all other objects, like the page tree.
+    rest: Vec<u32>,
+    /// The new number for each old ID.
+    numbers: HashMap<u32, u32>,
+    /// The number of the linearization dictionary. All objects of the first
+    /// page section have higher numbers, all others lower ones.
+    linearization: u32,
+    /// The number of the hint stream, which is the highest one.
+    hint: u32,
+}
+
+impl<'a> Layout<'a> {
+    fn new(file: &'a File<'a>) -> Option<Self> {
+        let catalog = file.trailer.get(b"Root")?.reference()?;
+        let (page_ids, mut stop) = file.pages(catalog)?;
+
+        // Collect the objects each page needs, without following references
+        // to the catalog, the page tree or other pages.
+        stop.insert(catalog);
+        stop.extend(&page_ids);
+        let needed: Vec<Vec<u32>> = page_ids
+            .iter()
+            .map(|&page| {
+                let mut objects = vec![page];
+                let mut seen = HashSet::from([page]);
+                let mut i = 0;
+                while i < objects.len() {
+                    for id in file.refs(objects[i]) {
+                        if file.objects.contains_key(&id)
+                            && !stop.contains(&id)
+                            && seen.insert(id)
+                        {
+                            objects.push(id);
+                        }
+                    }
+                    i += 1;
+                }
+                objects
+            })
+            .collect();
+
+        // Everything the first page needs goes into its section. Of the
+        // objects of the other pages, the ones needed by more than one of them
+        // are shared.
+        let first: HashSet<u32> = needed.first()?.iter().copied().collect();
+        let mut users: HashMap<u32, usize> = HashMap::new();
+        for id in needed[1..].iter().flatten() {
+            if !first.contains(id) {
+                *users.entry(*id).or_default() += 1;
+            }
+        }
+
+        // Within a section, the page object comes first and the other objects
+        // keep their order.
+        let position: HashMap<u32, usize> =
+            file.order.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let mut pages = vec![];
+        let mut page_shared = vec![];
+        for (i, objects) in needed.iter().enumerate() {
+            let (mut own, shared): (Vec<u32>, Vec<u32>) = objects
+                .iter()
+                .copied()
+                .partition(|id| i == 0 || users.get(id).copied() == Some(1));
+            own[1..].sort_by_key(|id| position[id]);
+            pages.push(own);
+            page_shared.push(shared);
+        }
+
+        let placed: HashSet<u32> = needed.iter().flatten().copied().collect();
+        let shared: Vec<u32> = file
+            .order
+            .iter()
+            .copied()
+            .filter(|id| users.get(id).is_some_and(|&count| count > 1))
+            .collect();
+        let rest: Vec<u32> = file
+            .order
+            .iter()
+            .copied()
+            .filter(|id| *id != catalog && !placed.contains(id))
+            .collect();
+
+        // The objects of the first page section get the highest numbers, so
+        // that the main cross-reference table starts at zero.
+        let mut numbers = HashMap::new();
+        for id in pages[1..].iter().flatten().chain(&shared).chain(&rest) {
+            numbers.insert(*id, numbers.len() as u32 + 1);
+        }
+        let linearization = numbers.len() as u32 + 1;
+        for id in [&catalog].into_iter().chain(&pages[0]) {
+            numbers.insert(*id, numbers.len() as u32 + 2);
+        }
+        let hint = numbers.len() as u32 + 2;
+
+        Some(Self {
+            file,
+            catalog,
+            pages,
+            page_shared,
+            shared,
+            rest,
+            numbers,
+            linearization,
+            hint,
+        })
+    }
+
+    /// Write an object with its new number.
+    fn object(&self, id: u32) -> Vec<u8> {
+        let object = &self.file.objects[&id];
+        let mut refs = vec![];
+        object.value.refs(&mut refs);
+
+        let mut out = vec![];
+        writeln!(out, "{} 0 obj", self.numbers[&id]).unwrap();
+        let mut last = 0;
+        for (target, range) in refs {
+            // References to objects that don't exist are left as they are,
+            // since they resolve to null either way.
+            let Some(number) = self.numbers.get(&target) else { continue };
+            out.extend_from_slice(&object.body[last..range.start]);
+            write!(out, "{number} 0 R").unwrap();
+            last = range.end;
+        }
+        out.extend_from_slice(&object.body[last..]);
+        out.extend_from_slice(b"\nendobj\n\n");
+        out
+    }
+
+    fn write(&self, compress: bool) -> Vec<u8> {
+        let catalog = self.object(self.catalog);
+        let pages: Vec<Vec<Vec<u8>>> = self
+            .pages
+            .iter()
+            .map(|ids| ids.iter().map(|&id| self.object(id)).collect())
+            .collect();
+        let shared: Vec<Vec<u8>> =
+            self.shared.iter().map(|&id| self.object(id)).collect();
+        let rest: Vec<Vec<u8>> = self.rest.iter().map(|&id| self.object(id)).collect();
+
+        // The linearization dictionary and the first page cross-reference
+        // table have a fixed size, so that the offsets of everything after
+        // them are known before they are written.
+        let header = &self.file.data[..self.file.header_len];
+        let hint_offset = header.len()
+            + self.linearization_dict([0; 6]).len()
+            + self.first_xref(&[], 0).len()
+            + catalog.len();
+
+        // The hint tables refer to offsets as if the hint stream wasn't there.
+        let mut offset = hint_offset;
+        let mut spans = HashMap::new();
+        let mut page_offsets = vec![];
+        for (ids, objects) in self.pages.iter().zip(&pages) {
+            page_offsets.push(offset);
+            for (&id, object) in ids.iter().zip(objects) {
+                spans.insert(id, offset..offset + object.len());
+                offset += object.len();
+            }
+        }
+        page_offsets.push(offset);
+        for (&id, object) in self.shared.iter().zip(&shared) {
+            spans.insert(id, offset..offset + object.len());
+            offset += object.len();
+        }
+        let hint = self.hint_stream(&page_offsets, &spans, compress);
+
+        let mut out = Vec::with_capacity(offset + hint.len() + 1024);
+        out.extend_from_slice(header);
+        let prefix_start = out.len();
+        out.extend(self.linearization_dict([0; 6]));
+        let first_xref = out.len();
+        out.extend(self.first_xref(&[], 0));
+
+        let mut first_offsets = vec![
+            (self.linearization, prefix_start),
+            (self.numbers[&self.catalog], out.len()),
+            (self.hint, hint_offset),
+        ];
+        out.extend(&catalog);
+        out.extend(&hint);
+
+        let mut main_offsets = vec![];
+        let mut first_page_end = 0;
+        for (i, (ids, objects)) in self.pages.iter().zip(&pages).enumerate() {
+            let offsets = if i == 0 { &mut first_offsets } else { &mut main_offsets };
+            for (&id, object) in ids.iter().zip(objects) {
+                offsets.push((self.numbers[&id], out.len()));
+                out.extend(object);
+            }
+            if i == 0 {
+                first_page_end = out.len();
+            }
+        }
+        for (ids, objects) in [(&self.shared, &shared), (&self.rest, &rest)] {
+            for (&id, object) in ids.iter().zip(objects) {
+                main_offsets.push((self.numbers[&id], out.len()));
+                out.extend(object);
+            }
+        }
+
+        // The main cross-reference table, whose trailer only holds the size.
+        // The `startxref` at the end of the file points to the first page
+        // cross-reference table.
+        main_offsets.sort();
+        let main_xref = out.len();
+        write!(out, "xref\n0 {}\n", self.linearization).unwrap();
+        let main_xref_entries = out.len() - 1;
+        out.extend(b"0000000000 65535 f\r\n");
+        for (_, offset) in &main_offsets {
+            write!(out, "{offset:010} 00000 n\r\n").unwrap();
+        }
+        write!(
+            out,
+            "trailer\n<< /Size {} >>\nstartxref\n{first_xref}\n%%EOF",
+            self.linearization
+        )
+        .unwrap();
+
+        // Now that all offsets are known, fill in the linearization
+        // dictionary and the first page cross-reference table.
+        first_offsets.sort();
+        let values = [
+            out.len(),
+            hint_offset,
+            hint.len(),
+            first_page_end,
+            self.pages.len(),
+            main_xref_entries,
+        ];
+        let mut prefix = self.linearization_dict(values);
+        prefix.extend(self.first_xref(&first_offsets, main_xref));
+        out[prefix_start..prefix_start + prefix.len()].copy_from_slice(&prefix);
+
+        out
+    }
+
+    /// Write the linearization dictionary. The values are the length of the
+    /// file, the offset and length of the hint stream, the end of the first
+    /// page section, the number of pages and the offset of the white-space
+    /// before the first entry of the main cross-reference table.
+    fn linearization_dict(&self, values: [usize; 6]) -> Vec<u8> {
+        let [length, hint_offset, hint_len, first_page_end, count, xref] = values;
+        let mut out = vec![];
+        write!(
+            out,
+            "{} 0 obj\n<< /Linearized 1 /L {length:<10} /H [{hint_offset:<10} \
+             {hint_len:<10}] /O {} /E {first_page_end:<10} /N {count:<10} \
+             /T {xref:<10} >>\nendobj\n\n",
+            self.linearization, self.numbers[&self.pages[0][0]],
+        )
+        .unwrap();
+        out
+    }
+
+    /// Write the cross-reference table of the first page section and its
+    /// trailer, which points to the main cross-reference table.
+    fn first_xref(&self, offsets: &[(u32, usize)], main_xref: usize) -> Vec<u8> {
+        let count = self.hint + 1 - self.linearization;
+        let mut out = vec![];
+        write!(out, "xref\n{} {count}\n", self.linearization).unwrap();
+        for i in 0..count as usize {
+            let offset = offsets.get(i).map_or(0, |&(_, offset)| offset);
+            write!(out, "{offset:010} 00000 n\r\n").unwrap();
+        }
+
+        let trailer = &self.file.trailer;
+        write!(
+            out,
+            "trailer\n<< /Size {} /Root {} 0 R",
+            self.hint + 1,
+            self.numbers[&self.catalog]
+        )
+        .unwrap();
+        if let Some(info) = trailer
+            .get(b"Info")
+            .and_then(|info| self.numbers.get(&info.reference()?))
+        {
+            write!(out, " /Info {info} 0 R").unwrap();
+        }
+        if let Some(Primitive::Array(_, range)) = trailer.get(b"ID") {
+            out.extend(b" /ID ");
+            out.extend(&self.file.data[range.clone()]);
+        }
+        write!(out, " /Prev {main_xref:<10} >>\nstartxref\n0\n%%EOF\n").unwrap();
+        out
+    }
+
+    /// Write the primary hint stream with the page offset hint table and the
+    /// shared object hint table. The offsets of the page sections and the
+    /// byte ranges of the objects are the ones without the hint stream.
+    fn hint_stream(
+        &self,
+        page_offsets: &[usize],
+        spans: &HashMap<u32, Range<usize>>,
+        compress: bool,
+    ) -> Vec<u8> {
+        // The shared object hint table has an entry for each object in the
+        // first page section and for each shared object.
+        let shared_entries: Vec<u32> =
+            self.pages[0].iter().chain(&self.shared).copied().collect();
+        let identifiers: HashMap<u32, usize> =
+            shared_entries.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+
+        let objects: Vec<usize> = self.pages.iter().map(Vec::len).collect();
+        let lengths: Vec<usize> = page_offsets.windows(2).map(|w| w[1] - w[0]).collect();
+        let shared_counts: Vec<usize> = self.page_shared.iter().map(Vec::len).collect();
+        let (content_offsets, content_lengths): (Vec<usize>, Vec<usize>) = self
+            .pages
+            .iter()
+            .zip(page_offsets)
+            .zip(&lengths)
+            .map(|((ids, &start), &length)| {
+                self.file.objects[&ids[0]]
+                    .value
+                    .get(b"Contents")
+                    .and_then(|contents| match contents {
+                        Primitive::Array(items, _) => items.first()?.reference(),
+                        _ => contents.reference(),
+                    })
+                    .and_then(|id| spans.get(&id))
+                    .filter(|span| span.start >= start && span.end <= start + length)
+                    .map_or((0, length), |span| (span.start - start, span.len()))
+            })
+            .unzip();
+
+        let mut w = BitWriter::default();
+
+        // The page offset hint table. Each item is written for all pages
+        // before the next one.
+        let (min_objects, objects_bits) = range_of(&objects);
+        let (min_length, length_bits) = range_of(&lengths);
+        let (min_content_offset, content_offset_bits) = range_of(&content_offsets);
+        let (min_content_length, content_length_bits) = range_of(&content_lengths);
+        let shared_count_bits = bits(shared_counts.iter().copied().max().unwrap_or(0));
+        let identifier_bits = bits(shared_entries.len().saturating_sub(1));
+        w.write(min_objects, 32);
+        w.write(page_offsets[0], 32);
+        w.write(objects_bits, 16);
+        w.write(min_length, 32);
+        w.write(length_bits, 16);
+        w.write(min_content_offset, 32);
+        w.write(content_offset_bits, 16);
+        w.write(min_content_length, 32);
+        w.write(content_length_bits, 16);
+        w.write(shared_count_bits, 16);
+        w.write(identifier_bits, 16);
+        // The position of the first reference to a shared object within the
+        // content stream isn't known, so the numerators are left out.
+        w.write(0, 16);
+        w.write(1, 16);
+
+        w.write_all(objects.iter().map(|n| n - min_objects), objects_bits);
+        w.write_all(lengths.iter().map(|n| n - min_length), length_bits);
+        w.write_all(shared_counts.iter().copied(), shared_count_bits);
+        w.write_all(
+            self.page_shared.iter().flatten().map(|id| identifiers[id]),
+            identifier_bits,
+        );
+        w.write_all(
+            content_offsets.iter().map(|n| n - min_content_offset),
+            content_offset_bits,
+        );
+        w.write_all(
+            content_lengths.iter().map(|n| n - min_content_length),
+            content_length_bits,
+        );
+
+        // The shared object hint table. Each object is a group of its own.
+        let shared_table = w.data.len();
+        let group_lengths: Vec<usize> =
+            shared_entries.iter().map(|id| spans[id].len()).collect();
+        let (min_group_length, group_length_bits) = range_of(&group_lengths);
+        let first_shared = self.shared.first();
+        w.write(first_shared.map_or(0, |id| self.numbers[id] as usize), 32);
+        w.write(first_shared.map_or(0, |id| spans[id].start), 32);
+        w.write(self.pages[0].len(), 32);
+        w.write(shared_entries.len(), 32);
+        w.write(0, 16);
+        w.write(min_group_length, 32);
+        w.write(group_length_bits, 16);
+        w.write_all(
+            group_lengths.iter().map(|n| n - min_group_length),
+            group_length_bits,
+        );
+        // None of the groups have an MD5 signature.
+        w.write_all(shared_entries.iter().map(|_| 0), 1);
+
+        let (data, filter) = if compress {
+            (deflate(&w.data), " /Filter /FlateDecode")
+        } else {
+            (w.data, "")
+        };
+
+        let mut out = vec![];
+        write!(
+            out,
+            "{} 0 obj\n<< /Length {} /S {shared_table}{filter} >>\nstream\n",
+            self.hint,
+            data.len()
+        )
+        .unwrap();
+        out.extend(data);
+        out.extend(b"\nendstream\nendobj\n\n");
+        out
+    }
+}
+
+/// Writes the bit-packed values of the hint tables.
+#[derive(Default)]
+struct BitWriter {
+    data: Vec<u8>,
+    /// How many bits of the last byte are used.
+    used: u32,
+}
+
+impl BitWriter {
+    /// Write the lowest `bits` bits of a value, starting with the highest one.
+    fn write(&mut self, value: usize, bits: usize) {
+        for i in (0..bits).rev() {
+            if self.used == 0 {
+                self.data.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.data.last_mut().unwrap() |= bit << (7 - self.used);
+            self.used = (self.used + 1) % 8;
+        }
+    }
+
+    /// Write one item of a hint table for all entries. The next item starts
+    /// at a byte boundary.
+    fn write_all(&mut self, values: impl Iterator<Item = usize>, bits: usize) {
+        for value in values {
+            self.write(value, bits);
+        }
+        self.used = 0;
+    }
+}
+
+/// The smallest of some values and the number of bits needed to store their
+/// differences to it.
+fn range_of(values: &[usize]) -> (usize, usize) {
+    let min = values.iter().copied().min().unwrap_or(0);
+    let max = values.iter().copied().max().unwrap_or(0);
+    (min, bits(max - min))
+}
+
+/// The number of bits needed to store a value.
+fn bits(value: usize) -> usize {
+    (usize::BITS - value.leading_zeros()) as usize
+}
+
+/// Parses the values of indirect objects and the trailer.
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&b) = self.data.get(self.pos) {
+            if b == b'%' {
+                while self.data.get(self.pos).is_some_and(|&b| b != b'\n' && b != b'\r') {
+                    self.pos += 1;
+                }
+            } else if is_whitespace(b) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Read a token of regular characters, like a number or a keyword.
+    fn regular(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|&b| !is_whitespace(b) && !is_delimiter(b))
+        {
+            self.pos += 1;
+        }
+        &self.data[start..self.pos]
+    }
+
+    fn value(&mut self) -> Option<Primitive<'a>> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match *self.rest().first()? {
+            b'/' => {
+                self.pos += 1;
+                self.regular();
+                Some(Primitive::Other)
+            }
+            b'<' if self.rest().starts_with(b"<<") => {
+                self.pos += 2;
+                let mut entries = vec![];
+                loop {
+                    self.skip_whitespace();
+                    if self.rest().starts_with(b">>") {
+                        self.pos += 2;
+                        return Some(Primitive::Dict(entries));
+                    }
+                    let key = self.name()?;
+                    entries.push((key, self.value()?));
+                }
+            }
+            b'<' => {
+                self.pos += find(self.rest(), b">")? + 1;
+                Some(Primitive::Other)
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = vec![];
+                loop {
+                    self.skip_whitespace();
+                    if self.rest().starts_with(b"]") {
+                        self.pos += 1;
+                        return Some(Primitive::Array(items, start..self.pos));
+                    }
+                    items.push(self.value()?);
+                }
+            }
+            b'(' => {
+                self.pos += 1;
+                let mut depth = 1;
+                while depth > 0 {
+                    match *self.rest().first()? {
+                        b'\\' => self.pos += 1,
+                        b'(' => depth += 1,
+                        b')' => depth -= 1,
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+                Some(Primitive::Other)
+            }
+            _ => {
+                let token = self.regular();
+                if token.is_empty() {
+                    return None;
+                }
+                let Some(number) = parse_int(token) else {
+                    return Some(Primitive::Other);
+                };
+
+                // Check whether the number starts a reference.
+                let end = self.pos;
+                self.skip_whitespace();
+                if parse_int(self.regular()).is_some() {
+                    self.skip_whitespace();
+                    if self.regular() == b"R" {
+                        return Some(Primitive::Ref(number, start..self.pos));
+                    }
+                }
+                self.pos = end;
+                Some(Primitive::Int(number))
+            }
+        }
+    }
+
+    fn name(&mut self) -> Option<&'a [u8]> {
+        self.skip_whitespace();
+        if !self.rest().starts_with(b"/") {
+            return None;
+        }
+        self.pos += 1;
+        Some(self.regular())
+    }
+}
+
+fn parse_int(token: &[u8]) -> Option<u32> {
+    std::str::from_utf8(token).ok()?.parse().ok()
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b'\0' | b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(b, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).position(|window| window == needle)
+}
+
+fn rfind(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).rposition(|window| window == needle)
+}
+
+fn trim(data: &[u8]) -> &[u8] {
+    let start = data.iter().position(|&b| !is_whitespace(b)).unwrap_or(data.len());
+    let end = data.iter().rposition(|&b| !is_whitespace(b)).map_or(start, |i| i + 1);
+    &data[start..end]
+}
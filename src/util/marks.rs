@@ -0,0 +1,79 @@
+use pdf_writer::types::ColorSpaceOperand;
+use pdf_writer::{Content, Name};
+use usvg::NonZeroRect;
+
+/// The space around the trim box that holds the marks, in points.
+pub const MARK_MARGIN: f32 = 36.0;
+/// The distance between the trim box and the crop marks, so that they don't
+/// show up on the trimmed page if it is cut slightly off.
+const MARK_OFFSET: f32 = 9.0;
+/// The length of the crop marks.
+const MARK_LENGTH: f32 = 18.0;
+/// The radius of the circle of the registration marks.
+const REGISTRATION_RADIUS: f32 = 4.0;
+/// The line width of all marks. Printers use hairlines for them.
+const MARK_WIDTH: f32 = 0.25;
+/// The factor for approximating a quarter circle with a cubic Bézier curve.
+const KAPPA: f32 = 0.5522848;
+
+/// Draw crop marks at the corners of the trim box and registration marks
+/// centered on its sides, both in the margin around the trim box. The marks
+/// use the color space resource with the given name, which needs to be a
+/// `Separation` for the colorant `All`, so that they show up on every
+/// separation of the page.
+pub fn draw_crop_marks(content: &mut Content, trim: NonZeroRect, registration: &str) {
+    content.save_state();
+    content
+        .set_stroke_color_space(ColorSpaceOperand::Named(Name(registration.as_bytes())));
+    content.set_stroke_color([1.0]);
+    content.set_line_width(MARK_WIDTH);
+
+    // The rectangle is in PDF coordinates, so `top` is the lower edge.
+    let (x0, y0, x1, y1) = (trim.left(), trim.top(), trim.right(), trim.bottom());
+
+    // Each mark points away from the trim box in the direction `(dx, dy)`.
+    for (x, y, dx, dy) in [
+        (x0, y0, -1.0, -1.0),
+        (x1, y0, 1.0, -1.0),
+        (x1, y1, 1.0, 1.0),
+        (x0, y1, -1.0, 1.0),
+    ] {
+        content.move_to(x + dx * MARK_OFFSET, y);
+        content.line_to(x + dx * (MARK_OFFSET + MARK_LENGTH), y);
+        content.move_to(x, y + dy * MARK_OFFSET);
+        content.line_to(x, y + dy * (MARK_OFFSET + MARK_LENGTH));
+    }
+
+    let (cx, cy) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+    let distance = MARK_MARGIN / 2.0;
+    for (x, y) in [
+        (cx, y0 - distance),
+        (x1 + distance, cy),
+        (cx, y1 + distance),
+        (x0 - distance, cy),
+    ] {
+        draw_registration_mark(content, x, y);
+    }
+
+    content.stroke();
+    content.restore_state();
+}
+
+/// Draw a circle with a cross through it.
+fn draw_registration_mark(content: &mut Content, x: f32, y: f32) {
+    let r = REGISTRATION_RADIUS;
+    let k = r * KAPPA;
+
+    content.move_to(x + r, y);
+    content.cubic_to(x + r, y + k, x + k, y + r, x, y + r);
+    content.cubic_to(x - k, y + r, x - r, y + k, x - r, y);
+    content.cubic_to(x - r, y - k, x - k, y - r, x, y - r);
+    content.cubic_to(x + k, y - r, x + r, y - k, x + r, y);
+    content.close_path();
+
+    let arm = 2.0 * r;
+    content.move_to(x - arm, y);
+    content.line_to(x + arm, y);
+    content.move_to(x, y - arm);
+    content.line_to(x, y + arm);
+}
@@ -0,0 +1,138 @@
+use super::preprocess::{apply_edits, content_range, is_svg_element, parse};
+use crate::{ColorScheme, MediaType, Result};
+
+/// Resolve the `@media` rules in the style sheets of an SVG, so that the SVG
+/// can be parsed with the matching styles. usvg ignores `@media` rules
+/// completely. Rules whose media query matches the media type and the color
+/// scheme are replaced by the style rules they contain, all other ones are
+/// removed. Only the content of `style` elements is touched.
+pub(crate) fn apply_media_queries(
+    svg: &str,
+    media: MediaType,
+    color_scheme: ColorScheme,
+) -> Result<String> {
+    if !svg.contains("@media") {
+        return Ok(svg.to_string());
+    }
+
+    let target = Target { media, color_scheme };
+    let document = parse(svg)?;
+    let mut edits = vec![];
+    for node in document.descendants().filter(|node| is_svg_element(*node, "style")) {
+        let Some(range) = content_range(svg, node) else { continue };
+        let css = &svg[range.clone()];
+        if css.contains("@media") {
+            edits.push((range, resolve_rules(css, target)));
+        }
+    }
+
+    Ok(apply_edits(svg, edits))
+}
+
+/// The media for which the rules are resolved.
+#[derive(Copy, Clone)]
+struct Target {
+    media: MediaType,
+    color_scheme: ColorScheme,
+}
+
+/// Resolve the `@media` rules of a style sheet.
+fn resolve_rules(css: &str, target: Target) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+
+    while let Some(start) = rest.find("@media") {
+        out.push_str(&rest[..start]);
+
+        let after = &rest[start + "@media".len()..];
+        let Some((query, body, remaining)) = split_rule(after) else {
+            // Unterminated rules are left alone.
+            out.push_str(&rest[start..]);
+            return out;
+        };
+
+        if query_list_matches(query, target) {
+            // Media rules can be nested.
+            out.push_str(&resolve_rules(body, target));
+        }
+
+        rest = remaining;
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Split the text after `@media` into the query, the body of the rule and the
+/// text after the rule.
+fn split_rule(text: &str) -> Option<(&str, &str, &str)> {
+    let open = text.find('{')?;
+    let mut depth = 0;
+
+    for (i, c) in text[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let close = open + i;
+                    return Some((
+                        &text[..open],
+                        &text[open + 1..close],
+                        &text[close + 1..],
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Whether any query in a comma-separated list of media queries matches.
+fn query_list_matches(list: &str, target: Target) -> bool {
+    list.split(',').any(|query| query_matches(query, target))
+}
+
+/// Whether a single media query like `not print and (min-width: 100px)` matches.
+fn query_matches(query: &str, target: Target) -> bool {
+    let query = query.trim().to_ascii_lowercase();
+    let query = query.as_str();
+    let (negated, query) = match query.strip_prefix("not ") {
+        Some(query) => (true, query),
+        None => (false, query.strip_prefix("only ").unwrap_or(query)),
+    };
+
+    let matches = query
+        .split(" and ")
+        .map(str::trim)
+        .all(|condition| condition_matches(condition, target));
+
+    matches != negated
+}
+
+/// Whether a media type or a media feature in parentheses matches.
+fn condition_matches(condition: &str, target: Target) -> bool {
+    if let Some(feature) = condition.strip_prefix('(').and_then(|c| c.strip_suffix(')')) {
+        let (name, value) = feature.split_once(':').unwrap_or((feature, ""));
+        return feature_matches(name.trim(), value.trim(), target);
+    }
+
+    match condition {
+        "all" => true,
+        "print" => target.media == MediaType::Print,
+        "screen" => target.media == MediaType::Screen,
+        _ => false,
+    }
+}
+
+/// Whether a media feature matches. Features that don't make sense for a PDF,
+/// like the width of the viewport, never match.
+fn feature_matches(name: &str, value: &str, target: Target) -> bool {
+    match (name, value) {
+        ("prefers-color-scheme", "light") => target.color_scheme == ColorScheme::Light,
+        ("prefers-color-scheme", "dark") => target.color_scheme == ColorScheme::Dark,
+        _ => false,
+    }
+}
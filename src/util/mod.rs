@@ -1,4 +1,17 @@
 pub mod allocate;
+pub mod clip;
 pub mod context;
+pub mod estimate;
+pub mod external_use;
+#[cfg(feature = "text")]
+pub mod font_face;
+pub mod foreign_object;
 pub mod helper;
+pub mod linearize;
+pub mod marks;
+pub mod media;
+pub mod preprocess;
 pub mod resources;
+pub mod symbols;
+#[cfg(feature = "text")]
+pub mod watermark;
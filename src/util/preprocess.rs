@@ -0,0 +1,370 @@
+use std::ops::Range;
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+use super::clip::apply_clip_properties;
+use super::external_use::resolve_external_uses;
+use super::foreign_object::replace_foreign_objects;
+use super::media::apply_media_queries;
+use crate::{ConversionError, PreprocessOptions, Result};
+
+/// The namespace of SVG elements.
+const SVG_NS: &str = "http://www.w3.org/2000/svg";
+/// The namespace of the `xml:` attributes.
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// An SVG source that was prepared with [`preprocess`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preprocessed {
+    /// The source that can be parsed into a [`usvg::Tree`].
+    pub svg: String,
+    /// The language of the root element from its `xml:lang` attribute, which
+    /// usvg doesn't preserve. It can be used as the
+    /// [`document_language`](crate::ConversionOptions::document_language).
+    pub language: Option<String>,
+    /// The warnings that were emitted while preprocessing, e.g. because a
+    /// `foreignObject` was replaced. They are also logged with the `log` crate.
+    pub warnings: Vec<String>,
+}
+
+/// Prepare an SVG source for parsing with usvg, handling the features that
+/// usvg drops or ignores while parsing.
+///
+/// Depending on the options, this
+/// - resolves `use` elements that refer to elements of other files,
+/// - resolves `@media` rules in style sheets,
+/// - applies the deprecated `clip` property of `svg` elements,
+/// - converts angles with CSS units in transforms into degrees,
+/// - converts percentages in `letter-spacing` into `em`,
+/// - evaluates `systemLanguage` attributes on any element and
+/// - handles `foreignObject` elements.
+///
+/// Since all of this happens on the source, it has no effect on trees that
+/// were parsed without it. [`convert_many`](crate::convert_many) and
+/// [`Converter::convert_data`](crate::Converter::convert_data) call it
+/// automatically.
+///
+/// Fails with [`ConversionError::InvalidSvg`] if the source isn't well-formed
+/// XML and with [`ConversionError::UnsupportedForeignObject`] if it contains a
+/// `foreignObject` that is rejected by the options.
+pub fn preprocess(svg: &str, options: &PreprocessOptions) -> Result<Preprocessed> {
+    let language = if svg.contains("xml:lang") { root_language(svg)? } else { None };
+
+    let mut warnings = vec![];
+    let mut svg = svg.to_string();
+    if let Some(resolver) = &options.resource_resolver {
+        svg = resolve_external_uses(&svg, resolver, &mut warnings)?;
+    }
+    svg = apply_media_queries(&svg, options.media, options.color_scheme)?;
+    svg = apply_clip_properties(&svg)?;
+    svg = convert_property(&svg, "transform", convert_angles)?;
+    svg = convert_property(&svg, "letter-spacing", convert_letter_spacing)?;
+    if let Some(language) = &options.language {
+        svg = resolve_system_language(&svg, language)?;
+    }
+    svg = replace_foreign_objects(&svg, options.foreign_object, &mut warnings)?;
+
+    Ok(Preprocessed { svg, language, warnings })
+}
+
+/// Log a warning and add it to a list of warnings.
+pub(crate) fn warn(warnings: &mut Vec<String>, warning: String) {
+    log::warn!("{warning}");
+    warnings.push(warning);
+}
+
+/// Parse an SVG source into an XML document with the same options that usvg
+/// uses.
+pub(crate) fn parse(svg: &str) -> Result<Document<'_>> {
+    let options = ParsingOptions { allow_dtd: true, ..ParsingOptions::default() };
+    Document::parse_with_options(svg, options).map_err(|_| ConversionError::InvalidSvg)
+}
+
+/// Whether a node is an SVG element with the given name. Elements without a
+/// namespace are treated as SVG elements, too.
+pub(crate) fn is_svg_element(node: Node, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().name() == name
+        && node.tag_name().namespace().map_or(true, |ns| ns == SVG_NS)
+}
+
+/// Whether a node is nested in an SVG element with the given name.
+pub(crate) fn is_inside(node: Node, name: &str) -> bool {
+    node.ancestors()
+        .skip(1)
+        .any(|ancestor| is_svg_element(ancestor, name))
+}
+
+/// Replace the given ranges of a source. The edits need to be sorted by the
+/// start of their range and must not overlap.
+pub(crate) fn apply_edits(svg: &str, edits: Vec<(Range<usize>, String)>) -> String {
+    let mut out = String::with_capacity(svg.len());
+    let mut last = 0;
+    for (range, replacement) in edits {
+        out.push_str(&svg[last..range.start]);
+        out.push_str(&replacement);
+        last = range.end;
+    }
+    out.push_str(&svg[last..]);
+    out
+}
+
+/// The source of the start tag of an element.
+pub(crate) fn start_tag<'a>(svg: &'a str, node: Node) -> &'a str {
+    let element = &svg[node.range()];
+    &element[..start_tag_len(element).unwrap_or(element.len())]
+}
+
+/// The name of an element as it is written in its start tag, including the
+/// prefix.
+pub(crate) fn element_name(start_tag: &str) -> &str {
+    let name = &start_tag[1..];
+    let len = name
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .unwrap_or(name.len());
+    &name[..len]
+}
+
+/// The range of the source between the start and the end tag of an element,
+/// or `None` if the element is self-closing.
+pub(crate) fn content_range(svg: &str, node: Node) -> Option<Range<usize>> {
+    let start = node.range().start;
+    let element = &svg[node.range()];
+    let tag_len = start_tag_len(element)?;
+    if element[..tag_len].ends_with("/>") {
+        return None;
+    }
+    Some(start + tag_len..start + element.rfind("</")?)
+}
+
+/// The length of the start tag at the beginning of an element, including the
+/// closing `>`.
+pub(crate) fn start_tag_len(element: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in element.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('>', None) => return Some(i + 1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Iterate over the attributes of a start tag as they are written in the
+/// source, i.e. with prefixes and without resolving entities.
+pub(crate) fn attributes(start_tag: &str) -> impl Iterator<Item = (&str, &str)> {
+    let tag = start_tag.trim_end_matches('>').trim_end_matches('/');
+    // Skip the name of the element.
+    let mut rest = &tag[tag.find(char::is_whitespace).unwrap_or(tag.len())..];
+
+    std::iter::from_fn(move || {
+        let (name, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let (value, remaining) = after[1..].split_once(quote)?;
+        rest = remaining;
+        Some((name.trim(), value))
+    })
+}
+
+/// Write an attribute with a value from [`attributes`] in double quotes.
+pub(crate) fn write_attribute(out: &mut String, name: &str, value: &str) {
+    out.push_str(&format!(r#" {name}="{}""#, value.replace('"', "&quot;")));
+}
+
+/// Read the `xml:lang` attribute of the root element of an SVG source.
+fn root_language(svg: &str) -> Result<Option<String>> {
+    let document = parse(svg)?;
+    let language = document.root_element().attribute((XML_NS, "lang")).map(str::trim);
+    Ok(language.filter(|language| !language.is_empty()).map(Into::into))
+}
+
+/// Resolve the `systemLanguage` attributes of an SVG source against a
+/// language. usvg evaluates them against the languages of its own options, so
+/// attributes that match are removed and all others are emptied, which makes
+/// their elements fail the condition no matter which languages usvg uses.
+///
+/// Like in usvg, a language in the attribute matches if it is equal to the
+/// given one or if its primary subtag is, so `en-US` matches `en`.
+fn resolve_system_language(svg: &str, language: &str) -> Result<String> {
+    if !svg.contains("systemLanguage") {
+        return Ok(svg.to_string());
+    }
+
+    let matches = |tag: &str| {
+        let tag = tag.trim();
+        let primary = tag.split('-').next().unwrap_or(tag);
+        tag.eq_ignore_ascii_case(language) || primary.eq_ignore_ascii_case(language)
+    };
+
+    let document = parse(svg)?;
+    let mut edits = vec![];
+    for node in document.descendants() {
+        let Some(languages) = node.attribute("systemLanguage") else { continue };
+
+        let tag = start_tag(svg, node);
+        let mut new = format!("<{}", element_name(tag));
+        for (name, value) in attributes(tag) {
+            if name != "systemLanguage" {
+                write_attribute(&mut new, name, value);
+            } else if !languages.split(',').any(matches) {
+                new.push_str(r#" systemLanguage="""#);
+            }
+        }
+        new.push_str(if tag.ends_with("/>") { "/>" } else { ">" });
+
+        let start = node.range().start;
+        edits.push((start..start + tag.len(), new));
+    }
+
+    Ok(apply_edits(svg, edits))
+}
+
+/// The transform functions that take an angle.
+const ANGLE_FUNCTIONS: &[&str] = &["rotate(", "skewX(", "skewY("];
+
+/// Convert the values of a property, both as a presentation attribute and in
+/// `style` attributes and style sheets. Values for which `convert` returns
+/// `None` are kept as-is.
+fn convert_property(
+    svg: &str,
+    property: &str,
+    convert: fn(&str) -> Option<String>,
+) -> Result<String> {
+    if !svg.contains(property) {
+        return Ok(svg.to_string());
+    }
+
+    let document = parse(svg)?;
+    let mut edits = vec![];
+    for node in document.descendants().filter(|node| node.is_element()) {
+        let tag = start_tag(svg, node);
+        let converted: Vec<_> = attributes(tag)
+            .map(|(name, value)| match name {
+                "style" => convert_declarations(value, property, convert),
+                _ if name == property => convert(value.trim()),
+                _ => None,
+            })
+            .collect();
+
+        if converted.iter().any(Option::is_some) {
+            let mut new = format!("<{}", element_name(tag));
+            for ((name, value), converted) in attributes(tag).zip(&converted) {
+                write_attribute(&mut new, name, converted.as_deref().unwrap_or(value));
+            }
+            new.push_str(if tag.ends_with("/>") { "/>" } else { ">" });
+
+            let start = node.range().start;
+            edits.push((start..start + tag.len(), new));
+        }
+
+        if is_svg_element(node, "style") {
+            if let Some(range) = content_range(svg, node) {
+                let css = &svg[range.clone()];
+                if let Some(converted) = convert_declarations(css, property, convert) {
+                    edits.push((range, converted));
+                }
+            }
+        }
+    }
+
+    Ok(apply_edits(svg, edits))
+}
+
+/// Convert the values of a property in CSS declarations. Returns `None` if
+/// none of them was converted.
+fn convert_declarations(
+    css: &str,
+    property: &str,
+    convert: fn(&str) -> Option<String>,
+) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = css;
+    let mut changed = false;
+    while let Some(i) = rest.find(property) {
+        let end = i + property.len();
+        // Other properties that contain the name, like `transform-origin`,
+        // are skipped.
+        let standalone = !rest[..i].ends_with(|c: char| c.is_alphanumeric() || c == '-');
+        let value = rest[end..].trim_start().strip_prefix(':').filter(|_| standalone);
+        let Some(value) = value else {
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        };
+
+        let start = rest.len() - value.len();
+        let value = &value[..value.find([';', '}', '!']).unwrap_or(value.len())];
+        let len = value.len();
+        out.push_str(&rest[..start]);
+        match convert(value.trim()) {
+            Some(converted) => {
+                changed = true;
+                // Keep the whitespace around the value.
+                out.push_str(&value[..value.len() - value.trim_start().len()]);
+                out.push_str(&converted);
+                out.push_str(&value[value.trim_end().len()..]);
+            }
+            None => out.push_str(value),
+        }
+        rest = &rest[start + len..];
+    }
+    out.push_str(rest);
+
+    changed.then_some(out)
+}
+
+/// Convert a percentage of the font size in `letter-spacing` into `em`. usvg
+/// resolves percentages against the viewport instead.
+fn convert_letter_spacing(value: &str) -> Option<String> {
+    let percentage = value.strip_suffix('%')?.trim().parse::<f32>().ok()?;
+    Some(format!("{}em", percentage / 100.0))
+}
+
+/// Convert the angles with CSS units in the transform functions of a value
+/// into degrees. Returns `None` if there are none.
+fn convert_angles(value: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut changed = false;
+    while let Some(start) = ANGLE_FUNCTIONS
+        .iter()
+        .filter_map(|function| Some(rest.find(function)? + function.len()))
+        .min()
+    {
+        let Some(len) = rest[start..].find(')') else { break };
+        out.push_str(&rest[..start]);
+        let arguments: Vec<String> = rest[start..start + len]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|argument| !argument.is_empty())
+            .map(|argument| match degrees(argument) {
+                Some(degrees) => {
+                    changed = true;
+                    degrees.to_string()
+                }
+                None => argument.to_string(),
+            })
+            .collect();
+        out.push_str(&arguments.join(" "));
+        rest = &rest[start + len..];
+    }
+    out.push_str(rest);
+
+    changed.then_some(out)
+}
+
+/// Parse an angle with a CSS unit into degrees.
+fn degrees(angle: &str) -> Option<f32> {
+    let units = [
+        ("deg", 1.0),
+        ("grad", 0.9),
+        ("rad", 180.0 / std::f32::consts::PI),
+        ("turn", 360.0),
+    ];
+    units.iter().find_map(|(unit, factor)| {
+        Some(angle.strip_suffix(unit)?.trim().parse::<f32>().ok()? * factor)
+    })
+}
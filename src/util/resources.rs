@@ -4,7 +4,7 @@ use std::rc::Rc;
 use crate::util::allocate::NameAllocator;
 use pdf_writer::types::ProcSet;
 use pdf_writer::writers::{ColorSpace, Resources};
-use pdf_writer::{Dict, Ref};
+use pdf_writer::{Dict, Name, Ref};
 
 use super::helper::NameExt;
 
@@ -16,6 +16,7 @@ enum PendingResourceType {
     Shading,
     Font,
     ColorSpace,
+    Properties,
 }
 
 impl PendingResourceType {
@@ -27,6 +28,9 @@ impl PendingResourceType {
             PendingResourceType::Shading => resources.shadings(),
             PendingResourceType::Font => resources.fonts(),
             PendingResourceType::ColorSpace => resources.color_spaces(),
+            PendingResourceType::Properties => {
+                resources.insert(Name(b"Properties")).dict()
+            }
         }
     }
 
@@ -38,6 +42,7 @@ impl PendingResourceType {
             PendingResourceType::Shading,
             PendingResourceType::Font,
             PendingResourceType::ColorSpace,
+            PendingResourceType::Properties,
         ]
         .iter()
         .copied()
@@ -68,6 +73,7 @@ impl PendingResource {
 }
 
 /// Holds all resources for an XObject or a page.
+///
 /// NOTE: References for distinct objects are assumed to be distinct,
 /// as a consequence, two same references are assumed to always point
 /// to the same object and thus will be deduplicated.
@@ -77,6 +83,12 @@ pub struct ResourceContainer {
     pending_resources: HashMap<Ref, PendingResource>,
 }
 
+impl Default for ResourceContainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ResourceContainer {
     fn add_resource_entry(
         &mut self,
@@ -104,6 +116,9 @@ impl ResourceContainer {
                     PendingResourceType::ColorSpace => {
                         self.name_allocator.alloc_color_space_name()
                     }
+                    PendingResourceType::Properties => {
+                        self.name_allocator.alloc_properties_name()
+                    }
                 };
 
                 let name = Rc::new(name);
@@ -113,6 +128,7 @@ impl ResourceContainer {
             .clone()
     }
 
+    /// Create a new, empty resource container.
     pub fn new() -> Self {
         Self {
             name_allocator: NameAllocator::default(),
@@ -121,36 +137,42 @@ impl ResourceContainer {
     }
 
     /// Add a new XObject as a resource. Returns the name of the XObject.
-    pub fn add_x_object(&mut self, reference: Ref) -> Rc<String> {
+    pub(crate) fn add_x_object(&mut self, reference: Ref) -> Rc<String> {
         self.add_resource_entry(reference, PendingResourceType::XObject)
     }
 
     /// Add a new Shading as a resource. Returns the name of the Shading.
-    pub fn add_shading(&mut self, reference: Ref) -> Rc<String> {
+    pub(crate) fn add_shading(&mut self, reference: Ref) -> Rc<String> {
         self.add_resource_entry(reference, PendingResourceType::Shading)
     }
 
     /// Add a new Pattern as a resource. Returns the name of the Pattern.
-    pub fn add_pattern(&mut self, reference: Ref) -> Rc<String> {
+    pub(crate) fn add_pattern(&mut self, reference: Ref) -> Rc<String> {
         self.add_resource_entry(reference, PendingResourceType::Pattern)
     }
 
     /// Add a new GraphicsState as a resource. Returns the name of the GraphicsState.
-    pub fn add_graphics_state(&mut self, reference: Ref) -> Rc<String> {
+    pub(crate) fn add_graphics_state(&mut self, reference: Ref) -> Rc<String> {
         self.add_resource_entry(reference, PendingResourceType::GraphicsState)
     }
 
     /// Add a new Font as a resource. Returns the name of the Font.
     #[cfg(feature = "text")]
-    pub fn add_font(&mut self, reference: Ref) -> Rc<String> {
+    pub(crate) fn add_font(&mut self, reference: Ref) -> Rc<String> {
         self.add_resource_entry(reference, PendingResourceType::Font)
     }
 
     /// Add a new ColorSpace as a resource. Returns the name of the ColorSpace.
-    pub fn add_color_space(&mut self, reference: Ref) -> Rc<String> {
+    pub(crate) fn add_color_space(&mut self, reference: Ref) -> Rc<String> {
         self.add_resource_entry(reference, PendingResourceType::ColorSpace)
     }
 
+    /// Add a new property list, like an optional content group, as a resource.
+    /// Returns the name of the property list.
+    pub(crate) fn add_properties(&mut self, reference: Ref) -> Rc<String> {
+        self.add_resource_entry(reference, PendingResourceType::Properties)
+    }
+
     /// Dump all pending resources into a resources dictionary.
     pub fn finish(self, resources: &mut Resources) {
         for object_type in PendingResourceType::iterator() {
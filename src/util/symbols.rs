@@ -0,0 +1,91 @@
+use usvg::Tree;
+
+use super::preprocess::{is_svg_element, parse};
+use crate::util::context::Context;
+use crate::{write_pdf, ConversionError, ConversionOptions, PageOptions, Result};
+
+/// The size of the area a symbol is fitted into, in points.
+const TILE_SIZE: f32 = 96.0;
+/// The height of the label below each symbol.
+const LABEL_HEIGHT: f32 = 16.0;
+/// The space between the tiles and around the edges of the page.
+const GAP: f32 = 24.0;
+const COLUMNS: usize = 4;
+const ROWS: usize = 5;
+
+/// Convert all `<symbol>` elements of an SVG into a PDF with a grid of labeled
+/// tiles, e.g. to preview an icon library.
+///
+/// usvg only keeps symbols that are instantiated with `<use>`, so this works
+/// on the SVG source. Each symbol is fitted into a tile of its own and labeled
+/// with its ID. Symbols without an ID are skipped. The pages hold up to twenty
+/// tiles each, in the order in which the symbols appear in the file.
+pub fn symbol_sheet(
+    svg: &str,
+    usvg_options: &usvg::Options,
+    conversion_options: ConversionOptions,
+) -> Result<Vec<u8>> {
+    let document = parse(svg)?;
+    let source = &svg[document.root_element().range()];
+    let ids = symbol_ids(&document);
+
+    // Empty sheets still get a page.
+    let pages: Vec<&[String]> = if ids.is_empty() {
+        vec![&ids[..]]
+    } else {
+        ids.chunks(COLUMNS * ROWS).collect()
+    };
+
+    let trees = pages
+        .into_iter()
+        .map(|ids| {
+            let page = page_svg(source, ids);
+            Tree::from_str(&page, usvg_options).map_err(|_| ConversionError::InvalidSvg)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut ctx = Context::new(conversion_options);
+    write_pdf(&trees, &mut ctx, PageOptions::default())
+}
+
+/// Find the IDs of all symbols in an SVG.
+fn symbol_ids(document: &roxmltree::Document) -> Vec<String> {
+    document
+        .descendants()
+        .filter(|node| is_svg_element(*node, "symbol"))
+        .filter_map(|node| node.attribute("id"))
+        .map(Into::into)
+        .collect()
+}
+
+/// Create an SVG for a page that shows the given symbols of the source along
+/// with their IDs. The whole source is embedded into the definitions once, so
+/// that the symbols can refer to anything else in the file.
+fn page_svg(source: &str, ids: &[String]) -> String {
+    let width = COLUMNS as f32 * (TILE_SIZE + GAP) + GAP;
+    let height = ROWS as f32 * (TILE_SIZE + LABEL_HEIGHT + GAP) + GAP;
+
+    let mut out = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"
+            width="{width}" height="{height}"><defs>{source}</defs>"#
+    );
+
+    for (i, id) in ids.iter().enumerate() {
+        let x = GAP + (i % COLUMNS) as f32 * (TILE_SIZE + GAP);
+        let y = GAP + (i / COLUMNS) as f32 * (TILE_SIZE + LABEL_HEIGHT + GAP);
+        let href = id.replace('&', "&amp;").replace('"', "&quot;");
+        let label = id.replace('&', "&amp;").replace('<', "&lt;");
+        out.push_str(&format!(
+            r##"<g transform="translate({x} {y})">
+                <use xlink:href="#{href}" width="{TILE_SIZE}" height="{TILE_SIZE}"/>
+                <text x="{center}" y="{baseline}" text-anchor="middle"
+                    font-family="sans-serif" font-size="10">{label}</text>
+            </g>"##,
+            center = TILE_SIZE / 2.0,
+            baseline = TILE_SIZE + LABEL_HEIGHT - 4.0,
+        ));
+    }
+
+    out.push_str("</svg>");
+    out
+}
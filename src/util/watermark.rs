@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use usvg::{Size, Tree};
+
+use crate::{ConversionError, Result, Watermark};
+
+/// Create a tree with the size of a page that shows the text of a watermark
+/// diagonally across its center.
+pub fn watermark_tree(
+    watermark: &Watermark,
+    size: Size,
+    fontdb: Arc<fontdb::Database>,
+) -> Result<Tree> {
+    let (width, height) = (size.width(), size.height());
+    let (x, y) = (width / 2.0, height / 2.0);
+    let text = watermark.text.replace('&', "&amp;").replace('<', "&lt;");
+    let usvg::Color { red, green, blue } = watermark.color;
+
+    // SVG rotates clockwise, while the angle of the watermark is
+    // counter-clockwise.
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}">
+            <text x="{x}" y="{y}" transform="rotate({angle} {x} {y})"
+                text-anchor="middle" dominant-baseline="central"
+                font-family="sans-serif" font-weight="bold" font-size="{font_size}"
+                fill="rgb({red}, {green}, {blue})" fill-opacity="{opacity}">{text}</text>
+        </svg>"#,
+        angle = -watermark.angle,
+        font_size = width.min(height) / 6.0,
+        opacity = watermark.opacity.clamp(0.0, 1.0),
+    );
+
+    let options = usvg::Options { fontdb, ..usvg::Options::default() };
+    Tree::from_str(&svg, &options).map_err(|_| ConversionError::UnknownError)
+}
@@ -9,10 +9,9 @@ OUT_PATH = ROOT / "src" / "render.rs"
 
 NO_RELATIVE_PATHS = "no relative paths supported"
 INVESTIGATE = "need to investigate"
-NO_REFLECT = "spreadMethod reflect not supported"
-NO_REPEAT = "spreadMethod repeat not supported"
 NO_SUPPORT = "not supported in PDF"
 NO_FONT = "font is not part of test suite yet"
+NO_REF = "reference image needs to be generated and reviewed"
 
 IGNORE_TESTS = {
     # The following test cases still need to be investigated
@@ -54,17 +53,38 @@ IGNORE_TESTS = {
     "svg/resvg/structure/image/zero-height.svg": NO_RELATIVE_PATHS,
     "svg/resvg/structure/image/zero-width.svg": NO_RELATIVE_PATHS,
 
-    # The following test cases should work but are not implemented in svg2pdf yet.
-    "svg/resvg/paint-servers/linearGradient/attributes-via-xlink-href-complex-order.svg": NO_REFLECT,
-    "svg/resvg/paint-servers/linearGradient/attributes-via-xlink-href-from-radialGradient.svg": NO_REFLECT,
-    "svg/resvg/paint-servers/linearGradient/spreadMethod=reflect.svg": NO_REFLECT,
-    "svg/resvg/paint-servers/linearGradient/spreadMethod=repeat.svg": NO_REPEAT,
-    "svg/resvg/paint-servers/radialGradient/attributes-via-xlink-href-complex-order.svg":NO_REFLECT,
-    "svg/resvg/paint-servers/radialGradient/attributes-via-xlink-href-from-linearGradient.svg": NO_REFLECT,
-    "svg/resvg/paint-servers/radialGradient/spreadMethod=reflect.svg": NO_REFLECT,
-    "svg/resvg/paint-servers/radialGradient/spreadMethod=repeat.svg": NO_REPEAT,
-    "svg/resvg/painting/stroke-linecap/zero-length-path-with-round.svg": "need to check how Chrome does it",
-    "svg/resvg/painting/stroke-linecap/zero-length-path-with-square.svg": "need to check how Firefox does it",
+    # The following test cases changed because filters of rotated or skewed
+    # groups are clipped to their region now, so their old references are gone.
+    "svg/resvg/filters/feDiffuseLighting/complex-transform.svg": NO_REF,
+    "svg/resvg/filters/feFlood/complex-transform.svg": NO_REF,
+    "svg/resvg/filters/feGaussianBlur/complex-transform.svg": NO_REF,
+    "svg/resvg/filters/feImage/link-on-an-element-with-complex-transform.svg": NO_REF,
+    "svg/resvg/filters/feImage/with-subregion-5.svg": NO_REF,
+    "svg/resvg/filters/feMerge/complex-transform.svg": NO_REF,
+    "svg/resvg/filters/feOffset/complex-transform.svg": NO_REF,
+    "svg/resvg/filters/fePointLight/complex-transform.svg": NO_REF,
+    "svg/resvg/filters/feSpotLight/complex-transform.svg": NO_REF,
+    "svg/resvg/filters/feTile/complex-transform.svg": NO_REF,
+    "svg/resvg/filters/feTurbulence/complex-transform.svg": NO_REF,
+    "svg/resvg/filters/filter/transform-on-shape-with-filter-region.svg": NO_REF,
+    "svg/resvg/filters/filter/transform-on-shape.svg": NO_REF,
+    "svg/resvg/filters/filter/with-multiple-transforms-1.svg": NO_REF,
+
+    # Repeating and reflecting gradients are supported now, but these test cases
+    # don't have references yet.
+    "svg/resvg/paint-servers/linearGradient/attributes-via-xlink-href-complex-order.svg": NO_REF,
+    "svg/resvg/paint-servers/linearGradient/attributes-via-xlink-href-from-radialGradient.svg": NO_REF,
+    "svg/resvg/paint-servers/linearGradient/spreadMethod=reflect.svg": NO_REF,
+    "svg/resvg/paint-servers/linearGradient/spreadMethod=repeat.svg": NO_REF,
+    "svg/resvg/paint-servers/radialGradient/attributes-via-xlink-href-complex-order.svg": NO_REF,
+    "svg/resvg/paint-servers/radialGradient/attributes-via-xlink-href-from-linearGradient.svg": NO_REF,
+    "svg/resvg/paint-servers/radialGradient/spreadMethod=reflect.svg": NO_REF,
+    "svg/resvg/paint-servers/radialGradient/spreadMethod=repeat.svg": NO_REF,
+
+    # Round and square caps of degenerate subpaths are drawn now, but these test
+    # cases don't have references yet.
+    "svg/resvg/painting/stroke-linecap/zero-length-path-with-round.svg": NO_REF,
+    "svg/resvg/painting/stroke-linecap/zero-length-path-with-square.svg": NO_REF,
 }
 
 
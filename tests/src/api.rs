@@ -1,13 +1,21 @@
 #[allow(unused_imports)]
 use {
+    crate::read_svg,
     crate::render_pdf,
     crate::FONTDB,
-    crate::{convert_svg, run_test_impl},
-    pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str},
+    crate::{
+        capture_warnings, contains, convert_str, convert_svg, get_diff, ink_bounds,
+        page_count, run_test_impl,
+    },
+    pdf_writer::{Chunk, Content, Finish, Name, Pdf, Rect, Ref, Str},
     std::collections::HashMap,
     std::path::Path,
-    svg2pdf::ConversionOptions,
-    svg2pdf::PageOptions,
+    std::sync::Arc,
+    svg2pdf::ConversionError,
+    svg2pdf::{ColorScheme, ConversionOptions, ForeignObjectPolicy, MediaType},
+    svg2pdf::{Context, ResourceContainer},
+    svg2pdf::{Fit, PageOptions},
+    svg2pdf::{PreprocessOptions, Preprocessed, ResourceResolver},
 };
 
 #[test]
@@ -24,7 +32,7 @@ fn text_to_paths() {
 #[test]
 fn dpi() {
     let conversion_options = ConversionOptions::default();
-    let page_options = PageOptions { dpi: 140.0 };
+    let page_options = PageOptions { dpi: 140.0, ..PageOptions::default() };
 
     let svg_path = "svg/resvg/text/text/simple-case.svg";
     let (pdf, actual_image) =
@@ -89,3 +97,2998 @@ fn to_chunk() {
 
     assert_eq!(res, 0);
 }
+
+#[test]
+fn max_output_bytes() {
+    let mut svg = String::from(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">"#,
+    );
+    for i in 0..200 {
+        svg.push_str(&format!(
+            r#"<circle cx="{}" cy="{}" r="5" fill="rgb({}, 0, 0)" opacity="0.5"/>"#,
+            i, i, i
+        ));
+    }
+    svg.push_str("</svg>");
+
+    let options = ConversionOptions {
+        max_output_bytes: Some(4096),
+        ..ConversionOptions::default()
+    };
+    let res = convert_str(&svg, options, PageOptions::default());
+    assert!(matches!(res, Err(ConversionError::LimitExceeded)));
+
+    let res = convert_str(&svg, ConversionOptions::default(), PageOptions::default());
+    assert!(res.is_ok());
+}
+
+#[test]
+fn max_output_bytes_rasterized() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="2000" height="2000">
+        <filter id="blur"><feGaussianBlur stdDeviation="5"/></filter>
+        <rect x="100" y="100" width="1800" height="1800" filter="url(#blur)"/>
+    </svg>"#;
+
+    // The pixmap for the filter alone would take up more than 20 MB, so the
+    // conversion is aborted before it's allocated, even though the encoded
+    // image would be much smaller.
+    let options = ConversionOptions {
+        max_output_bytes: Some(1_000_000),
+        ..ConversionOptions::default()
+    };
+    let res = convert_str(svg, options, PageOptions::default());
+    assert!(matches!(res, Err(ConversionError::LimitExceeded)));
+
+    let options = ConversionOptions {
+        max_output_bytes: Some(100_000_000),
+        ..ConversionOptions::default()
+    };
+    assert!(convert_str(svg, options, PageOptions::default()).is_ok());
+}
+
+#[test]
+fn max_nodes() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="10" height="10"/>
+        <rect x="20" width="10" height="10"/>
+        <rect x="40" width="10" height="10"/>
+    </svg>"#;
+
+    let options =
+        ConversionOptions { max_nodes: Some(2), ..ConversionOptions::default() };
+    let res = convert_str(svg, options, PageOptions::default());
+    assert!(matches!(res, Err(ConversionError::LimitExceeded)));
+
+    let options =
+        ConversionOptions { max_nodes: Some(3), ..ConversionOptions::default() };
+    let res = convert_str(svg, options, PageOptions::default());
+    assert!(res.is_ok());
+}
+
+#[test]
+fn jpeg_exif_orientation() {
+    let svg =
+        std::fs::read_to_string("svg/custom/structure/image/jpeg-exif-orientation.svg")
+            .unwrap();
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(&svg, options, PageOptions::default()).unwrap();
+
+    // The image is rotated by 90 degrees when drawing it into the unit square.
+    assert!(contains(&pdf, "0 1 1 0 0 0 cm"));
+
+    // The width and height of the image are swapped by the rotation. The left
+    // half of the image, which is black, ends up at the top.
+    let svg = std::fs::read_to_string(
+        "svg/custom/structure/image/jpeg-exif-orientation-non-square.svg",
+    )
+    .unwrap();
+    let pdf =
+        convert_str(&svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+    assert_eq!(ink_bounds(&image), Some((0, 0, 15, 31)));
+    assert!(image.get_pixel(8, 4).0[0] < 32);
+    assert!(image.get_pixel(8, 28).0[0] > 224);
+}
+
+#[test]
+fn dash_pattern() {
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let svg = |dasharray: &str, dashoffset: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <path d="M 10 10 L 90 10" stroke="black"
+                    stroke-dasharray="{dasharray}" stroke-dashoffset="{dashoffset}"/>
+            </svg>"#
+        )
+    };
+
+    let pdf =
+        convert_str(&svg("4 2", "1"), options.clone(), PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "[4 2] 1 d"));
+
+    let pdf =
+        convert_str(&svg("4 2", "-1"), options.clone(), PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "[4 2] 5 d"));
+
+    let pdf = convert_str(&svg("4 2 1", "0"), options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "[4 2 1 4 2 1] 0 d"));
+}
+
+#[test]
+fn miter_limit_and_line_join() {
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <path d="M 10 90 L 50 10 L 90 90" fill="none" stroke="black" stroke-width="5"
+            stroke-linejoin="miter" stroke-miterlimit="12"/>
+    </svg>"#;
+
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "12 M"));
+    assert!(contains(&pdf, "0 j"));
+}
+
+#[test]
+fn degenerate_subpath_with_caps() {
+    let svg = |linecap: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <path d="M 50 50 L 50 50" stroke="black" stroke-width="10"
+                    stroke-linecap="{linecap}"/>
+            </svg>"#
+        )
+    };
+
+    let pdf =
+        convert_str(&svg("butt"), ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(50, 50).0[3], 0);
+
+    let pdf =
+        convert_str(&svg("round"), ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(50, 50).0[3], 255);
+    assert_eq!(image.get_pixel(54, 54).0[3], 0);
+
+    let pdf =
+        convert_str(&svg("square"), ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(50, 50).0[3], 255);
+    assert_eq!(image.get_pixel(54, 54).0[3], 255);
+}
+
+#[test]
+fn degenerate_subpaths_with_square_caps() {
+    // Only some subpaths are degenerate, so the path as a whole isn't.
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <path d="M 20 20 L 20 20 M 50 50 L 50 50 M 10 80 L 90 80" stroke="black"
+            stroke-width="10" stroke-linecap="square"/>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+    for (x, y) in [(16, 16), (23, 23), (46, 46), (53, 53)] {
+        assert_eq!(image.get_pixel(x, y).0[3], 255);
+    }
+    assert_eq!(image.get_pixel(35, 35).0[3], 0);
+
+    // The other subpaths keep their square caps, which extend past their ends.
+    assert_eq!(image.get_pixel(7, 80).0[3], 255);
+    assert_eq!(image.get_pixel(92, 80).0[3], 255);
+}
+
+#[test]
+fn empty_geometry() {
+    let svg = |path: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                {path}
+            </svg>"#
+        )
+    };
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let has_path_operators = |path: &str| {
+        let pdf =
+            convert_str(&svg(path), options.clone(), PageOptions::default()).unwrap();
+        [" m\n", " l\n", " c\n", "\nf\n", "\nf*\n", "\nS\n"]
+            .iter()
+            .any(|operator| contains(&pdf, operator))
+    };
+
+    assert!(!has_path_operators(r#"<path d=""/>"#));
+    assert!(!has_path_operators(r#"<path d="M 10 10"/>"#));
+    assert!(!has_path_operators(r#"<path d="M 10 10 L 90 10" fill="black"/>"#));
+    assert!(!has_path_operators(
+        r#"<path d="M 10 10 L 10 10 M 20 20 L 20 20" stroke="black"/>"#
+    ));
+
+    // Degenerate subpaths are still visible with round caps.
+    assert!(has_path_operators(
+        r#"<path d="M 10 10 L 10 10 M 20 20 L 20 20" stroke="black"
+            stroke-linecap="round"/>"#
+    ));
+}
+
+#[test]
+fn min_stroke_width() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <path d="M 10 10 L 90 90" stroke="black" stroke-width="0.0001"/>
+        <g transform="scale(4)">
+            <path d="M 10 10 L 20 20" stroke="black" stroke-width="0.0001"/>
+        </g>
+    </svg>"#;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options.clone(), PageOptions::default()).unwrap();
+    assert!(!contains(&pdf, "0.5 w"));
+
+    let options = ConversionOptions { min_stroke_width: Some(0.5), ..options };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "0.5 w"));
+    assert!(contains(&pdf, "0.125 w"));
+}
+
+#[test]
+fn stroke_with_gradient() {
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <linearGradient id="lg">
+            <stop offset="0" stop-color="red"/>
+            <stop offset="1" stop-color="blue"/>
+        </linearGradient>
+        <path d="M 10 50 L 90 50" fill="none" stroke="url(#lg)" stroke-width="10"/>
+    </svg>"##;
+
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/Pattern CS"));
+    assert!(contains(&pdf, "SCN"));
+    assert!(contains(&pdf, "/ShadingType 2"));
+    assert!(!contains(&pdf, " SC\n"));
+}
+
+#[test]
+fn fill_and_stroke_opacity() {
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect x="10" y="10" width="80" height="80" fill="green" stroke="blue"
+            stroke-width="5" fill-opacity="0.3" stroke-opacity="0.7"/>
+    </svg>"#;
+
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/ca 0.3"));
+    assert!(contains(&pdf, "/CA 0.7"));
+    assert!(!contains(&pdf, "/CA 0.3"));
+    assert!(!contains(&pdf, "/ca 0.7"));
+}
+
+#[test]
+fn gradient_and_pattern_transform() {
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <linearGradient id="lg" gradientUnits="userSpaceOnUse" x1="0" x2="100"
+            gradientTransform="matrix(0 1 -1 0 0 0)">
+            <stop offset="0" stop-color="red"/>
+            <stop offset="1" stop-color="blue"/>
+        </linearGradient>
+        <rect width="100" height="100" fill="url(#lg)"/>
+    </svg>"##;
+
+    let pdf = convert_str(svg, options.clone(), PageOptions::default()).unwrap();
+    // The rotation is combined with the transform that flips the y-axis of the page.
+    assert!(contains(&pdf, "/Matrix [0 -1 -1 0 0 100]"));
+
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <pattern id="pat" patternUnits="userSpaceOnUse" width="20" height="20"
+            patternTransform="matrix(0 1 -1 0 0 0)">
+            <rect width="10" height="20" fill="green"/>
+        </pattern>
+        <rect width="100" height="100" fill="url(#pat)"/>
+    </svg>"##;
+
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/Matrix [0 -1 -1 0 0 100]"));
+}
+
+#[test]
+fn pattern_with_gradient() {
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <radialGradient id="rg">
+            <stop offset="0" stop-color="red"/>
+            <stop offset="1" stop-color="blue"/>
+        </radialGradient>
+        <pattern id="pat" patternUnits="userSpaceOnUse" width="50" height="50">
+            <rect width="50" height="50" fill="url(#rg)"/>
+        </pattern>
+        <rect width="100" height="100" fill="url(#pat)"/>
+    </svg>"##;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+
+    // The center of each tile is red, while its corners are blue.
+    for (x, y) in [(25, 25), (75, 25), (25, 75), (75, 75)] {
+        let [r, _, b, a] = image.get_pixel(x, y).0;
+        assert!(r > 200 && b < 50 && a == 255);
+    }
+
+    for (x, y) in [(1, 1), (51, 51), (98, 98)] {
+        let [r, _, b, a] = image.get_pixel(x, y).0;
+        assert!(r < 50 && b > 200 && a == 255);
+    }
+}
+
+#[test]
+fn nested_svg_viewport_clip() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <svg x="10" y="10" width="40" height="40" viewBox="0 0 20 20">
+            <rect x="-10" y="-10" width="100" height="100" fill="black"/>
+        </svg>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+
+    assert_eq!(image.get_pixel(30, 30).0[3], 255);
+    assert_eq!(image.get_pixel(5, 5).0[3], 0);
+    assert_eq!(image.get_pixel(55, 30).0[3], 0);
+    assert_eq!(image.get_pixel(30, 55).0[3], 0);
+    assert_eq!(image.get_pixel(75, 75).0[3], 0);
+}
+
+#[test]
+fn preserve_aspect_ratio() {
+    // A 20x10 image whose left half is red and whose right half is blue.
+    const IMAGE: &str = concat!(
+        "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAABQAAAAKCAIAAAA7N+mxAAAAGElEQVR42",
+        "mP4z8CAB+GV/M8wqnlUMyHNAGn9xzn3KHifAAAAAElFTkSuQmCC"
+    );
+
+    let color = |image: &image::RgbaImage, x: u32, y: u32| match image.get_pixel(x, y).0 {
+        [_, _, _, 0] => "none",
+        [r, _, b, _] if r > 200 && b < 50 => "red",
+        [r, _, b, _] if r < 50 && b > 200 => "blue",
+        _ => "mixed",
+    };
+
+    let cases: [(&str, &[(u32, u32, &str)]); 6] = [
+        (
+            "xMidYMid meet",
+            &[(50, 10, "none"), (20, 50, "red"), (80, 50, "blue"), (50, 90, "none")],
+        ),
+        ("xMinYMin meet", &[(20, 20, "red"), (80, 20, "blue"), (50, 70, "none")]),
+        ("xMaxYMax meet", &[(20, 80, "red"), (80, 80, "blue"), (50, 20, "none")]),
+        ("xMinYMin slice", &[(20, 50, "red"), (70, 50, "red")]),
+        ("xMaxYMid slice", &[(30, 50, "blue"), (80, 50, "blue")]),
+        ("none", &[(20, 90, "red"), (80, 10, "blue")]),
+    ];
+
+    for (aspect, expected) in cases {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <image width="100" height="100" preserveAspectRatio="{aspect}" href="{IMAGE}"/>
+            </svg>"#
+        );
+
+        let pdf = convert_str(&svg, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+        let image = render_pdf(&pdf);
+
+        for &(x, y, c) in expected {
+            assert_eq!(color(&image, x, y), c, "{aspect} at ({x}, {y})");
+        }
+    }
+
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <svg width="100" height="100" viewBox="0 0 20 10" preserveAspectRatio="xMidYMax meet">
+            <rect width="10" height="10" fill="red"/>
+            <rect x="10" width="10" height="10" fill="blue"/>
+        </svg>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+    assert_eq!(color(&image, 20, 75), "red");
+    assert_eq!(color(&image, 80, 75), "blue");
+    assert_eq!(color(&image, 50, 25), "none");
+}
+
+#[test]
+fn negative_and_large_view_box() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"
+        viewBox="-1000 -1000 2000 2000">
+        <circle r="500" fill="black"/>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+
+    // The circle is centered on the page and has a radius of 25.
+    assert_eq!(image.get_pixel(50, 50).0[3], 255);
+    assert_eq!(image.get_pixel(28, 50).0[3], 255);
+    assert_eq!(image.get_pixel(71, 50).0[3], 255);
+    assert_eq!(image.get_pixel(50, 28).0[3], 255);
+    assert_eq!(image.get_pixel(50, 71).0[3], 255);
+    assert_eq!(image.get_pixel(22, 50).0[3], 0);
+    assert_eq!(image.get_pixel(77, 50).0[3], 0);
+    assert_eq!(image.get_pixel(10, 10).0[3], 0);
+
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"
+        viewBox="1000000 -1000000 100 100">
+        <rect x="1000000" y="-1000000" width="50" height="100" fill="black"/>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(25, 50).0[3], 255);
+    assert_eq!(image.get_pixel(75, 50).0[3], 0);
+}
+
+#[test]
+fn relative_size_uses_default_size() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100%" height="100%">
+        <rect width="10" height="10"/>
+    </svg>"#;
+
+    let options = usvg::Options {
+        default_size: usvg::Size::from_wh(300.0, 200.0).unwrap(),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).unwrap();
+    let pdf =
+        svg2pdf::to_pdf(&tree, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+
+    assert!(contains(&pdf, "/MediaBox [0 0 300 200]"));
+}
+
+#[test]
+fn small_caps() {
+    let svg = |variant: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="50">
+                <text x="10" y="35" font-family="Noto Sans" font-size="30"
+                    font-variant="{variant}">abc</text>
+            </svg>"#
+        )
+    };
+
+    let render = |svg: &str, embed_text: bool| {
+        let options = ConversionOptions { embed_text, ..ConversionOptions::default() };
+        render_pdf(&convert_str(svg, options, PageOptions::default()).unwrap())
+    };
+
+    // The embedded text needs to use the same small-cap glyphs as the outlines
+    // produced by usvg, which have been shaped with the `smcp` feature.
+    let embedded = render(&svg("small-caps"), true);
+    let outlined = render(&svg("small-caps"), false);
+    let normal = render(&svg("normal"), true);
+
+    let (_, outlined_diff) = get_diff(&embedded, &outlined);
+    let (_, normal_diff) = get_diff(&embedded, &normal);
+    assert!(outlined_diff < normal_diff);
+}
+
+#[test]
+fn baseline_shift() {
+    let svg = |shift: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <text x="10" y="50" font-family="Noto Sans" font-size="20">
+                    <tspan baseline-shift="{shift}">x</tspan>
+                </text>
+            </svg>"#
+        )
+    };
+
+    let bounds = |shift: &str| {
+        let pdf = convert_str(
+            &svg(shift),
+            ConversionOptions::default(),
+            PageOptions::default(),
+        )
+        .unwrap();
+        ink_bounds(&render_pdf(&pdf)).unwrap()
+    };
+
+    let baseline = bounds("0");
+    let numeric = bounds("-10");
+    assert_eq!(numeric.1, baseline.1 + 10);
+    assert_eq!(numeric.3, baseline.3 + 10);
+
+    assert!(bounds("sub").1 > baseline.1);
+    assert!(bounds("super").1 < baseline.1);
+}
+
+#[test]
+fn letter_and_word_spacing() {
+    let svg = |spacing: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="50">
+                <text x="10" y="35" font-family="Noto Sans" font-size="20" {spacing}>ab c</text>
+            </svg>"#
+        )
+    };
+
+    let width = |spacing: &str| {
+        let preprocessed =
+            svg2pdf::preprocess(&svg(spacing), &PreprocessOptions::default()).unwrap();
+        let pdf = convert_str(
+            &preprocessed.svg,
+            ConversionOptions::default(),
+            PageOptions::default(),
+        )
+        .unwrap();
+        let (x_min, _, x_max, _) = ink_bounds(&render_pdf(&pdf)).unwrap();
+        (x_max - x_min) as i32
+    };
+
+    let normal = width("");
+    // Three gaps between the four characters, including the space.
+    assert!((width(r#"letter-spacing="5""#) - normal - 15).abs() <= 1);
+    assert_eq!(width(r#"letter-spacing="normal""#), normal);
+    // Relative to the font size of 20.
+    assert!((width(r#"letter-spacing="0.1em""#) - normal - 6).abs() <= 1);
+    assert!((width(r#"letter-spacing="10%""#) - normal - 6).abs() <= 1);
+    assert!((width(r#"style="letter-spacing: 10%""#) - normal - 6).abs() <= 1);
+    // A single space.
+    assert!((width(r#"word-spacing="10""#) - normal - 10).abs() <= 1);
+}
+
+#[test]
+fn language() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="50">
+        <text x="10" y="35" font-family="Noto Sans" font-size="20">Bonjour</text>
+    </svg>"#;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options.clone(), PageOptions::default()).unwrap();
+    assert!(!contains(&pdf, "BDC"));
+
+    let options = ConversionOptions { language: Some("fr".to_string()), ..options };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/Span"));
+    assert!(contains(&pdf, "/Lang (fr)"));
+    assert!(contains(&pdf, "BDC"));
+    assert!(contains(&pdf, "EMC"));
+}
+
+#[test]
+fn font_face_data_url() {
+    let svg = std::fs::read_to_string("svg/custom/text/font-face/woff.svg").unwrap();
+
+    let mut fontdb = fontdb::Database::new();
+    assert_eq!(svg2pdf::load_font_faces(&svg, &mut fontdb).count, 1);
+
+    let options = usvg::Options {
+        fontdb: Arc::new(fontdb),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(&svg, &options).unwrap();
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let output = svg2pdf::convert(&tree, options, PageOptions::default()).unwrap();
+    assert!(contains(&output.pdf, "+Yellowtail-Regular"));
+    assert!(contains(&output.pdf, "/FontFile2"));
+    assert_eq!(output.stats.fonts, 1);
+}
+
+#[test]
+fn shared_context() {
+    let svg = |text: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50">
+                <text x="5" y="30" font-family="Noto Sans" font-size="20">{text}</text>
+            </svg>"#
+        )
+    };
+
+    let trees = [read_svg(&svg("Hello")), read_svg(&svg("World"))];
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let mut ctx = Context::new(options);
+    let mut pdf = Pdf::new();
+
+    let catalog_ref = ctx.alloc_ref();
+    let page_tree_ref = ctx.alloc_ref();
+    let mut page_refs = vec![];
+
+    for tree in &trees {
+        let page_ref = ctx.alloc_ref();
+        let content_ref = ctx.alloc_ref();
+
+        let mut content = Content::new();
+        let mut rc = ResourceContainer::new();
+        ctx.render_tree(tree, &mut pdf, &mut content, &mut rc).unwrap();
+        pdf.stream(content_ref, &content.finish());
+
+        let mut page = pdf.page(page_ref);
+        page.media_box(Rect::new(0.0, 0.0, 100.0, 50.0))
+            .parent(page_tree_ref)
+            .contents(content_ref);
+        rc.finish(&mut page.resources());
+        page.finish();
+
+        page_refs.push(page_ref);
+    }
+
+    pdf.catalog(catalog_ref).pages(page_tree_ref);
+    pdf.pages(page_tree_ref).count(2).kids(page_refs);
+    ctx.finish(&mut pdf).unwrap();
+    let pdf = pdf.finish();
+
+    let count = |needle: &str| {
+        pdf.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+    };
+
+    // Both pages use the same font and ICC profile, so they are only written once.
+    assert_eq!(count("/FontFile2"), 1);
+    assert_eq!(count("/Range [0 1 0 1 0 1]"), 1);
+    assert_eq!(count("/Type /Page\n"), 2);
+}
+
+#[test]
+fn render_into_content() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect x="10" y="10" width="80" height="80" fill="green" fill-opacity="0.5"/>
+    </svg>"#;
+    let tree = read_svg(svg);
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let mut ctx = Context::new(options);
+    let mut chunk = Chunk::new();
+    let mut content = Content::new();
+    let mut rc = ResourceContainer::new();
+
+    let transform = svg2pdf::usvg::Transform::from_row(0.5, 0.0, 0.0, 0.5, 20.0, 30.0);
+    ctx.render_into_content(&tree, &mut chunk, &mut content, &mut rc, transform)
+        .unwrap();
+    ctx.finish(&mut chunk).unwrap();
+
+    let content = content.finish();
+    assert!(contains(&content, "0.5 0 0 0.5 20 30 cm"));
+    assert!(contains(&content, " re\n") || contains(&content, " l\n"));
+    assert!(contains(&content, "/gs0 gs"));
+
+    // The graphics state that is referenced in the content stream is
+    // registered in the resource container.
+    let mut pdf = Pdf::new();
+    let mut page = pdf.page(Ref::new(1000));
+    rc.finish(&mut page.resources());
+    page.finish();
+    assert!(contains(&pdf.finish(), "/gs0"));
+}
+
+#[test]
+fn in_memory_fonts() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="50">
+        <text x="5" y="30" font-family="Noto Sans" font-size="20">Hello</text>
+    </svg>"#;
+
+    let mut fontdb = fontdb::Database::new();
+    fontdb.load_font_data(std::fs::read("fonts/NotoSans-Regular.ttf").unwrap());
+
+    let options = usvg::Options {
+        fontdb: Arc::new(fontdb),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).unwrap();
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = svg2pdf::to_pdf(&tree, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "+NotoSans-Regular"));
+    assert!(contains(&pdf, "/FontFile2"));
+}
+
+#[test]
+fn capi_convert() {
+    use svg2pdf::capi::*;
+
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="50" height="50" fill="green"/>
+    </svg>"#;
+
+    let mut options = Svg2pdfOptions::default();
+    unsafe { svg2pdf_default_options(&mut options) };
+    options.compress = false;
+
+    let mut out = std::ptr::null_mut();
+    let mut out_len = 0;
+    let code = unsafe {
+        svg2pdf_convert(svg.as_ptr(), svg.len(), &options, &mut out, &mut out_len)
+    };
+    assert_eq!(code, SVG2PDF_OK);
+
+    let pdf = unsafe { std::slice::from_raw_parts(out, out_len) }.to_vec();
+    unsafe { svg2pdf_free(out, out_len) };
+    assert!(pdf.starts_with(b"%PDF-"));
+    assert!(contains(&pdf, "/MediaBox [0 0 100 100]"));
+
+    let invalid = "<svg";
+    let code = unsafe {
+        svg2pdf_convert(
+            invalid.as_ptr(),
+            invalid.len(),
+            std::ptr::null(),
+            &mut out,
+            &mut out_len,
+        )
+    };
+    assert_eq!(code, SVG2PDF_ERROR_INVALID_SVG);
+    assert!(out.is_null());
+}
+
+#[test]
+fn shared_context_xobjects() {
+    let svg = |color: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <rect width="50" height="50" fill="{color}"/>
+            </svg>"#
+        )
+    };
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let mut ctx = Context::new(options);
+    let mut chunk = Chunk::new();
+
+    let refs: Vec<_> = ["red", "green", "blue"]
+        .iter()
+        .map(|color| ctx.render_xobject(&read_svg(&svg(color)), &mut chunk).unwrap())
+        .collect();
+    ctx.finish(&mut chunk).unwrap();
+
+    assert_eq!(refs.len(), 3);
+    let icc_profiles = chunk
+        .as_bytes()
+        .windows(b"/Range [0 1 0 1 0 1]".len())
+        .filter(|w| *w == b"/Range [0 1 0 1 0 1]")
+        .count();
+    assert_eq!(icc_profiles, 1);
+}
+
+#[test]
+fn flatten_transparency() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <circle cx="40" cy="50" r="30" fill="red" fill-opacity="0.5"/>
+        <circle cx="60" cy="50" r="30" fill="blue" opacity="0.5"/>
+    </svg>"#;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options.clone(), PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/ca 0.5"));
+
+    let options = ConversionOptions { flatten_transparency: true, ..options };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(!contains(&pdf, "/ca"));
+    assert!(!contains(&pdf, "/CA"));
+    assert!(!contains(&pdf, "/SMask"));
+    assert!(!contains(&pdf, "/Group"));
+
+    // The overlapping region is composited onto a white background.
+    let image = render_pdf(&pdf);
+    let [r, g, b, a] = image.get_pixel(50, 50).0;
+    assert_eq!(a, 255);
+    assert!(r > 100 && g < 100 && b > 100);
+    assert_eq!(image.get_pixel(2, 2).0, [255, 255, 255, 255]);
+}
+
+#[test]
+fn shared_complex_clip_path() {
+    // Overlapping shapes with the even-odd rule can't be represented with a native
+    // clip path, so a soft mask is needed.
+    let rects: String = (0..20)
+        .map(|_| r#"<rect width="100" height="100" clip-path="url(#clip)"/>"#)
+        .collect();
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <clipPath id="clip">
+                <rect width="60" height="60" clip-rule="evenodd"/>
+                <rect x="40" y="40" width="60" height="60" clip-rule="evenodd"/>
+            </clipPath>
+            {rects}
+        </svg>"#
+    );
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(&svg, options, PageOptions::default()).unwrap();
+
+    let count = |needle: &str| {
+        pdf.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+    };
+    assert_eq!(count("/S /Alpha"), 1);
+}
+
+#[test]
+fn complex_clip_paths_of_shared_context() {
+    let svg = |size: u32| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <clipPath id="clip">
+                    <rect width="{size}" height="{size}" clip-rule="evenodd"/>
+                    <rect x="40" y="40" width="60" height="60" clip-rule="evenodd"/>
+                </clipPath>
+                <rect width="100" height="100" clip-path="url(#clip)"/>
+            </svg>"#
+        )
+    };
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let mut ctx = Context::new(options);
+    let mut chunk = Chunk::new();
+
+    // Each tree is dropped before the next one is parsed, so their clip paths
+    // can end up at the same address. They still get a soft mask each.
+    for size in 50..60 {
+        let tree = read_svg(&svg(size));
+        ctx.render_xobject(&tree, &mut chunk).unwrap();
+    }
+    ctx.finish(&mut chunk).unwrap();
+
+    let count = chunk
+        .as_bytes()
+        .windows(b"/S /Alpha".len())
+        .filter(|w| *w == b"/S /Alpha");
+    assert_eq!(count.count(), 10);
+}
+
+#[test]
+fn nested_clip_paths() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <clipPath id="rect">
+            <rect width="50" height="100"/>
+        </clipPath>
+        <clipPath id="circle">
+            <circle cx="50" cy="50" r="30"/>
+        </clipPath>
+        <g clip-path="url(#rect)">
+            <rect width="100" height="100" fill="black" clip-path="url(#circle)"/>
+        </g>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+
+    // Only the left half of the circle is visible.
+    assert_eq!(image.get_pixel(40, 50).0[3], 255);
+    assert_eq!(image.get_pixel(25, 50).0[3], 255);
+    assert_eq!(image.get_pixel(60, 50).0[3], 0);
+    assert_eq!(image.get_pixel(10, 50).0[3], 0);
+    assert_eq!(image.get_pixel(45, 15).0[3], 0);
+    assert_eq!(image.get_pixel(30, 90).0[3], 0);
+}
+
+#[test]
+fn scaled_root_transform() {
+    // The view box is scaled up by a factor of two to fit the size of the SVG.
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="0 0 100 50">
+        <rect width="100" height="50" fill="black"/>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/MediaBox [0 0 200 100]"));
+
+    let image = render_pdf(&pdf);
+    assert_eq!(ink_bounds(&image), Some((0, 0, 199, 99)));
+
+    let page_options = PageOptions { dpi: 144.0, ..PageOptions::default() };
+    let pdf = convert_str(svg, ConversionOptions::default(), page_options).unwrap();
+    assert!(contains(&pdf, "/MediaBox [0 0 100 50]"));
+}
+
+#[test]
+fn estimate_size() {
+    let options = ConversionOptions::default();
+    let estimate = |content: &str| {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">{content}</svg>"#
+        );
+        svg2pdf::estimate_size(&read_svg(&svg), &options)
+    };
+
+    let path = r#"<path d="M 10 10 L 90 10 L 90 90 L 10 90 Z"/>"#;
+    let gradient = r#"<linearGradient id="lg"><stop offset="0" stop-color="red"/>
+        <stop offset="1" stop-color="blue"/></linearGradient>
+        <rect width="50" height="50" fill="url(#lg)"/>"#;
+    let text = r#"<text x="10" y="50" font-family="Noto Sans">Hello World</text>"#;
+    let filter = r#"<filter id="blur"><feGaussianBlur stdDeviation="2"/></filter>
+        <rect width="50" height="50" filter="url(#blur)"/>"#;
+
+    let mut content = String::new();
+    let mut previous = estimate(&content);
+    for addition in [path, path, gradient, text, text, filter] {
+        content.push_str(addition);
+        let next = estimate(&content);
+        assert!(next > previous, "estimate didn't grow after adding {addition}");
+        previous = next;
+    }
+
+    // The estimate should be in the right ballpark of the actual size.
+    let pdf = convert_str(
+        &format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">{content}</svg>"#
+        ),
+        options.clone(),
+        PageOptions::default(),
+    )
+    .unwrap();
+    assert!(previous > pdf.len() / 10 && previous < pdf.len() * 10);
+}
+
+#[test]
+fn convert_many() {
+    let malformed = std::env::temp_dir().join("svg2pdf-malformed.svg");
+    std::fs::write(&malformed, "<svg xmlns=\"http://www.w3.org/2000/svg\"").unwrap();
+
+    let inputs = [
+        Path::new("svg/resvg/shapes/rect/simple-case.svg").to_path_buf(),
+        malformed,
+        Path::new("svg/does-not-exist.svg").to_path_buf(),
+    ];
+    let options = usvg::Options { fontdb: FONTDB.clone(), ..usvg::Options::default() };
+    let results = svg2pdf::convert_many(
+        &inputs,
+        &options,
+        &PreprocessOptions::default(),
+        &ConversionOptions::default(),
+        PageOptions::default(),
+    );
+
+    assert_eq!(results.len(), 3);
+    assert!(results[0].as_ref().unwrap().starts_with(b"%PDF-"));
+    assert!(matches!(results[1], Err(ConversionError::InvalidSvg)));
+    assert!(matches!(results[2], Err(ConversionError::ReadError)));
+}
+
+#[test]
+fn multipage() {
+    let trees = [
+        read_svg(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50">
+                <text x="10" y="30" font-family="Noto Sans">Page one</text>
+            </svg>"#,
+        ),
+        read_svg(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="80" height="120">
+                <text x="10" y="30" font-family="Noto Sans">Page two</text>
+            </svg>"#,
+        ),
+    ];
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = svg2pdf::to_multipage_pdf(&trees, options, PageOptions::default()).unwrap();
+
+    assert_eq!(page_count(&pdf), 2);
+    assert!(contains(&pdf, "/Count 2"));
+
+    // The pages are sized according to their trees and appear in order.
+    let first = pdf.windows(20).position(|w| w == b"/MediaBox [0 0 100 5").unwrap();
+    let second = pdf.windows(20).position(|w| w == b"/MediaBox [0 0 80 120").unwrap();
+    assert!(first < second);
+
+    // The font is only embedded once.
+    let needle = b"/Type /FontDescriptor";
+    let fonts = pdf.windows(needle.len()).filter(|w| w == needle).count();
+    assert_eq!(fonts, 1);
+
+    // A PDF needs at least one page.
+    assert!(matches!(
+        svg2pdf::to_multipage_pdf(
+            &[],
+            ConversionOptions::default(),
+            PageOptions::default()
+        ),
+        Err(ConversionError::InvalidPageRange)
+    ));
+}
+
+#[test]
+fn page_size_and_fit() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50">
+        <rect width="100" height="50" fill="black"/>
+    </svg>"#;
+    let a4 = usvg::Size::from_wh(595.0, 842.0).unwrap();
+
+    let layout = |fit, margin| {
+        let page_options = PageOptions {
+            page_size: Some(a4),
+            fit,
+            margin,
+            ..PageOptions::default()
+        };
+        let pdf = convert_str(svg, ConversionOptions::default(), page_options).unwrap();
+        let image = render_pdf(&pdf);
+        assert_eq!(image.dimensions(), (595, 842));
+        ink_bounds(&image).unwrap()
+    };
+
+    // The content is scaled to the width of the page and centered vertically.
+    let (x0, y0, x1, y1) = layout(Fit::Contain, 0.0);
+    assert_eq!((x0, x1), (0, 594));
+    assert!(y0.abs_diff(272) <= 1 && y1.abs_diff(569) <= 1);
+
+    let (x0, y0, x1, y1) = layout(Fit::Contain, 20.0);
+    assert_eq!((x0, x1), (20, 574));
+    assert!(y0.abs_diff(282) <= 1 && y1.abs_diff(559) <= 1);
+
+    // Covering content is clipped to the margins.
+    assert_eq!(layout(Fit::Cover, 10.0), (10, 10, 584, 831));
+    assert_eq!(layout(Fit::Fill, 0.0), (0, 0, 594, 841));
+
+    let (x0, y0, x1, y1) = layout(Fit::None, 0.0);
+    assert!(x0.abs_diff(248) <= 1 && x1.abs_diff(347) <= 1);
+    assert_eq!((y0, y1), (396, 445));
+
+    // Without a page size, the margin is added around the content.
+    let page_options = PageOptions { margin: 10.0, ..PageOptions::default() };
+    let pdf = convert_str(svg, ConversionOptions::default(), page_options).unwrap();
+    let image = render_pdf(&pdf);
+    assert_eq!(image.dimensions(), (120, 70));
+    assert_eq!(ink_bounds(&image), Some((10, 10, 109, 59)));
+}
+
+#[test]
+fn svg_images_are_embedded_as_vectors() {
+    // An SVG that references another SVG file next to it.
+    let dir = std::env::temp_dir().join("svg2pdf-external-svg");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("inner.svg"),
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+            <circle cx="5" cy="5" r="5"/>
+        </svg>"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("outer.svg"),
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <image href="inner.svg" width="100" height="100"/>
+        </svg>"#,
+    )
+    .unwrap();
+
+    for path in [
+        Path::new("svg/resvg/structure/image/embedded-svg.svg").to_path_buf(),
+        dir.join("outer.svg"),
+    ] {
+        let options = usvg::Options {
+            resources_dir: path.parent().map(Path::to_path_buf),
+            fontdb: FONTDB.clone(),
+            ..usvg::Options::default()
+        };
+        let svg = std::fs::read_to_string(&path).unwrap();
+        let tree = usvg::Tree::from_str(&svg, &options).unwrap();
+
+        let conversion_options =
+            ConversionOptions { compress: false, ..ConversionOptions::default() };
+        let pdf =
+            svg2pdf::to_pdf(&tree, conversion_options, PageOptions::default()).unwrap();
+
+        assert!(contains(&pdf, "/Subtype /Form"), "{}", path.display());
+        assert!(!contains(&pdf, "/Subtype /Image"), "{}", path.display());
+    }
+}
+
+#[test]
+fn crisp_edges_in_rasterized_group() {
+    let svg = |shape_rendering| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="100" height="100">
+                    <feOffset dx="0" dy="0"/>
+                </filter>
+                <g filter="url(#f)">
+                    <rect x="10.5" y="10.5" width="50" height="50" shape-rendering="{shape_rendering}"/>
+                </g>
+            </svg>"#
+        )
+    };
+
+    let options = ConversionOptions { raster_scale: 1.0, ..ConversionOptions::default() };
+    let partially_covered = |svg: &str| {
+        let pdf = convert_str(svg, options.clone(), PageOptions::default()).unwrap();
+        render_pdf(&pdf)
+            .pixels()
+            .filter(|p| p.0[3] > 0 && p.0[3] < 255)
+            .count()
+    };
+
+    assert!(partially_covered(&svg("geometricPrecision")) > 0);
+    assert_eq!(partially_covered(&svg("crispEdges")), 0);
+}
+
+#[test]
+fn media_print() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <style>
+            rect { fill: rgb(255, 0, 0) }
+            @media print { rect { fill: rgb(0, 255, 0) } }
+            @media not print { circle { display: none } }
+            @media screen, (min-width: 50px) { .screen { fill: rgb(0, 0, 255) } }
+        </style>
+        <rect width="50" height="50"/>
+        <rect class="screen" x="50" width="50" height="50"/>
+        <circle cx="50" cy="75" r="10"/>
+    </svg>"#;
+
+    let render = |media| {
+        let options = PreprocessOptions { media, ..PreprocessOptions::default() };
+        let svg = svg2pdf::preprocess(svg, &options).unwrap().svg;
+        let pdf = convert_str(&svg, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+        render_pdf(&pdf)
+    };
+
+    let image = render(MediaType::Print);
+    assert_eq!(image.get_pixel(25, 25).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(75, 25).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(50, 75).0[3], 255);
+
+    let image = render(MediaType::Screen);
+    assert_eq!(image.get_pixel(25, 25).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(75, 25).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(50, 75).0[3], 0);
+}
+
+#[test]
+fn prefers_color_scheme() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <style>
+            @media (prefers-color-scheme: light) { rect { fill: rgb(255, 255, 0) } }
+            @media (prefers-color-scheme: dark) { rect { fill: rgb(0, 0, 128) } }
+        </style>
+        <rect width="100" height="100"/>
+    </svg>"#;
+
+    let render = |color_scheme| {
+        let options = PreprocessOptions { color_scheme, ..PreprocessOptions::default() };
+        let svg = svg2pdf::preprocess(svg, &options).unwrap().svg;
+        let pdf = convert_str(&svg, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+        render_pdf(&pdf)
+    };
+
+    assert_eq!(render(ColorScheme::default()).get_pixel(50, 50).0, [255, 255, 0, 255]);
+    assert_eq!(render(ColorScheme::Dark).get_pixel(50, 50).0, [0, 0, 128, 255]);
+}
+
+#[test]
+fn foreign_object_placeholder() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <foreignObject x="20" y="20" width="60" height="40">
+            <div xmlns="http://www.w3.org/1999/xhtml">Some <b>HTML</b></div>
+        </foreignObject>
+        <switch>
+            <foreignObject requiredExtensions="http://www.w3.org/1999/xhtml" width="100" height="10"/>
+            <rect y="90" width="100" height="10" fill="black"/>
+        </switch>
+    </svg>"#;
+
+    let convert = |foreign_object| {
+        let options =
+            PreprocessOptions { foreign_object, ..PreprocessOptions::default() };
+        svg2pdf::preprocess(svg, &options).and_then(|preprocessed| {
+            convert_str(
+                &preprocessed.svg,
+                ConversionOptions::default(),
+                PageOptions::default(),
+            )
+        })
+    };
+
+    let mut result = None;
+    let warnings =
+        capture_warnings(|| result = Some(convert(ForeignObjectPolicy::Placeholder)));
+    assert_eq!(warnings.iter().filter(|w| w.contains("foreignObject")).count(), 2);
+
+    let options = PreprocessOptions {
+        foreign_object: ForeignObjectPolicy::Placeholder,
+        ..PreprocessOptions::default()
+    };
+    let foreign_object_warnings: Vec<_> =
+        warnings.into_iter().filter(|w| w.contains("foreignObject")).collect();
+    assert_eq!(
+        svg2pdf::preprocess(svg, &options).unwrap().warnings,
+        foreign_object_warnings
+    );
+
+    let image = render_pdf(&result.unwrap().unwrap());
+    assert_eq!(image.get_pixel(50, 40).0, [224, 224, 224, 255]);
+    assert_eq!(image.get_pixel(10, 40).0[3], 0);
+    // The switch still picks its fallback.
+    assert_eq!(image.get_pixel(50, 95).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(50, 5).0[3], 0);
+
+    let image = render_pdf(&convert(ForeignObjectPolicy::Skip).unwrap());
+    assert_eq!(ink_bounds(&image), Some((0, 90, 99, 99)));
+
+    assert!(matches!(
+        convert(ForeignObjectPolicy::Error),
+        Err(ConversionError::UnsupportedForeignObject)
+    ));
+}
+
+#[test]
+fn preprocess_only_touches_markup() {
+    let options = PreprocessOptions {
+        media: MediaType::Print,
+        foreign_object: ForeignObjectPolicy::Placeholder,
+        resource_resolver: Some(ResourceResolver::from_dir("svg")),
+        ..PreprocessOptions::default()
+    };
+
+    // Text, comments and CDATA sections that look like markup are left alone.
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <!-- <svg clip="rect(0 10 10 0)"> <use href="missing.svg#a"/> <foreignObject/> -->
+        <text x="10" y="20"><![CDATA[@media print { <use href="missing.svg"/> }]]></text>
+        <desc>@media print { rect { fill: green } }</desc>
+    </svg>"#;
+    let preprocessed = svg2pdf::preprocess(svg, &options).unwrap();
+    assert_eq!(
+        preprocessed,
+        Preprocessed {
+            svg: svg.to_string(),
+            language: None,
+            warnings: vec![]
+        }
+    );
+
+    // Nested and prefixed `foreignObject` elements are replaced as a whole.
+    let svg = r#"<svg:svg xmlns:svg="http://www.w3.org/2000/svg" width="100" height="100">
+        <svg:foreignObject width="50" height="50">
+            <svg:foreignObject width="10" height="10"></svg:foreignObject>
+            <p xmlns="http://www.w3.org/1999/xhtml">HTML</p>
+        </svg:foreignObject>
+        <svg:rect x="50" width="50" height="50"/>
+    </svg:svg>"#;
+    let preprocessed = svg2pdf::preprocess(svg, &options).unwrap().svg;
+    assert!(!preprocessed.contains("foreignObject"));
+    assert!(!preprocessed.contains("HTML"));
+    assert_eq!(preprocessed.matches("<svg:rect").count(), 2);
+
+    let image = render_pdf(
+        &convert_str(&preprocessed, ConversionOptions::default(), PageOptions::default())
+            .unwrap(),
+    );
+    assert_eq!(image.get_pixel(25, 25).0, [224, 224, 224, 255]);
+    assert_eq!(image.get_pixel(75, 25).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn single_child_group_opacity() {
+    let svg = |content| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <g opacity="0.5">{content}</g>
+            </svg>"#
+        )
+    };
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+
+    let pdf = convert_str(
+        &svg(r#"<rect width="100" height="100" fill="black"/>"#),
+        options.clone(),
+        PageOptions::default(),
+    )
+    .unwrap();
+    assert!(!contains(&pdf, "/Subtype /Form"));
+    assert!(contains(&pdf, "/ca 0.5"));
+    let alpha = render_pdf(&pdf).get_pixel(50, 50).0[3];
+    assert!(alpha.abs_diff(128) <= 1);
+
+    // Overlapping fill and stroke still need a transparency group.
+    let pdf = convert_str(
+        &svg(r#"<rect x="10" y="10" width="80" height="80" fill="black" stroke="red" stroke-width="10"/>"#),
+        options,
+        PageOptions::default(),
+    )
+    .unwrap();
+    assert!(contains(&pdf, "/Subtype /Form"));
+}
+
+#[test]
+fn filter_region_clipping() {
+    let svg = |transform| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <filter id="blur" filterUnits="userSpaceOnUse" x="35" y="35" width="30" height="30">
+                    <feGaussianBlur stdDeviation="5"/>
+                </filter>
+                <g transform="{transform}" filter="url(#blur)">
+                    <rect x="35" y="35" width="30" height="30" fill="black"/>
+                </g>
+            </svg>"#
+        )
+    };
+
+    // The blur doesn't extend past the region.
+    let pdf = convert_str(&svg(""), ConversionOptions::default(), PageOptions::default())
+        .unwrap();
+    let (x0, y0, x1, y1) = ink_bounds(&render_pdf(&pdf)).unwrap();
+    assert!(x0 >= 34 && y0 >= 34 && x1 <= 65 && y1 <= 65);
+
+    // If the group is rotated, the region is a diamond, which is smaller than
+    // its bounding box.
+    let pdf = convert_str(
+        &svg("rotate(45 50 50)"),
+        ConversionOptions::default(),
+        PageOptions::default(),
+    )
+    .unwrap();
+    let image = render_pdf(&pdf);
+    assert!(image.get_pixel(50, 50).0[3] > 128);
+    assert_eq!(image.get_pixel(33, 33).0[3], 0);
+    assert_eq!(image.get_pixel(67, 67).0[3], 0);
+}
+
+#[test]
+fn drop_shadow() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <filter id="shadow" x="0" y="0" width="2" height="2">
+            <feDropShadow dx="10" dy="10" stdDeviation="2" flood-color="black" flood-opacity="0.5"/>
+        </filter>
+        <rect x="20" y="20" width="40" height="40" fill="rgb(255, 0, 0)" filter="url(#shadow)"/>
+    </svg>"#;
+
+    // By default, the group is rasterized as a whole.
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let rasterized = render_pdf(&pdf);
+
+    let options = ConversionOptions {
+        compress: false,
+        vector_drop_shadows: true,
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    // The shadow is an image, but the rectangle itself is still drawn as a path.
+    assert!(contains(&pdf, "/Subtype /Image"));
+    assert!(contains(&pdf, "20 20 m"));
+
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(40, 40).0, [255, 0, 0, 255]);
+    let shadow = image.get_pixel(65, 65).0;
+    assert!(shadow[0] < 10 && shadow[3].abs_diff(128) < 16);
+    assert_eq!(image.get_pixel(5, 5).0[3], 0);
+
+    // The shadow matches the one of the rasterized group.
+    for (x, y) in [(61, 40), (64, 64), (65, 50), (40, 62)] {
+        let alpha = |image: &image::RgbaImage| image.get_pixel(x, y).0[3] as i32;
+        assert!((alpha(&image) - alpha(&rasterized)).abs() <= 16);
+    }
+}
+
+#[test]
+fn rasterized() {
+    let rasterized = |svg: &str| {
+        let tree = read_svg(svg);
+        let mut ctx = Context::new(ConversionOptions::default());
+        let mut chunk = Chunk::new();
+        let mut content = Content::new();
+        let mut rc = ResourceContainer::new();
+        ctx.render_tree(&tree, &mut chunk, &mut content, &mut rc).unwrap();
+        ctx.rasterized()
+    };
+
+    let plain = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect x="20" y="20" width="60" height="60" fill="green"/>
+    </svg>"#;
+    assert!(!rasterized(plain));
+
+    let filtered = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <filter id="blur">
+            <feGaussianBlur stdDeviation="5"/>
+        </filter>
+        <rect x="20" y="20" width="60" height="60" fill="green" filter="url(#blur)"/>
+    </svg>"#;
+    assert!(rasterized(filtered));
+}
+
+#[test]
+fn dashed_text_stroke() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+        <text x="10" y="60" font-family="Noto Sans" font-size="40" fill="none"
+            stroke="black" stroke-width="2" stroke-dasharray="3 1"
+            stroke-linejoin="round">Dash</text>
+    </svg>"#;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    // The glyphs are stroked as paths with the stroke properties of the text...
+    assert!(contains(&pdf, "[3 1] 0 d"));
+    assert!(contains(&pdf, "2 w"));
+    assert!(contains(&pdf, "1 j"));
+    assert!(contains(&pdf, " c\n"));
+    assert!(contains(&pdf, "S\n"));
+    assert!(!contains(&pdf, "1 Tr"));
+
+    // ... while the text itself stays extractable.
+    assert!(contains(&pdf, "3 Tr"));
+}
+
+#[test]
+fn text_runs() {
+    let svg = |attrs: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+                <text x="10" y="50" font-family="Noto Sans" font-size="20" {attrs}>Hello</text>
+            </svg>"#
+        )
+    };
+
+    let count = |pdf: &[u8], needle: &str| {
+        pdf.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+    };
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+
+    // The whole word is shown with a single operation.
+    let pdf = convert_str(&svg(""), options.clone(), PageOptions::default()).unwrap();
+    assert_eq!(count(&pdf, " Tj\n") + count(&pdf, " TJ\n"), 1);
+    assert_eq!(count(&pdf, " Tm\n"), 1);
+
+    // Letter spacing is expressed with adjustments.
+    let pdf = convert_str(
+        &svg(r#"letter-spacing="5""#),
+        options.clone(),
+        PageOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(count(&pdf, " TJ\n"), 1);
+    assert!(contains(&pdf, "-250"));
+
+    // Glyphs that are moved off the baseline need a new text matrix, but the
+    // following ones continue on the new baseline.
+    let pdf = convert_str(&svg(r#"dy="0 5""#), options, PageOptions::default()).unwrap();
+    assert_eq!(count(&pdf, " Tm\n"), 2);
+}
+
+#[test]
+fn hidden_text() {
+    let svg = |attr: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+                <text x="10" y="50" font-family="Noto Sans" font-size="20">A<tspan {attr}>WWW</tspan>A</text>
+            </svg>"#
+        )
+    };
+
+    let render = |attr: &str| {
+        let pdf =
+            convert_str(&svg(attr), ConversionOptions::default(), PageOptions::default())
+                .unwrap();
+        render_pdf(&pdf)
+    };
+
+    let visible = render("");
+    let hidden = render(r#"visibility="hidden""#);
+    let removed = render(r#"display="none""#);
+
+    let ink = |image: &image::RgbaImage| image.pixels().filter(|p| p.0[3] > 128).count();
+
+    // A hidden span leaves a gap where it would have been drawn...
+    let (x0, _, x1, _) = ink_bounds(&hidden).unwrap();
+    let (visible_x0, _, visible_x1, _) = ink_bounds(&visible).unwrap();
+    assert_eq!((x0, x1), (visible_x0, visible_x1));
+    assert!(ink(&hidden) < ink(&visible));
+
+    // ... while a span that isn't displayed doesn't take up any space.
+    assert!(ink_bounds(&removed).unwrap().2 < x1);
+    assert!(ink(&removed) < ink(&visible));
+}
+
+#[test]
+fn outline_fonts() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+        <text x="10" y="30" font-family="Noto Sans" font-size="20">Embedded</text>
+        <text x="10" y="70" font-family="Noto Serif" font-size="20">Outlined</text>
+    </svg>"#;
+
+    let query = fontdb::Query {
+        families: &[fontdb::Family::Name("Noto Serif")],
+        ..fontdb::Query::default()
+    };
+    let serif = FONTDB.query(&query).unwrap();
+
+    let options = ConversionOptions {
+        compress: false,
+        outline_fonts: vec![serif],
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    // Only the first text is embedded as selectable text...
+    assert!(contains(&pdf, "NotoSans-Regular"));
+    assert!(!contains(&pdf, "NotoSerif"));
+    let count = |needle: &str| {
+        pdf.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+    };
+    assert_eq!(count(" Tj\n") + count(" TJ\n"), 1);
+
+    // ... while the second one is filled as a path.
+    assert!(contains(&pdf, " c\n"));
+    assert!(contains(&pdf, "\nf\n"));
+}
+
+#[test]
+fn transparency_group_color_space() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="100" height="100" fill="yellow"/>
+        <g style="mix-blend-mode: multiply">
+            <rect x="20" y="20" width="60" height="60" fill="blue"/>
+        </g>
+    </svg>"#;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/S /Transparency"));
+    assert!(contains(&pdf, "/CS [/ICCBased"));
+    assert!(!contains(&pdf, "/DeviceRGB"));
+
+    let options = ConversionOptions {
+        compress: false,
+        embed_icc: false,
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/S /Transparency"));
+    assert!(contains(&pdf, "/CS /DeviceRGB"));
+    assert!(!contains(&pdf, "/ICCBased"));
+}
+
+#[test]
+fn object_bounding_box_pattern() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="110">
+        <pattern id="tiles" patternUnits="objectBoundingBox" patternContentUnits="objectBoundingBox"
+            width="0.5" height="0.5">
+            <rect width="0.25" height="0.25" fill="black"/>
+        </pattern>
+        <rect x="10" y="10" width="40" height="40" fill="url(#tiles)"/>
+        <rect x="10" y="60" width="80" height="40" fill="url(#tiles)"/>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+    let is_black = |x: u32, y: u32| image.get_pixel(x, y).0 == [0, 0, 0, 255];
+    let is_empty = |x: u32, y: u32| image.get_pixel(x, y).0[3] == 0;
+
+    // The tiles of the square are 20x20 with a 10x10 square in their corner.
+    assert!(is_black(15, 15));
+    assert!(is_empty(25, 15));
+    assert!(is_black(35, 15));
+    assert!(is_empty(15, 25));
+
+    // The tiles of the wide rectangle are 40x20 with a 20x10 rectangle.
+    assert!(is_black(15, 65));
+    assert!(is_black(25, 65));
+    assert!(is_empty(35, 65));
+    assert!(is_black(55, 65));
+    assert!(is_empty(15, 75));
+}
+
+#[test]
+fn object_bounding_box_radial_gradient() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="50">
+        <radialGradient id="gradient" gradientUnits="objectBoundingBox" cx="0.5" cy="0.5" r="0.5">
+            <stop offset="0" stop-color="rgb(255, 0, 0)"/>
+            <stop offset="1" stop-color="rgb(0, 0, 255)"/>
+        </radialGradient>
+        <rect width="200" height="50" fill="url(#gradient)"/>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+    let red = |x: u32, y: u32| image.get_pixel(x, y).0[0] as i32;
+
+    // The gradient is stretched into an ellipse, so points that are halfway to
+    // the edge horizontally and vertically have the same color.
+    assert!((red(150, 25) - red(100, 12)).abs() <= 12);
+    assert!((red(150, 25) - 128).abs() <= 16);
+
+    // Points at the same distance in user space don't: the one to the right
+    // of the center is much closer to it relative to the wide ellipse.
+    assert!(red(112, 25) > red(100, 12) + 32);
+}
+
+#[test]
+fn root_mask() {
+    let mask = r#"<mask id="mask" maskUnits="userSpaceOnUse" maskContentUnits="objectBoundingBox"
+            x="0" y="0" width="100" height="100">
+            <circle cx="0.5" cy="0.5" r="0.4" fill="white"/>
+        </mask>"#;
+    let content = r#"<rect x="10" y="10" width="80" height="80" fill="green"/>
+        <rect x="30" y="30" width="40" height="40" fill="blue"/>"#;
+
+    let on_group = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            {mask}
+            <g mask="url(#mask)">{content}</g>
+        </svg>"#
+    );
+    let on_root = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" mask="url(#mask)">
+            {mask}
+            {content}
+        </svg>"#
+    );
+
+    let render = |svg: &str| {
+        render_pdf(
+            &convert_str(svg, ConversionOptions::default(), PageOptions::default())
+                .unwrap(),
+        )
+    };
+
+    let on_group = render(&on_group);
+    let on_root = render(&on_root);
+
+    // The content is masked...
+    assert_eq!(on_root.get_pixel(12, 12).0[3], 0);
+    assert_eq!(on_root.get_pixel(50, 50).0, [0, 0, 255, 255]);
+
+    // ... in exactly the same way.
+    let (_, diff) = get_diff(&on_group, &on_root);
+    assert_eq!(diff, 0);
+}
+
+#[test]
+fn options_builders() {
+    let options = ConversionOptions::builder()
+        .compress(false)
+        .raster_scale(2.0)
+        .language("fr")
+        .max_nodes(1000)
+        .embed_icc(false)
+        .build();
+
+    assert_eq!(
+        options,
+        ConversionOptions {
+            compress: false,
+            raster_scale: 2.0,
+            language: Some("fr".into()),
+            max_nodes: Some(1000),
+            embed_icc: false,
+            ..ConversionOptions::default()
+        }
+    );
+    assert_eq!(ConversionOptions::builder().build(), ConversionOptions::default());
+
+    let page_size = svg2pdf::usvg::Size::from_wh(595.0, 842.0).unwrap();
+    let options = PageOptions::builder()
+        .dpi(144.0)
+        .page_size(page_size)
+        .fit(Fit::Cover)
+        .margin(10.0)
+        .build();
+
+    assert_eq!(
+        options,
+        PageOptions {
+            dpi: 144.0,
+            page_size: Some(page_size),
+            fit: Fit::Cover,
+            margin: 10.0
+        }
+    );
+    assert_eq!(PageOptions::builder().build(), PageOptions::default());
+}
+
+#[test]
+fn element_map() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+        <g id="btn" transform="translate(20 10)">
+            <rect id="background" width="50" height="30" fill="gray"/>
+        </g>
+        <rect x="100" y="50" width="10" height="10"/>
+    </svg>"#;
+
+    let tree = read_svg(svg);
+    let page_options = PageOptions { margin: 10.0, ..PageOptions::default() };
+    let (pdf, map) =
+        svg2pdf::to_pdf_with_map(&tree, ConversionOptions::default(), page_options)
+            .unwrap();
+    assert!(pdf.starts_with(b"%PDF"));
+
+    let ids: Vec<_> = map.iter().map(|(id, _)| id.as_str()).collect();
+    assert_eq!(ids, ["btn", "background"]);
+
+    // The rectangle is in PDF coordinates, so the y-axis points upwards.
+    let rect = map[0].1;
+    let approx = |a: f32, b: f32| (a - b).abs() < 0.01;
+    assert!(approx(rect.left(), 30.0));
+    assert!(approx(rect.right(), 80.0));
+    assert!(approx(rect.top(), 70.0));
+    assert!(approx(rect.bottom(), 100.0));
+}
+
+#[test]
+fn preserve_aspect_ratio_none() {
+    let svg = |preserve_aspect_ratio: &str| {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100"
+                viewBox="0 0 100 100" preserveAspectRatio="{preserve_aspect_ratio}">
+                <rect width="100" height="100" fill="black"/>
+                <rect x="0" y="0" width="50" height="50" fill="red"/>
+            </svg>"#
+        )
+    };
+
+    let render = |svg: &str| {
+        render_pdf(
+            &convert_str(svg, ConversionOptions::default(), PageOptions::default())
+                .unwrap(),
+        )
+    };
+
+    // The square is stretched to the full width and height.
+    let image = render(&svg("none"));
+    assert_eq!(ink_bounds(&image), Some((0, 0, 199, 99)));
+    assert_eq!(image.get_pixel(95, 45).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(105, 45).0, [0, 0, 0, 255]);
+
+    // By default, it is scaled uniformly and centered.
+    let image = render(&svg("xMidYMid"));
+    assert_eq!(ink_bounds(&image), Some((50, 0, 149, 99)));
+}
+
+#[test]
+fn background_image_filter_input() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"
+        enable-background="new">
+        <filter id="filter">
+            <feOffset in="BackgroundImage" dx="5" dy="5"/>
+        </filter>
+        <rect x="20" y="20" width="60" height="60" fill="green" filter="url(#filter)"/>
+    </svg>"#;
+
+    let mut result = None;
+    let warnings = capture_warnings(|| {
+        result =
+            Some(convert_str(svg, ConversionOptions::default(), PageOptions::default()))
+    });
+    assert!(warnings.iter().any(|w| w.contains("BackgroundImage")));
+
+    let pdf = result.unwrap().unwrap();
+    assert!(contains(&pdf, "/Subtype /Image"));
+    assert!(ink_bounds(&render_pdf(&pdf)).is_some());
+}
+
+#[test]
+fn symbol_instances() {
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"
+        width="200" height="100">
+        <symbol id="icon" viewBox="0 0 10 10">
+            <rect width="10" height="10"/>
+        </symbol>
+        <use xlink:href="#icon" x="0" y="0" width="100" height="100" fill="rgb(255, 0, 0)"/>
+        <use xlink:href="#icon" x="150" y="25" width="50" height="50" fill="rgb(0, 0, 255)"/>
+    </svg>"##;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+
+    // Each instance has its own size and color.
+    assert_eq!(image.get_pixel(50, 50).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(175, 50).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(175, 10).0[3], 0);
+    assert_eq!(image.get_pixel(125, 50).0[3], 0);
+}
+
+#[test]
+fn document_language() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="100" height="100"/>
+    </svg>"#;
+
+    let options = ConversionOptions {
+        document_language: Some("de-DE".into()),
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/Lang (de-DE)"));
+
+    // The language of the text is used as a fallback.
+    let options = ConversionOptions {
+        language: Some("fr".into()),
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/Lang (fr)"));
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    assert!(!contains(&pdf, "/Lang"));
+
+    // `convert_many` reads it from the root element.
+    let path = std::env::temp_dir().join("svg2pdf-document-language.svg");
+    std::fs::write(&path, svg.replace("<svg ", r#"<svg xml:lang="nl" "#)).unwrap();
+    let options = usvg::Options { fontdb: FONTDB.clone(), ..usvg::Options::default() };
+    let results = svg2pdf::convert_many(
+        &[path],
+        &options,
+        &PreprocessOptions::default(),
+        &ConversionOptions::default(),
+        PageOptions::default(),
+    );
+    assert!(contains(results[0].as_ref().unwrap(), "/Lang (nl)"));
+}
+
+#[test]
+fn shared_fonts_across_chunks() {
+    let tree = read_svg(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50">
+            <text x="5" y="30" font-family="Noto Sans" font-size="20">Shared</text>
+        </svg>"#,
+    );
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let mut ctx = Context::new(options);
+
+    let mut chunks = vec![];
+    for _ in 0..3 {
+        let mut chunk = Chunk::new();
+        ctx.render_xobject(&tree, &mut chunk).unwrap();
+        chunks.push(chunk);
+    }
+
+    let mut shared = Chunk::new();
+    ctx.finish(&mut shared).unwrap();
+
+    let count = |data: &[u8], needle: &str| {
+        data.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+    };
+
+    // The font program is only written once, into the shared chunk, and all
+    // XObjects refer to it.
+    for chunk in &chunks {
+        assert_eq!(count(chunk.as_bytes(), "/FontFile2"), 0);
+    }
+    assert_eq!(count(shared.as_bytes(), "/FontFile2"), 1);
+    assert_eq!(count(shared.as_bytes(), "/Type /Font"), 2);
+
+    let font_refs: Vec<_> = chunks
+        .iter()
+        .map(|chunk| {
+            let bytes = chunk.as_bytes();
+            let start = bytes.windows(5).position(|w| w == b"/Font").unwrap();
+            let end = start + bytes[start..].iter().position(|b| *b == b'>').unwrap();
+            bytes[start..end].to_vec()
+        })
+        .collect();
+    assert!(font_refs.windows(2).all(|w| w[0] == w[1]));
+}
+
+#[test]
+fn indexed_png() {
+    // A 4x2 PNG with a 2-bit palette of red, blue and a transparent green.
+    const IMAGE: &str = concat!(
+        "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAQAAAACAgMAAAACxpXwAAAACVBMVEX/",
+        "AAAAAP8A/wCHDR4IAAAAA3RSTlP//wDXyg1BAAAADElEQVR4nGOQYHACAACOAFst6+C2AAAAAElFTkSuQmCC"
+    );
+
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="80" height="40">
+            <image width="80" height="40" href="{IMAGE}"/>
+        </svg>"#
+    );
+
+    let pdf =
+        convert_str(&svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/ColorSpace [/Indexed /DeviceRGB 2 "));
+    assert!(contains(&pdf, "/BitsPerComponent 2"));
+    assert!(contains(&pdf, "/SMask"));
+
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(10, 10).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(30, 10).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(50, 10).0[3], 0);
+}
+
+#[test]
+fn rtl_text_anchor() {
+    let bounds = |anchor: &str| {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="300" height="100">
+                <text x="150" y="60" font-family="Amiri" font-size="40" direction="rtl"
+                      text-anchor="{anchor}">مرحبا بالعالم</text>
+            </svg>"#
+        );
+
+        let pdf = convert_str(&svg, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+        ink_bounds(&render_pdf(&pdf)).unwrap()
+    };
+
+    // For right-to-left text, the start is on the right, so a start-anchored
+    // text ends at x and an end-anchored text begins at x.
+    let (_, _, start_right, _) = bounds("start");
+    assert!((145..=152).contains(&start_right));
+
+    let (end_left, _, end_right, _) = bounds("end");
+    assert!((148..=155).contains(&end_left));
+    assert!(end_right > 200);
+}
+
+#[test]
+fn debug_readable() {
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <path d="M 10 10 L 90 10 L 90 50 Z" fill="#ff0000"/>
+        <image x="10" y="60" width="40" height="20" href="data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAABQAAAAKCAIAAAA7N+mxAAAAGElEQVR42mP4z8CAB+GV/M8wqnlUMyHNAGn9xzn3KHifAAAAAElFTkSuQmCC"/>
+        <text x="60" y="80" font-family="Noto Sans" font-size="20">A</text>
+    </svg>"##;
+
+    let options = ConversionOptions {
+        debug_readable: true,
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    assert!(contains(&pdf, "10 10 m"));
+    assert!(contains(&pdf, "/ASCIIHexDecode"));
+    assert!(!contains(&pdf, "/FlateDecode"));
+
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(80, 20).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(15, 70).0[3], 255);
+}
+
+#[test]
+fn marker_overflow() {
+    let right = |overflow: &str| {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <marker id="arrow" markerWidth="10" markerHeight="10" refX="0" refY="5"
+                        markerUnits="userSpaceOnUse" overflow="{overflow}">
+                    <rect width="30" height="10"/>
+                </marker>
+                <path d="M 10 50 L 50 50" stroke="black" stroke-width="2" marker-end="url(#arrow)"/>
+            </svg>"#
+        );
+
+        let pdf = convert_str(&svg, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+        ink_bounds(&render_pdf(&pdf)).unwrap().2
+    };
+
+    // The marker content is three times as wide as the marker viewport.
+    assert!((58..=61).contains(&right("hidden")));
+    assert!((78..=81).contains(&right("visible")));
+}
+
+#[test]
+fn convert_output() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <filter id="blur">
+            <feGaussianBlur stdDeviation="2"/>
+        </filter>
+        <rect id="box" x="20" y="20" width="60" height="60" filter="url(#blur)"/>
+    </svg>"#;
+
+    let tree = read_svg(svg);
+    let output =
+        svg2pdf::convert(&tree, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+
+    assert!(output.pdf.starts_with(b"%PDF"));
+    assert!(output.rasterized);
+    assert_eq!(output.warnings.len(), 1);
+    assert!(output.warnings[0].contains("rasterized"));
+    assert!(output.element_map.iter().any(|(id, _)| id == "box"));
+    // The group with the filter and the rect.
+    assert_eq!(output.stats, svg2pdf::Stats { nodes: 2, fonts: 0 });
+
+    let output = svg2pdf::convert(
+        &read_svg(r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>"#),
+        ConversionOptions::default(),
+        PageOptions::default(),
+    )
+    .unwrap();
+    assert!(output.warnings.is_empty());
+    assert!(!output.rasterized);
+}
+
+#[test]
+fn object_bounding_box_clip_path() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+        <clipPath id="clip" clipPathUnits="objectBoundingBox">
+            <rect x="0.25" y="0.25" width="0.5" height="0.5"/>
+        </clipPath>
+        <rect x="0" y="0" width="40" height="40" clip-path="url(#clip)"/>
+        <rect x="100" y="0" width="80" height="100" clip-path="url(#clip)"/>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+
+    let left = image::imageops::crop_imm(&image, 0, 0, 100, 100).to_image();
+    assert_eq!(ink_bounds(&left), Some((10, 10, 29, 29)));
+
+    let right = image::imageops::crop_imm(&image, 100, 0, 100, 100).to_image();
+    assert_eq!(ink_bounds(&right), Some((20, 25, 59, 74)));
+}
+
+#[test]
+fn layers_from_groups() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <g id="background">
+            <rect width="100" height="100" fill="green"/>
+        </g>
+        <g id="foreground">
+            <rect x="25" y="25" width="50" height="50" fill="blue"/>
+        </g>
+    </svg>"#;
+
+    let options = ConversionOptions {
+        compress: false,
+        layers_from_groups: true,
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    let count = |needle: &str| {
+        pdf.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+    };
+    assert_eq!(count("/Type /OCG"), 2);
+    assert!(contains(&pdf, "/Name (background)"));
+    assert!(contains(&pdf, "/Name (foreground)"));
+    assert!(contains(&pdf, "/OCProperties"));
+
+    // Each layer is drawn with its own form XObject.
+    assert!(contains(&pdf, "/OC /oc0 BDC\n/xo0 Do\nEMC"));
+    assert!(contains(&pdf, "/OC /oc1 BDC\n/xo1 Do\nEMC"));
+    assert_eq!(count("/Subtype /Form"), 2);
+
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(10, 10).0, [0, 128, 0, 255]);
+    assert_eq!(image.get_pixel(50, 50).0, [0, 0, 255, 255]);
+
+    // The attributes of the root element still apply to all layers.
+    let svg = svg.replace(
+        r#"height="100">"#,
+        r#"height="100" opacity="0.5" clip-path="url(#left)">
+        <clipPath id="left"><rect width="50" height="100"/></clipPath>"#,
+    );
+    let options = ConversionOptions {
+        layers_from_groups: true,
+        ..ConversionOptions::default()
+    };
+    let image = render_pdf(&convert_str(&svg, options, PageOptions::default()).unwrap());
+    assert!(image.get_pixel(10, 10).0[3].abs_diff(128) <= 1);
+    assert!(image.get_pixel(40, 50).0[3].abs_diff(128) <= 1);
+    assert_eq!(image.get_pixel(60, 50).0[3], 0);
+    assert_eq!(image.get_pixel(90, 10).0[3], 0);
+}
+
+#[test]
+fn converter() {
+    let small = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <rect width="5" height="5"/>
+    </svg>"#;
+    let large = std::fs::read("svg/custom/integration/matplotlib/stairs.svg").unwrap();
+
+    let converter = svg2pdf::Converter::with_fontdb(FONTDB.clone());
+    let uncompressed =
+        ConversionOptions { compress: false, ..ConversionOptions::default() };
+
+    let pdf = converter
+        .convert_data(
+            &large,
+            &PreprocessOptions::default(),
+            uncompressed,
+            PageOptions::default(),
+        )
+        .unwrap()
+        .pdf;
+    assert!(pdf.starts_with(b"%PDF-"));
+
+    // Conversions don't affect each other.
+    let pdf = converter
+        .convert_data(
+            small.as_bytes(),
+            &PreprocessOptions::default(),
+            ConversionOptions::default(),
+            PageOptions::default(),
+        )
+        .unwrap()
+        .pdf;
+    assert_eq!(
+        pdf,
+        convert_str(small, ConversionOptions::default(), PageOptions::default()).unwrap()
+    );
+
+    // The warnings of preprocessing come before the ones of the conversion.
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <filter id="blur"><feGaussianBlur stdDeviation="1"/></filter>
+        <foreignObject width="10" height="10"/>
+        <rect width="5" height="5" filter="url(#blur)"/>
+    </svg>"#;
+    let output = converter
+        .convert_data(
+            svg.as_bytes(),
+            &PreprocessOptions::default(),
+            ConversionOptions::default(),
+            PageOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(output.warnings.len(), 2);
+    assert!(output.warnings[0].contains("foreignObject"));
+    assert!(output.warnings[1].contains("rasterized"));
+}
+
+#[test]
+fn transform_origin() {
+    let check = |svg: &str| {
+        let preprocessed =
+            svg2pdf::preprocess(svg, &PreprocessOptions::default()).unwrap();
+        let pdf = convert_str(
+            &preprocessed.svg,
+            ConversionOptions::default(),
+            PageOptions::default(),
+        )
+        .unwrap();
+        let image = render_pdf(&pdf);
+
+        // The square is turned into a diamond around the center of the SVG.
+        let (x_min, y_min, x_max, y_max) = ink_bounds(&image).unwrap();
+        for (value, expected) in [(x_min, 22), (y_min, 22), (x_max, 77), (y_max, 77)] {
+            assert!(value.abs_diff(expected) <= 1);
+        }
+        assert_eq!(image.get_pixel(50, 25).0[3], 255);
+        assert_eq!(image.get_pixel(31, 31).0[3], 0);
+    };
+
+    check(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <rect x="30" y="30" width="40" height="40" transform="rotate(45)"
+                  transform-origin="center"/>
+        </svg>"#,
+    );
+
+    // A transform from CSS with an angle unit.
+    check(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <rect x="30" y="30" width="40" height="40" style="transform: rotate(45deg)"
+                  transform-origin="center"/>
+        </svg>"#,
+    );
+}
+
+#[test]
+fn max_page_size() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10000" height="100">
+        <rect width="10000" height="100"/>
+    </svg>"#;
+
+    let page_options = PageOptions { dpi: 36.0, ..PageOptions::default() };
+    let output =
+        svg2pdf::convert(&read_svg(svg), ConversionOptions::default(), page_options)
+            .unwrap();
+
+    // 20000x200 points are scaled down to fit into the limit.
+    assert!(contains(&output.pdf, "/MediaBox [0 0 14400 144]"));
+    assert_eq!(output.warnings.len(), 1);
+    assert!(output.warnings[0].contains("maximum size"));
+
+    let output = svg2pdf::convert(
+        &read_svg(svg),
+        ConversionOptions::default(),
+        PageOptions::default(),
+    )
+    .unwrap();
+    assert!(contains(&output.pdf, "/MediaBox [0 0 10000 100]"));
+    assert!(output.warnings.is_empty());
+}
+
+#[test]
+fn multi_line_text() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <text font-family="Noto Sans" font-size="20">
+            <tspan x="10" y="30">x</tspan>
+            <tspan x="10" y="70">x <tspan baseline-shift="50%">x</tspan></tspan>
+        </text>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+    let bounds = |x, y, width, height| {
+        let region = image::imageops::crop_imm(&image, x, y, width, height).to_image();
+        let (x_min, y_min, x_max, y_max) = ink_bounds(&region).unwrap();
+        (x + x_min, y + y_min, x + x_max, y + y_max)
+    };
+
+    let first = bounds(0, 0, 100, 40);
+    let second = bounds(0, 40, 22, 60);
+    let shifted = bounds(24, 40, 76, 60);
+
+    // The second line starts at the same x, 40 units below the first one.
+    assert_eq!(second.0, first.0);
+    assert_eq!(second.1, first.1 + 40);
+    // The percentage is relative to the font size.
+    assert!((second.1 as i32 - shifted.1 as i32 - 10).abs() <= 1);
+    assert!((second.3 as i32 - shifted.3 as i32 - 10).abs() <= 1);
+}
+
+#[test]
+fn tag_ids() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect id="foo" width="50" height="50"/>
+        <rect x="50" y="50" width="50" height="50"/>
+    </svg>"#;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options.clone(), PageOptions::default()).unwrap();
+    assert!(!contains(&pdf, "BDC"));
+
+    let options = ConversionOptions { tag_ids: true, ..options };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/Span"));
+    assert!(contains(&pdf, "/ID (foo)"));
+    let count = |needle: &str| {
+        pdf.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+    };
+    assert_eq!(count("BDC"), 1);
+    assert_eq!(count("EMC"), 1);
+}
+
+#[test]
+fn image_rendering() {
+    let interpolate = |rendering: &str| {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                <image width="100" height="50" image-rendering="{rendering}"
+                    href="data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAABQAAAAKCAIAAAA7N+mxAAAAGElEQVR42mP4z8CAB+GV/M8wqnlUMyHNAGn9xzn3KHifAAAAAElFTkSuQmCC"/>
+            </svg>"#
+        );
+
+        let pdf = convert_str(&svg, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+        match (contains(&pdf, "/Interpolate true"), contains(&pdf, "/Interpolate false"))
+        {
+            (true, false) => true,
+            (false, true) => false,
+            _ => panic!("expected exactly one interpolation setting"),
+        }
+    };
+
+    assert!(interpolate("auto"));
+    assert!(interpolate("optimizeQuality"));
+    assert!(!interpolate("optimizeSpeed"));
+}
+
+#[test]
+fn symbol_sheet() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+        <symbol id="square" viewBox="0 0 10 10"><rect width="10" height="10"/></symbol>
+        <symbol id="circle" viewBox="0 0 10 10"><circle cx="5" cy="5" r="5"/></symbol>
+        <symbol id="bar" viewBox="0 0 10 10"><rect y="4" width="10" height="2"/></symbol>
+        <symbol id="dot" viewBox="0 0 10 10"><circle cx="5" cy="5" r="1"/></symbol>
+    </svg>"#;
+
+    let usvg_options =
+        usvg::Options { fontdb: FONTDB.clone(), ..usvg::Options::default() };
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = svg2pdf::symbol_sheet(svg, &usvg_options, options).unwrap();
+
+    assert_eq!(page_count(&pdf), 1);
+    assert!(contains(&pdf, "/FontFile"));
+
+    // Each tile shows its symbol with the label below it.
+    let image = render_pdf(&pdf);
+    for i in 0..4 {
+        let x = 24 + i * 120;
+        let symbol = image::imageops::crop_imm(&image, x, 24, 96, 96).to_image();
+        let label = image::imageops::crop_imm(&image, x, 120, 96, 16).to_image();
+        assert!(ink_bounds(&symbol).is_some());
+        assert!(ink_bounds(&label).is_some());
+    }
+
+    // Twenty tiles fit on a page.
+    let symbols: String = (0..21)
+        .map(|i| format!(r#"<symbol id="s{i}"><rect width="10" height="10"/></symbol>"#))
+        .collect();
+    let svg = format!(r#"<svg xmlns="http://www.w3.org/2000/svg">{symbols}</svg>"#);
+    let pdf =
+        svg2pdf::symbol_sheet(&svg, &usvg_options, ConversionOptions::default()).unwrap();
+    assert_eq!(page_count(&pdf), 2);
+}
+
+#[test]
+fn non_uniform_stroke_scale() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <g transform="scale(2, 1)">
+            <circle cx="25" cy="50" r="20" fill="none" stroke="black" stroke-width="4"/>
+        </g>
+    </svg>"#;
+
+    let pdf =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    let image = render_pdf(&pdf);
+
+    // The stroke is scaled along with the circle, so it is twice as thick on
+    // the left side as it is at the top.
+    let left = image::imageops::crop_imm(&image, 0, 50, 50, 1).to_image();
+    let (x_min, _, x_max, _) = ink_bounds(&left).unwrap();
+    let top = image::imageops::crop_imm(&image, 50, 0, 1, 50).to_image();
+    let (_, y_min, _, y_max) = ink_bounds(&top).unwrap();
+
+    assert!((x_max - x_min + 1).abs_diff(8) <= 1);
+    assert!((y_max - y_min + 1).abs_diff(4) <= 1);
+}
+
+#[test]
+fn focal_radial_gradient_repeat() {
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <radialGradient id="gradient" gradientUnits="userSpaceOnUse" cx="50" cy="50"
+                        r="20" fx="40" fy="50" spreadMethod="repeat">
+            <stop offset="0" stop-color="red"/>
+            <stop offset="1" stop-color="blue"/>
+        </radialGradient>
+        <rect width="100" height="100" fill="url(#gradient)"/>
+    </svg>"##;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    // The corner furthest from the focal point lies on the sixth circle, so
+    // the shading is stretched to it and the stops are repeated six times.
+    assert!(contains(&pdf, "/Coords [40 50 0 100 50 120]"));
+    assert!(contains(&pdf, "/Domain [0 6]"));
+    assert!(contains(&pdf, "/Bounds [1 2 3 4 5]"));
+    assert!(contains(&pdf, "/Encode [0 1 0 1 0 1 0 1 0 1 0 1]"));
+}
+
+#[test]
+fn font_error_message() {
+    // A face that is cut off before its `glyf` table can still be loaded and
+    // used for layout, but not be subsetted.
+    let data = std::fs::read("fonts/NotoSans-Regular.ttf").unwrap();
+    let mut fontdb = fontdb::Database::new();
+    fontdb.load_font_data(data[..229124].to_vec());
+
+    // The underline keeps the text, even though its glyphs have no outlines.
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50">
+        <text x="5" y="30" font-family="Noto Sans" font-size="20"
+              text-decoration="underline">Hello</text>
+    </svg>"#;
+    let options = usvg::Options {
+        fontdb: Arc::new(fontdb),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).unwrap();
+
+    let error =
+        svg2pdf::to_pdf(&tree, ConversionOptions::default(), PageOptions::default())
+            .unwrap_err();
+    let (ConversionError::SubsetError(font) | ConversionError::InvalidFont(font)) =
+        &error
+    else {
+        panic!("unexpected error: {error}");
+    };
+    assert_eq!(font.family, "Noto Sans");
+    assert_eq!(font.post_script_name, "NotoSans-Regular");
+    assert!(error
+        .to_string()
+        .contains("the font \"Noto Sans\" (NotoSans-Regular): "));
+}
+
+#[test]
+fn root_opacity() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"
+                      opacity="0.3">
+        <rect width="50" height="100" fill="black"/>
+        <rect x="50" width="50" height="100" fill="black" opacity="0.5"/>
+    </svg>"#;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/ca 0.3"));
+
+    // The opacity of the root applies to everything, including content that
+    // has an opacity of its own.
+    let image = render_pdf(&pdf);
+    assert!(image.get_pixel(25, 50).0[3].abs_diff(77) <= 2);
+    assert!(image.get_pixel(75, 50).0[3].abs_diff(38) <= 2);
+}
+
+#[test]
+fn watermark() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">
+        <rect width="200" height="200" fill="white"/>
+    </svg>"#;
+
+    let watermark = svg2pdf::Watermark { opacity: 0.25, ..svg2pdf::Watermark::default() };
+    let options = ConversionOptions {
+        compress: false,
+        watermark: Some(watermark),
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    // The text is drawn after the content of the SVG, so that it's on top.
+    let position =
+        |needle: &str| pdf.windows(needle.len()).position(|w| w == needle.as_bytes());
+    assert!(position("BT").unwrap() > position(" m\n").unwrap());
+    assert!(contains(&pdf, "/ca 0.25"));
+    assert!(contains(&pdf, "/FontFile"));
+
+    let without =
+        convert_str(svg, ConversionOptions::default(), PageOptions::default()).unwrap();
+    assert_ne!(render_pdf(&pdf), render_pdf(&without));
+}
+
+#[test]
+fn watermark_with_flatten_transparency() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">
+        <circle cx="100" cy="100" r="80" fill="blue" fill-opacity="0.5"/>
+    </svg>"#;
+
+    let watermark = svg2pdf::Watermark { opacity: 0.25, ..svg2pdf::Watermark::default() };
+    let options = ConversionOptions {
+        compress: false,
+        flatten_transparency: true,
+        watermark: Some(watermark),
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options.clone(), PageOptions::default()).unwrap();
+    let without = ConversionOptions { watermark: None, ..options };
+    let without = convert_str(svg, without, PageOptions::default()).unwrap();
+
+    // Only the page itself is flattened, the watermark stays vector text on top
+    // of the raster instead of being covered by a second, opaque one.
+    let images = |pdf: &[u8]| {
+        pdf.windows(b"/Subtype /Image".len())
+            .filter(|w| *w == b"/Subtype /Image")
+            .count()
+    };
+    assert_eq!(images(&pdf), images(&without));
+    let position =
+        |needle: &str| pdf.windows(needle.len()).position(|w| w == needle.as_bytes());
+    assert!(position("BT").unwrap() > position(" Do").unwrap());
+    assert!(contains(&pdf, "/FontFile"));
+    assert_ne!(render_pdf(&pdf), render_pdf(&without));
+}
+
+#[test]
+fn system_language() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="50" height="100" systemLanguage="en"/>
+        <g systemLanguage="fr, de-AT">
+            <rect x="50" width="50" height="100"/>
+        </g>
+    </svg>"#;
+
+    let converter = svg2pdf::Converter::with_fontdb(FONTDB.clone());
+    let options = PreprocessOptions {
+        language: Some("de".to_string()),
+        ..PreprocessOptions::default()
+    };
+    let pdf = converter
+        .convert_data(
+            svg.as_bytes(),
+            &options,
+            ConversionOptions::default(),
+            PageOptions::default(),
+        )
+        .unwrap()
+        .pdf;
+
+    // Elements whose language doesn't match are skipped, even outside of a
+    // `<switch>`.
+    assert_eq!(ink_bounds(&render_pdf(&pdf)), Some((50, 0, 99, 99)));
+}
+
+#[test]
+fn crop_marks() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect width="100" height="100"/>
+    </svg>"#;
+
+    let options = ConversionOptions {
+        compress: false,
+        crop_marks: true,
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    assert!(contains(&pdf, "/MediaBox [0 0 172 172]"));
+    assert!(contains(&pdf, "/TrimBox [36 36 136 136]"));
+
+    // Each corner has a horizontal and a vertical mark outside of the trim box.
+    for mark in [
+        "27 36 m\n9 36 l\n36 27 m\n36 9 l",
+        "145 36 m\n163 36 l\n136 27 m\n136 9 l",
+        "145 136 m\n163 136 l\n136 145 m\n136 163 l",
+        "27 136 m\n9 136 l\n36 145 m\n36 163 l",
+    ] {
+        assert!(contains(&pdf, mark));
+    }
+
+    // The marks are drawn in the registration color, which prints on every
+    // separation.
+    assert!(contains(&pdf, "/Separation /All /DeviceGray"));
+    assert!(contains(&pdf, " CS\n1 SC\n"));
+
+    // The content is moved into the trim box.
+    let image = render_pdf(&pdf);
+    let trim = image::imageops::crop_imm(&image, 36, 36, 100, 100).to_image();
+    assert!(trim.pixels().all(|p| p.0 == [0, 0, 0, 255]));
+}
+
+#[test]
+fn fractional_glyph_positions() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+        <text x="10.25" y="50" font-family="Noto Sans" font-size="20"
+              letter-spacing="0.05">HH</text>
+    </svg>"#;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    // The text starts at a fractional position...
+    assert!(contains(&pdf, "10.25"));
+
+    // ... and the second glyph is moved by a fraction of a thousandth of the
+    // font size, which isn't rounded away.
+    let adjustments: Vec<f32> = pdf
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| *w == b"-2")
+        .filter_map(|(i, _)| {
+            let len = pdf[i + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit() || **c == b'.')
+                .count();
+            std::str::from_utf8(&pdf[i..i + 1 + len]).ok()?.parse().ok()
+        })
+        .collect();
+    assert!(adjustments.iter().any(|a| (a + 2.5).abs() < 0.01 && a.fract() != 0.0));
+}
+
+#[test]
+fn raster_fallback() {
+    // The fallback is rendered at a tenth of the resolution, so the stripe
+    // bleeds into its surroundings in the raster layer, but not in the vector
+    // layer.
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <rect x="45" width="10" height="100" fill="green"/>
+    </svg>"#;
+
+    let options = ConversionOptions {
+        compress: false,
+        raster_fallback: true,
+        raster_scale: 0.1,
+        ..ConversionOptions::default()
+    };
+    let pdf = convert_str(svg, options.clone(), PageOptions::default()).unwrap();
+
+    let count = |needle: &str| {
+        pdf.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+    };
+    assert_eq!(count("/Type /OCG"), 2);
+    assert!(contains(&pdf, "/Name (Vector)"));
+    assert!(contains(&pdf, "/Name (Raster)"));
+
+    // The vector content is drawn directly, while the fallback is an image.
+    assert!(contains(&pdf, "/OC /oc0 BDC\n"));
+    assert!(contains(&pdf, "/OC /oc1 BDC\n"));
+    assert!(contains(&pdf, " m\n"));
+    assert!(contains(&pdf, "/Subtype /Image"));
+
+    // Only one of the layers is visible at a time, starting with the vectors.
+    assert!(contains(&pdf, "/OFF ["));
+    assert!(contains(&pdf, "/RBGroups [["));
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(50, 50).0, [0, 128, 0, 255]);
+    assert_eq!(image.get_pixel(42, 50).0[3], 0);
+
+    // The same layers are available when rendering into a shared context.
+    let mut ctx = Context::new(options);
+    let mut chunk = Chunk::new();
+    ctx.render_xobject(&read_svg(svg), &mut chunk).unwrap();
+    let [vector, raster] = ctx.layers() else { panic!("expected two layers") };
+    assert_eq!(ctx.hidden_layers(), [*raster]);
+    assert_eq!(ctx.exclusive_layers(), [vec![*vector, *raster]]);
+}
+
+#[test]
+fn clip_property() {
+    let convert = |svg: &str| {
+        let converter = svg2pdf::Converter::with_fontdb(FONTDB.clone());
+        let pdf = converter
+            .convert_data(
+                svg.as_bytes(),
+                &PreprocessOptions::default(),
+                ConversionOptions::default(),
+                PageOptions::default(),
+            )
+            .unwrap()
+            .pdf;
+        ink_bounds(&render_pdf(&pdf))
+    };
+
+    let nested = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+        <svg x="100" width="100" height="100" style="clip: rect(0 50 50 0)">
+            <rect width="100" height="100"/>
+        </svg>
+    </svg>"#;
+    assert_eq!(convert(nested), Some((100, 0, 149, 49)));
+
+    // The clip of the root is relative to its viewport, not its view box.
+    let root = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"
+                       viewBox="0 0 10 10" clip="rect(10, auto, auto, 20)">
+        <rect width="10" height="10"/>
+    </svg>"#;
+    assert_eq!(convert(root), Some((20, 10, 99, 99)));
+}
+
+#[test]
+fn linearize() {
+    let trees: Vec<_> = ["one", "two", "three"]
+        .iter()
+        .map(|text| {
+            read_svg(&format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50">
+                    <text x="10" y="30" font-family="Noto Sans">Page {text}</text>
+                </svg>"#
+            ))
+        })
+        .collect();
+
+    let options = ConversionOptions { linearize: true, ..ConversionOptions::default() };
+    let pdf = svg2pdf::to_multipage_pdf(&trees, options, PageOptions::default()).unwrap();
+    assert_eq!(page_count(&pdf), 3);
+
+    let find = |needle: &str| {
+        pdf.windows(needle.len())
+            .position(|w| w == needle.as_bytes())
+            .unwrap()
+    };
+    let number = |at: usize| {
+        pdf[at..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .fold(0, |n, b| n * 10 + (b - b'0') as usize)
+    };
+    let value = |key: &str| number(find(key) + key.len());
+    let at = |offset: usize, text: &str| pdf[offset..].starts_with(text.as_bytes());
+
+    // The linearization dictionary is the first object in the file.
+    assert!(at(find(" obj"), " obj\n<< /Linearized 1 "));
+    assert_eq!(value("/L "), pdf.len());
+    assert_eq!(value("/N "), 3);
+    assert!(at(value("/T "), "\n0000000000 65535 f"));
+
+    // The hint stream is where the dictionary says.
+    let hint = value("/H [");
+    let hint_dict = &pdf[hint..hint + 64];
+    assert!(hint_dict.windows(3).any(|w| w == b"/S "));
+
+    // The trailer at the end points to the first page cross-reference table,
+    // which points to the main one. Both point to the objects with the right
+    // numbers.
+    let start_xref = pdf.windows(10).rposition(|w| w == b"startxref\n").unwrap();
+    let start_xref = number(start_xref + 10);
+    for xref in [start_xref, value("/Prev ")] {
+        assert!(at(xref, "xref\n"));
+        let first = number(xref + 5);
+        let count = number(xref + 5 + first.to_string().len() + 1);
+        let entries = xref + 5 + format!("{first} {count}\n").len();
+        for (i, id) in (first..first + count).enumerate() {
+            if id > 0 {
+                let offset = number(entries + 20 * i);
+                assert!(at(offset, &format!("{id} 0 obj\n")));
+            }
+        }
+    }
+}
+
+#[test]
+fn gradient_stop_opacity() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="50">
+        <linearGradient id="gradient">
+            <stop offset="0" stop-color="blue"/>
+            <stop offset="1" stop-color="blue" stop-opacity="0"/>
+        </linearGradient>
+        <rect width="200" height="50" fill="url(#gradient)"/>
+    </svg>"#;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+    assert!(contains(&pdf, "/SMask"));
+    assert!(contains(&pdf, "/S /Luminosity"));
+
+    // The gradient fades out from left to right.
+    let image = render_pdf(&pdf);
+    let alpha = |x: u32| image.get_pixel(x, 25).0[3];
+    assert!(alpha(2) > 240);
+    assert!(alpha(100).abs_diff(128) <= 16);
+    assert!(alpha(197) < 16);
+    let [r, g, b, _] = image.get_pixel(2, 25).0;
+    assert!(r < 16 && g < 16 && b > 240);
+}
+
+#[test]
+fn mask_clip_path_and_opacity() {
+    // The second clip path mixes clip rules, so it is drawn as a soft mask.
+    let clip_paths = [
+        r#"<clipPath id="clip"><rect width="50" height="100"/></clipPath>"#,
+        r#"<clipPath id="clip"><rect width="50" height="100" clip-rule="evenodd"/>
+            <rect width="1" height="1"/></clipPath>"#,
+    ];
+
+    for clip_path in clip_paths {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+                {clip_path}
+                <mask id="mask" maskUnits="userSpaceOnUse" x="0" y="0" width="100" height="100">
+                    <rect width="100" height="50" fill="white"/>
+                </mask>
+                <g clip-path="url(#clip)" mask="url(#mask)" opacity="0.5">
+                    <rect width="100" height="100" fill="blue"/>
+                </g>
+            </svg>"#
+        );
+
+        let pdf = convert_str(&svg, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+        let image = render_pdf(&pdf);
+
+        // Only the quadrant that is both clipped in and masked in is drawn, at
+        // half opacity.
+        let [r, g, b, a] = image.get_pixel(25, 25).0;
+        assert!(r < 16 && g < 16 && b > 240);
+        assert!(a.abs_diff(128) <= 8);
+        for (x, y) in [(75, 25), (25, 75), (75, 75)] {
+            assert_eq!(image.get_pixel(x, y).0[3], 0);
+        }
+    }
+}
+
+#[test]
+fn no_compression() {
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <path d="M 10 10 L 90 10 L 90 50 Z" fill="#ff0000" opacity="0.5"/>
+        <image x="10" y="60" width="40" height="20" href="data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAABQAAAAKCAIAAAA7N+mxAAAAGElEQVR42mP4z8CAB+GV/M8wqnlUMyHNAGn9xzn3KHifAAAAAElFTkSuQmCC"/>
+        <text x="60" y="80" font-family="Noto Sans" font-size="20">A</text>
+    </svg>"##;
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let pdf = convert_str(svg, options, PageOptions::default()).unwrap();
+
+    // Neither content streams nor images, fonts and ICC profiles are
+    // compressed, and nothing is hex-encoded either.
+    assert!(contains(&pdf, "10 10 m"));
+    assert!(contains(&pdf, "/FontFile2"));
+    assert!(contains(&pdf, "/Subtype /Image"));
+    assert!(!contains(&pdf, "/Filter"));
+
+    let image = render_pdf(&pdf);
+    assert_eq!(image.get_pixel(80, 20).0[..3], [255, 0, 0]);
+    assert_eq!(image.get_pixel(15, 70).0[3], 255);
+}
+
+#[test]
+fn multipage_range() {
+    let trees: Vec<_> = (1..=5)
+        .map(|width| {
+            read_svg(&format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="50">
+                    <rect width="10" height="10"/>
+                </svg>"#,
+                width * 10
+            ))
+        })
+        .collect();
+
+    let options = ConversionOptions { compress: false, ..ConversionOptions::default() };
+    let convert = |pages| {
+        svg2pdf::to_multipage_pdf_range(
+            &trees,
+            pages,
+            options.clone(),
+            PageOptions::default(),
+        )
+    };
+
+    // The second and the third tree are converted, in order.
+    let pdf = convert(1..3).unwrap();
+    assert_eq!(page_count(&pdf), 2);
+    assert!(contains(&pdf, "/Count 2"));
+    let first = pdf.windows(19).position(|w| w == b"/MediaBox [0 0 20 5").unwrap();
+    let second = pdf.windows(19).position(|w| w == b"/MediaBox [0 0 30 5").unwrap();
+    assert!(first < second);
+    assert!(!contains(&pdf, "/MediaBox [0 0 10 5"));
+    assert!(!contains(&pdf, "/MediaBox [0 0 40 5"));
+
+    assert!(matches!(convert(4..6), Err(ConversionError::InvalidPageRange)));
+    assert!(matches!(convert(2..2), Err(ConversionError::InvalidPageRange)));
+}
+
+#[test]
+fn outlined_text_precision() {
+    let convert = |text_rendering: &str| {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="200">
+                <text x="10" y="150" font-family="Noto Sans" font-size="150"
+                      text-rendering="{text_rendering}">OS</text>
+            </svg>"#
+        );
+        let options = ConversionOptions {
+            embed_text: false,
+            compress: false,
+            ..ConversionOptions::default()
+        };
+        convert_str(&svg, options, PageOptions::default()).unwrap()
+    };
+
+    // The outlines are written with their curves instead of being flattened
+    // into lines, so there is no more precise output for `geometricPrecision`.
+    let precise = convert("geometricPrecision");
+    let curves = precise.windows(3).filter(|w| w == b" c\n").count();
+    let lines = precise.windows(3).filter(|w| w == b" l\n").count();
+    assert!(curves > 10);
+    assert!(lines < curves);
+    assert_eq!(convert("optimizeSpeed"), precise);
+}
+
+#[test]
+fn external_use() {
+    let icons = r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="square" width="20" height="20" fill="blue"/>
+        <symbol id="circle" viewBox="0 0 10 10"><circle cx="5" cy="5" r="5"/></symbol>
+    </svg>"#;
+    let dir = std::env::temp_dir().join("svg2pdf-external-use");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("icons.svg"), icons).unwrap();
+    std::fs::write(std::env::temp_dir().join("svg2pdf-outside.svg"), icons).unwrap();
+
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"
+                       width="100" height="100">
+        <use href="icons.svg#square" x="10" y="10"/>
+        <use xlink:href="icons.svg#circle" x="50" y="50" width="40" height="40"/>
+        <use href="missing.svg#square"/>
+        <use href="../svg2pdf-outside.svg#square"/>
+    </svg>"##;
+
+    // References are only resolved with a resources directory.
+    let preprocessed = svg2pdf::preprocess(svg, &PreprocessOptions::default()).unwrap();
+    assert_eq!(preprocessed.svg, svg);
+
+    let options = PreprocessOptions {
+        resource_resolver: Some(ResourceResolver::from_dir(dir)),
+        ..PreprocessOptions::default()
+    };
+    let mut resolved = String::new();
+    let warnings = capture_warnings(|| {
+        resolved = svg2pdf::preprocess(svg, &options).unwrap().svg;
+    });
+
+    // Files outside of the directory are not read.
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("missing.svg#square"));
+    assert!(warnings[1].contains("../svg2pdf-outside.svg#square"));
+
+    let pdf = svg2pdf::to_pdf(
+        &read_svg(&resolved),
+        ConversionOptions::default(),
+        PageOptions::default(),
+    )
+    .unwrap();
+    let image = render_pdf(&pdf);
+    assert_eq!(ink_bounds(&image), Some((10, 10, 89, 89)));
+    assert_eq!(image.get_pixel(20, 20).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(70, 70).0, [0, 0, 0, 255]);
+    // The symbol is scaled into the size of the `use` element.
+    assert_eq!(image.get_pixel(52, 52).0[3], 0);
+}
+
+#[test]
+fn external_use_copies() {
+    let badge = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+        <g id="badge">
+            <linearGradient id="fill"><stop stop-color="blue"/></linearGradient>
+            <rect id="shape" width="20" height="20" fill="url(#fill)"/>
+            <use xlink:href="#shape" x="20"/>
+        </g>
+    </svg>"##;
+
+    // The document doesn't bind the `xlink` prefix that the copies use.
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <use href="badge.svg#badge"/>
+        <use href="badge.svg#badge" y="50"/>
+    </svg>"#;
+
+    let resolver = ResourceResolver::new(move |path| {
+        (path == "badge.svg").then(|| badge.to_string())
+    });
+    let options = PreprocessOptions {
+        resource_resolver: Some(resolver),
+        ..PreprocessOptions::default()
+    };
+    let preprocessed = svg2pdf::preprocess(svg, &options).unwrap();
+    assert!(preprocessed.warnings.is_empty());
+
+    // Each copy has ids of its own, which its references point to.
+    let resolved = preprocessed.svg;
+    for i in 0..2 {
+        assert!(resolved.contains(&format!(r#"id="shape-use{i}""#)));
+        assert!(resolved.contains(&format!(r##"xlink:href="#shape-use{i}""##)));
+        assert!(resolved.contains(&format!("url(#fill-use{i})")));
+    }
+
+    let pdf = svg2pdf::to_pdf(
+        &read_svg(&resolved),
+        ConversionOptions::default(),
+        PageOptions::default(),
+    )
+    .unwrap();
+    let image = render_pdf(&pdf);
+    assert_eq!(ink_bounds(&image), Some((0, 0, 39, 69)));
+    assert_eq!(image.get_pixel(30, 60).0, [0, 0, 255, 255]);
+}
+
+#[cfg(unix)]
+#[test]
+fn external_use_symlink() {
+    let icons = r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="square" width="20" height="20"/>
+    </svg>"#;
+    let dir = std::env::temp_dir().join("svg2pdf-external-use-symlink");
+    std::fs::create_dir_all(&dir).unwrap();
+    let outside = std::env::temp_dir().join("svg2pdf-symlink-target.svg");
+    std::fs::write(&outside, icons).unwrap();
+    let link = dir.join("icons.svg");
+    let _ = std::fs::remove_file(&link);
+    std::os::unix::fs::symlink(&outside, &link).unwrap();
+
+    // The link is inside of the directory, but the file it leads to isn't.
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+        <use href="icons.svg#square"/>
+    </svg>"#;
+    let options = PreprocessOptions {
+        resource_resolver: Some(ResourceResolver::from_dir(dir)),
+        ..PreprocessOptions::default()
+    };
+    let preprocessed = svg2pdf::preprocess(svg, &options).unwrap();
+    assert_eq!(preprocessed.svg, svg);
+    assert_eq!(preprocessed.warnings.len(), 1);
+}
+
+#[test]
+fn percentage_stroke_width() {
+    let convert = |stroke_width: &str| {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="300" height="400">
+                <path d="M 50 200 L 250 200" stroke="black" stroke-width="{stroke_width}"/>
+            </svg>"#
+        );
+        let pdf = convert_str(&svg, ConversionOptions::default(), PageOptions::default())
+            .unwrap();
+        ink_bounds(&render_pdf(&pdf))
+    };
+
+    // Percentages refer to the diagonal of the viewport divided by the square
+    // root of two, which is 500 / sqrt(2) here. So the width is about 35.36
+    // and not 10 or 30.
+    let bounds = convert("10%");
+    assert_eq!(bounds, Some((50, 182, 249, 217)));
+    assert_eq!(bounds, convert("35.3553"));
+}
@@ -5,7 +5,8 @@ mod api;
 use std::cmp::max;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
 
 use image::io::Reader;
 use image::{Rgba, RgbaImage};
@@ -15,7 +16,7 @@ use pdfium_render::pdfium::Pdfium;
 use pdfium_render::prelude::{PdfColor, PdfRenderConfig};
 use usvg::Tree;
 
-use svg2pdf::{ConversionOptions, PageOptions};
+use svg2pdf::{ConversionError, ConversionOptions, PageOptions};
 
 static FONTDB: Lazy<Arc<fontdb::Database>> = Lazy::new(|| {
     let mut fontdb = fontdb::Database::new();
@@ -62,6 +63,55 @@ pub fn render_pdf(pdf: &[u8]) -> RgbaImage {
     result
 }
 
+/// Returns the number of pages of a PDF.
+pub fn page_count(pdf: &[u8]) -> usize {
+    let pdfium = PDFIUM.lock().unwrap();
+    let document = pdfium.load_pdf_from_byte_slice(pdf, None).unwrap();
+    let count = document.pages().len() as usize;
+    count
+}
+
+/// The warnings that were logged, along with the thread that logged them.
+static WARNINGS: Mutex<Vec<(ThreadId, String)>> = Mutex::new(Vec::new());
+
+/// A logger that records warnings, so that tests can check them.
+struct WarningLogger;
+
+impl log::Log for WarningLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Warn
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let warning = (thread::current().id(), record.args().to_string());
+            WARNINGS.lock().unwrap().push(warning);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Runs a function and returns the warnings it logged. Since tests run in
+/// parallel, only the warnings of the current thread are returned.
+pub fn capture_warnings(f: impl FnOnce()) -> Vec<String> {
+    static LOGGER: WarningLogger = WarningLogger;
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(log::LevelFilter::Warn);
+    }
+
+    let id = thread::current().id();
+    WARNINGS.lock().unwrap().retain(|(thread, _)| *thread != id);
+    f();
+
+    let mut warnings = WARNINGS.lock().unwrap();
+    let (own, other): (Vec<_>, Vec<_>) = std::mem::take(&mut *warnings)
+        .into_iter()
+        .partition(|(thread, _)| *thread == id);
+    *warnings = other;
+    own.into_iter().map(|(_, warning)| warning).collect()
+}
+
 /// Converts an SVG string into a usvg Tree
 pub fn read_svg(svg_string: &str) -> Tree {
     let options = usvg::Options { fontdb: FONTDB.clone(), ..usvg::Options::default() };
@@ -82,6 +132,34 @@ pub fn convert_svg(
     (pdf, image)
 }
 
+/// Converts an SVG string into a PDF without rendering it, so that the
+/// output can be inspected directly.
+pub fn convert_str(
+    svg: &str,
+    conversion_options: ConversionOptions,
+    page_options: PageOptions,
+) -> Result<Vec<u8>, ConversionError> {
+    let tree = read_svg(svg);
+    svg2pdf::to_pdf(&tree, conversion_options, page_options)
+}
+
+/// Checks whether some PDF output contains a string.
+pub fn contains(pdf: &[u8], needle: &str) -> bool {
+    pdf.windows(needle.len()).any(|w| w == needle.as_bytes())
+}
+
+/// Returns the bounding box of all non-transparent pixels of an image as
+/// `(x_min, y_min, x_max, y_max)`.
+pub fn ink_bounds(image: &RgbaImage) -> Option<(u32, u32, u32, u32)> {
+    image.enumerate_pixels().filter(|(_, _, p)| p.0[3] > 128).fold(
+        None,
+        |bounds, (x, y, _)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((x0, y0, x1, y1)) => Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y))),
+        },
+    )
+}
+
 /// Saves an RGBA image to a path.
 pub fn save_image(image: &RgbaImage, path: &Path) {
     image.save_with_format(path, image::ImageFormat::Png).unwrap();